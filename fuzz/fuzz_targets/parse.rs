@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use peparser::PE;
+
+// PE::parse must return an error rather than panic on any input
+fuzz_target!(|data: &[u8]| {
+    if let Ok((_, pe)) = PE::parse(data) {
+        let _ = pe.to_string();
+    }
+});
@@ -0,0 +1,57 @@
+use crate::parse;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::fmt::Formatter;
+
+/// The byte range of a parsed field, labelled like the context of its parser, e.g. `AddressOfEntryPoint`.
+/// Collected in `PE::annotations` when `ParseOptions::annotations` is set, e.g. to highlight the fields in a hex viewer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSpan {
+    pub name: &'static str,
+    pub offset: usize, // the file offset of the first byte
+    pub len: usize,
+}
+
+/// Collects the spans of the fields parsed from a file
+#[derive(Debug, Default)]
+pub(crate) struct Annotations {
+    file_start: Cell<usize>, // the address of the first byte of the file
+    file_len: Cell<usize>,
+    spans: RefCell<Vec<FieldSpan>>,
+}
+
+impl Annotations {
+    /// Sets the file the offsets of the spans are relative to
+    pub(crate) fn set_file(&self, file: parse::Input) {
+        self.file_start.set(file.as_ptr() as usize);
+        self.file_len.set(file.len());
+    }
+
+    /// Records the span of a field read at the start of `input`.
+    /// Fields read from another buffer than the file are not recorded.
+    pub(crate) fn record(&self, name: &'static str, input: parse::Input, len: usize) {
+        let offset = match (input.as_ptr() as usize).checked_sub(self.file_start.get()) {
+            Some(offset) if offset.saturating_add(len) <= self.file_len.get() => offset,
+            _ => return,
+        };
+        self.spans
+            .borrow_mut()
+            .push(FieldSpan { name, offset, len });
+    }
+
+    pub(crate) fn take(&self) -> Vec<FieldSpan> {
+        self.spans.take()
+    }
+}
+
+impl fmt::Display for FieldSpan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:#x}..{:#x}: {}",
+            self.offset,
+            self.offset + self.len,
+            self.name
+        )
+    }
+}
@@ -0,0 +1,100 @@
+use crate::headers::sections::{Section, SectionName};
+use std::fmt;
+use std::fmt::Formatter;
+
+/// A section whose permissions contradict its conventional name, see `PE::characteristic_anomalies`.
+/// Packers and injected code often leave these, which is a stronger signal than a section being
+/// both writable and executable since compilers never emit them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Anomaly {
+    WritableCode { section: String },        // a writable .text section
+    ExecutableData { section: String },      // an executable .data or .rdata section
+    ExecutableResources { section: String }, // an executable .rsrc section
+}
+
+impl Anomaly {
+    /// The name of the section the anomaly was found in
+    pub fn section(&self) -> &str {
+        match self {
+            Self::WritableCode { section }
+            | Self::ExecutableData { section }
+            | Self::ExecutableResources { section } => section,
+        }
+    }
+
+    /// The anomaly of a section with a well-known name, None for the other names
+    pub(crate) fn of_section(section: &Section) -> Option<Self> {
+        let name = section.name.clone();
+        let is_named = |known: SectionName| section.name == known.as_str();
+        if is_named(SectionName::Text) && section.is_writable() {
+            Some(Self::WritableCode { section: name })
+        } else if (is_named(SectionName::Data) || is_named(SectionName::Rdata))
+            && section.is_executable()
+        {
+            Some(Self::ExecutableData { section: name })
+        } else if is_named(SectionName::Rsrc) && section.is_executable() {
+            Some(Self::ExecutableResources { section: name })
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WritableCode { section } => write!(f, "writable code section {}", section),
+            Self::ExecutableData { section } => write!(f, "executable data section {}", section),
+            Self::ExecutableResources { section } => {
+                write!(f, "executable resource section {}", section)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Anomaly;
+    use crate::headers::sections::{
+        Section, IMAGE_SCN_CNT_CODE, IMAGE_SCN_CNT_INITIALIZED_DATA, IMAGE_SCN_MEM_EXECUTE,
+        IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE,
+    };
+
+    fn section(name: &str, characteristics: u32) -> Section {
+        Section::for_test(name, 0x1000, 0x1000, 0x400, 0x200, characteristics)
+    }
+
+    #[test]
+    fn name_permission_mismatches() {
+        let code = IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_MEM_READ;
+        let data = IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ;
+        assert!(Anomaly::of_section(&section(".text", code)).is_none());
+        assert!(Anomaly::of_section(&section(".data", data | IMAGE_SCN_MEM_WRITE)).is_none());
+        assert!(Anomaly::of_section(&section("UPX0", code | IMAGE_SCN_MEM_WRITE)).is_none());
+
+        let anomalies = [
+            (".text", code | IMAGE_SCN_MEM_WRITE),
+            (".rdata", data | IMAGE_SCN_MEM_EXECUTE),
+            (".rsrc", data | IMAGE_SCN_MEM_EXECUTE),
+        ]
+        .into_iter()
+        .flat_map(|(name, characteristics)| Anomaly::of_section(&section(name, characteristics)))
+        .collect::<Vec<_>>();
+        assert_eq!(
+            anomalies,
+            vec![
+                Anomaly::WritableCode {
+                    section: ".text".to_string()
+                },
+                Anomaly::ExecutableData {
+                    section: ".rdata".to_string()
+                },
+                Anomaly::ExecutableResources {
+                    section: ".rsrc".to_string()
+                },
+            ]
+        );
+        assert_eq!(anomalies[1].section(), ".rdata");
+        assert_eq!(anomalies[0].to_string(), "writable code section .text");
+    }
+}
@@ -0,0 +1,64 @@
+use derive_more::Display;
+
+/// The toolchain which most likely built an image, see `PE::build_origin`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum BuildOrigin {
+    Msvc,
+    MinGw,
+    Clang,
+    Unknown,
+}
+
+impl BuildOrigin {
+    /// Classifies the toolchain from signals which no toolchain sets on purpose:
+    /// * the Microsoft linker writes a Rich header into the DOS stub, and versions itself 5.0 and later
+    /// * GNU ld (MinGW/GCC) writes no Rich header and versions itself 2.x after binutils
+    /// * lld-link (Clang) writes no Rich header but mimics the Microsoft linker version 14.x,
+    ///   and emits a CodeView debug entry when building with debug information
+    ///
+    /// Conflicting signals, e.g. a Rich header with a GNU linker version, result in `Unknown`,
+    /// as do stripped or otherwise tampered headers which match none of the above.
+    pub fn classify(has_rich_header: bool, linker_version: (u8, u8), has_codeview: bool) -> Self {
+        let (major, _) = linker_version;
+        match (has_rich_header, major) {
+            (true, 5..) => Self::Msvc,
+            (true, _) => Self::Unknown,
+            (false, 2) => Self::MinGw,
+            (false, 14..) if has_codeview => Self::Clang,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BuildOrigin;
+
+    #[test]
+    fn classify() {
+        assert_eq!(
+            BuildOrigin::classify(true, (14, 36), true),
+            BuildOrigin::Msvc
+        );
+        assert_eq!(
+            BuildOrigin::classify(true, (2, 38), false),
+            BuildOrigin::Unknown
+        );
+        assert_eq!(
+            BuildOrigin::classify(false, (2, 38), false),
+            BuildOrigin::MinGw
+        );
+        assert_eq!(
+            BuildOrigin::classify(false, (14, 0), true),
+            BuildOrigin::Clang
+        );
+        assert_eq!(
+            BuildOrigin::classify(false, (14, 0), false),
+            BuildOrigin::Unknown
+        );
+        assert_eq!(
+            BuildOrigin::classify(false, (6, 0), false),
+            BuildOrigin::Unknown
+        );
+    }
+}
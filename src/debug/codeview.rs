@@ -0,0 +1,176 @@
+use crate::{errors, parse, utils};
+use nom::bytes::complete::take;
+use nom::error::context;
+use nom::number::complete::le_u32;
+use nom::sequence::tuple;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// The CodeView debug data identifying the PDB file of the image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodeView {
+    /// "RSDS", written by VC++ 7.0 and later
+    Pdb70 {
+        guid: [u8; 16],
+        age: u32,
+        pdb_file_name: String,
+    },
+    /// "NB10", written by VC++ 6.0 and earlier
+    Pdb20 {
+        offset: u32,
+        signature: u32,
+        age: u32,
+        pdb_file_name: String,
+    },
+}
+
+impl CodeView {
+    pub fn parse(i: parse::Input) -> parse::Result<Self> {
+        let (i, magic) = context("CvSignature", take(4usize))(i)?;
+        match magic {
+            b"RSDS" => {
+                let (i, (guid, age)) =
+                    tuple((context("Signature", take(16usize)), context("Age", le_u32)))(i)?;
+                let mut guid_bytes = [0u8; 16];
+                guid_bytes.copy_from_slice(guid);
+                Ok((
+                    i,
+                    Self::Pdb70 {
+                        guid: guid_bytes,
+                        age,
+                        pdb_file_name: utils::read_null_terminated_string(i),
+                    },
+                ))
+            }
+            b"NB10" => {
+                let (i, (offset, signature, age)) = tuple((
+                    context("Offset", le_u32),
+                    context("Signature", le_u32),
+                    context("Age", le_u32),
+                ))(i)?;
+                Ok((
+                    i,
+                    Self::Pdb20 {
+                        offset,
+                        signature,
+                        age,
+                        pdb_file_name: utils::read_null_terminated_string(i),
+                    },
+                ))
+            }
+            _ => Err(errors::PEError::from_string(
+                i,
+                format!("unknown CodeView signature {:x?}", magic),
+            )),
+        }
+    }
+
+    /// The path of the PDB file as recorded by the linker, often an absolute path on the build machine
+    pub fn pdb_file_name(&self) -> &str {
+        match self {
+            Self::Pdb70 { pdb_file_name, .. } | Self::Pdb20 { pdb_file_name, .. } => pdb_file_name,
+        }
+    }
+
+    /// The GUID in its registry format, e.g. `3F2504E0-4F89-11D3-9A0C-0305E82C3301`,
+    /// or None for PDB 2.0 files which have no GUID
+    pub fn guid(&self) -> Option<String> {
+        match self {
+            Self::Pdb70 { guid, .. } => Some(format!(
+                "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{}",
+                Self::guid_data1(guid),
+                Self::guid_data2(guid),
+                Self::guid_data3(guid),
+                guid[8],
+                guid[9],
+                Self::hex(&guid[10..])
+            )),
+            Self::Pdb20 { .. } => None,
+        }
+    }
+
+    /// The path of the PDB file on a symbol server, `<pdbname>/<id><age>/<pdbname>`.
+    /// The id is the GUID whose first three fields are little-endian, or the signature for PDB 2.0 files.
+    pub fn symbol_path(&self) -> String {
+        // the name is a path on the build machine, only the file name is used by symbol servers
+        let pdb_file_name = self
+            .pdb_file_name()
+            .rsplit(['\\', '/'])
+            .next()
+            .unwrap_or_default();
+        let id = match self {
+            Self::Pdb70 { guid, age, .. } => format!(
+                "{:08X}{:04X}{:04X}{}{:X}",
+                Self::guid_data1(guid),
+                Self::guid_data2(guid),
+                Self::guid_data3(guid),
+                Self::hex(&guid[8..]),
+                age
+            ),
+            Self::Pdb20 { signature, age, .. } => format!("{:08X}{:X}", signature, age),
+        };
+        format!("{}/{}/{}", pdb_file_name, id, pdb_file_name)
+    }
+
+    fn guid_data1(guid: &[u8; 16]) -> u32 {
+        u32::from_le_bytes([guid[0], guid[1], guid[2], guid[3]])
+    }
+
+    fn guid_data2(guid: &[u8; 16]) -> u16 {
+        u16::from_le_bytes([guid[4], guid[5]])
+    }
+
+    fn guid_data3(guid: &[u8; 16]) -> u16 {
+        u16::from_le_bytes([guid[6], guid[7]])
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+    }
+}
+
+impl fmt::Display for CodeView {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pdb70 { age, .. } => write!(
+                f,
+                "PDB: {}, GUID: {}, Age: {}",
+                self.pdb_file_name(),
+                self.guid().unwrap_or_default(),
+                age
+            ),
+            Self::Pdb20 { signature, age, .. } => write!(
+                f,
+                "PDB: {}, Signature: {:#x}, Age: {}",
+                self.pdb_file_name(),
+                signature,
+                age
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CodeView;
+
+    #[test]
+    fn symbol_path() {
+        let mut data = b"RSDS".to_vec();
+        data.extend_from_slice(&[
+            0xe0, 0x04, 0x25, 0x3f, 0x89, 0x4f, 0xd3, 0x11, 0x9a, 0x0c, 0x03, 0x05, 0xe8, 0x2c,
+            0x33, 0x01,
+        ]);
+        data.extend_from_slice(&0x1au32.to_le_bytes());
+        data.extend_from_slice(b"C:\\build\\foo.pdb\0");
+        let (_, codeview) = CodeView::parse(&data).unwrap();
+        assert_eq!(
+            codeview.guid().unwrap(),
+            "3F2504E0-4F89-11D3-9A0C-0305E82C3301"
+        );
+        assert_eq!(
+            codeview.symbol_path(),
+            "foo.pdb/3F2504E04F8911D39A0C0305E82C33011A/foo.pdb"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use crate::debug::codeview::CodeView;
+use crate::parse;
+use nom::error::context;
+use nom::number::complete::{le_u16, le_u32};
+use nom::sequence::tuple;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// The data of a debug directory entry, decoded for the types known to the parser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugData<'a> {
+    CodeView(CodeView),
+    VcFeature(VcFeature),
+    Fpo(Vec<FpoData>),
+    Raw(&'a [u8]),
+}
+
+/// The counts of functions compiled with each hardening feature, recorded by the linker of VC++ 11 and later
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VcFeature {
+    pub pre_vc11: u32, // objects compiled by older compilers
+    pub c_cpp: u32,    // objects compiled by VC++ 11 and later
+    pub gs: u32,       // functions with stack cookies (/GS)
+    pub sdl: u32,      // functions compiled with the additional security checks (/sdl)
+    pub guard_n: u32,  // functions instrumented for control flow guard (/guard:cf)
+}
+
+impl VcFeature {
+    pub fn parse(i: parse::Input) -> parse::Result<Self> {
+        let (i, (pre_vc11, c_cpp, gs, sdl, guard_n)) = tuple((
+            context("PreVC11", le_u32),
+            context("C/C++", le_u32),
+            context("/GS", le_u32),
+            context("/sdl", le_u32),
+            context("guardN", le_u32),
+        ))(i)?;
+        Ok((
+            i,
+            Self {
+                pre_vc11,
+                c_cpp,
+                gs,
+                sdl,
+                guard_n,
+            },
+        ))
+    }
+}
+
+/// An FPO_DATA record describing the stack frame of a function compiled with frame pointer omission
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FpoData {
+    pub off_start: u32,   // offset of the first byte of the function code
+    pub proc_size: u32,   // number of bytes of the function
+    pub locals: u32,      // number of local variables in dwords
+    pub params: u16,      // size of the parameters in dwords
+    pub prolog: u8,       // number of bytes of the prolog
+    pub regs: u8,         // number of saved registers
+    pub has_seh: bool,    // whether the function uses structured exception handling
+    pub use_bp: bool,     // whether EBP has been allocated
+    pub frame: FrameType, // the type of the frame
+}
+
+impl FpoData {
+    pub const SIZE: usize = 16;
+
+    pub fn parse(i: parse::Input) -> parse::Result<Self> {
+        let (i, (off_start, proc_size, locals, params, attributes)) = tuple((
+            context("ulOffStart", le_u32),
+            context("cbProcSize", le_u32),
+            context("cdwLocals", le_u32),
+            context("cdwParams", le_u16),
+            context("Attributes", le_u16),
+        ))(i)?;
+        Ok((
+            i,
+            Self {
+                off_start,
+                proc_size,
+                locals,
+                params,
+                prolog: (attributes & 0xff) as u8,
+                regs: ((attributes >> 8) & 0x7) as u8,
+                has_seh: attributes & (1 << 11) != 0,
+                use_bp: attributes & (1 << 12) != 0,
+                frame: FrameType::from_bits(attributes >> 14),
+            },
+        ))
+    }
+
+    /// Parses the records filling the data, ignoring a truncated trailing record
+    pub fn parse_array(data: &[u8]) -> Vec<Self> {
+        data.chunks_exact(Self::SIZE)
+            .filter_map(|record| Self::parse(record).ok())
+            .map(|(_, fpo_data)| fpo_data)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameType {
+    Fpo,
+    Trap,
+    Tss,
+    NonFpo,
+}
+
+impl FrameType {
+    fn from_bits(bits: u16) -> Self {
+        match bits & 0x3 {
+            0 => Self::Fpo,
+            1 => Self::Trap,
+            2 => Self::Tss,
+            _ => Self::NonFpo,
+        }
+    }
+}
+
+impl fmt::Display for VcFeature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Pre-VC++ 11.00: {}, C/C++: {}, /GS: {}, /sdl: {}, guardN: {}",
+            self.pre_vc11, self.c_cpp, self.gs, self.sdl, self.guard_n
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FpoData, FrameType};
+
+    #[test]
+    fn fpo_attributes() {
+        let mut data = vec![];
+        data.extend_from_slice(&0x1000u32.to_le_bytes());
+        data.extend_from_slice(&0x40u32.to_le_bytes());
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&3u16.to_le_bytes());
+        // prolog 6, 3 registers, SEH, EBP allocated, non-FPO frame
+        data.extend_from_slice(&(6u16 | 3 << 8 | 1 << 11 | 1 << 12 | 3 << 14).to_le_bytes());
+        data.extend_from_slice(&[0; 8]); // truncated record
+        let records = FpoData::parse_array(&data);
+        assert_eq!(records.len(), 1);
+        let record = records[0];
+        assert_eq!((record.off_start, record.proc_size), (0x1000, 0x40));
+        assert_eq!((record.locals, record.params), (2, 3));
+        assert_eq!((record.prolog, record.regs), (6, 3));
+        assert!(record.has_seh && record.use_bp);
+        assert_eq!(record.frame, FrameType::NonFpo);
+    }
+}
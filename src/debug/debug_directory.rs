@@ -0,0 +1,233 @@
+use crate::debug::codeview::CodeView;
+use crate::debug::debug_data::{DebugData, FpoData, VcFeature};
+use crate::headers::nt::DataDirectory;
+use crate::headers::sections::Sections;
+use crate::options::ParseContext;
+use crate::{parse, utils};
+use derive_more::Display;
+use derive_try_from_primitive::TryFromPrimitive;
+use nom::error::context;
+use nom::number::complete::{le_u16, le_u32};
+use nom::sequence::tuple;
+use std::fmt;
+use std::fmt::Formatter;
+
+pub const IMAGE_DLLCHARACTERISTICS_EX_CET_COMPAT: u32 = 0x01;
+pub const IMAGE_DLLCHARACTERISTICS_EX_CET_COMPAT_STRICT_MODE: u32 = 0x02;
+
+/// The array of IMAGE_DEBUG_DIRECTORY pointed by the debug data directory
+#[derive(Debug)]
+pub struct DebugDirectory {
+    pub entries: Vec<DebugDirectoryEntry>,
+    // the extended DLL characteristics stored in the data of the EX_DLLCHARACTERISTICS entry
+    pub ex_dll_characteristics: Option<u32>,
+    pub codeview: Option<CodeView>,
+    pub vc_feature: Option<VcFeature>,
+}
+
+impl DebugDirectory {
+    pub fn parse<'a>(
+        pe_file: parse::Input<'a>,
+        debug_directory: DataDirectory,
+        sections: &Sections,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Option<Self>> {
+        sections.check_directory_mapped(pe_file, debug_directory, "debug directory")?;
+        let mut i = match sections
+            .rva_to_offset(debug_directory.virtual_address)
+            .and_then(|offset| pe_file.get(offset as usize..))
+        {
+            Some(i) => i,
+            None => return Ok((pe_file, None)),
+        };
+
+        // the size is capped by the file so that a crafted size doesn't cause a huge allocation
+        let count = debug_directory.size as usize / DebugDirectoryEntry::SIZE;
+        let count = count.min(i.len() / DebugDirectoryEntry::SIZE);
+        ctx.budget().table("debug directory").reserve(i, count)?;
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            let (next, entry) = DebugDirectoryEntry::parse(i)?;
+            entries.push(entry);
+            i = next;
+        }
+
+        let ex_dll_characteristics = entries
+            .iter()
+            .find(|entry| entry.debug_type() == Some(DebugType::ExDllCharacteristics))
+            .and_then(|entry| utils::read_u32_at(pe_file, entry.pointer_to_raw_data as usize));
+        let codeview = entries
+            .iter()
+            .find(|entry| entry.debug_type() == Some(DebugType::CodeView))
+            .and_then(|entry| CodeView::parse(entry.raw_data(pe_file)).ok())
+            .map(|(_, codeview)| codeview);
+        let vc_feature = entries
+            .iter()
+            .find(|entry| entry.debug_type() == Some(DebugType::VcFeature))
+            .and_then(|entry| VcFeature::parse(entry.raw_data(pe_file)).ok())
+            .map(|(_, vc_feature)| vc_feature);
+
+        Ok((
+            i,
+            Some(Self {
+                entries,
+                ex_dll_characteristics,
+                codeview,
+                vc_feature,
+            }),
+        ))
+    }
+
+    pub fn find_by_type(&self, debug_type: DebugType) -> Option<&DebugDirectoryEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.debug_type() == Some(debug_type))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DebugDirectoryEntry {
+    pub characteristics: u32,
+    pub time_date_stamp: u32,
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub typ: u32,
+    pub size_of_data: u32,
+    pub address_of_raw_data: u32, // RVA of the debug data when it is mapped
+    pub pointer_to_raw_data: u32, // file offset of the debug data
+}
+
+impl DebugDirectoryEntry {
+    pub const SIZE: usize = 28;
+
+    fn parse(i: parse::Input) -> parse::Result<Self> {
+        let (
+            i,
+            (
+                characteristics,
+                time_date_stamp,
+                major_version,
+                minor_version,
+                typ,
+                size_of_data,
+                address_of_raw_data,
+                pointer_to_raw_data,
+            ),
+        ) = tuple((
+            context("Characteristics", le_u32),
+            context("TimeDateStamp", le_u32),
+            context("MajorVersion", le_u16),
+            context("MinorVersion", le_u16),
+            context("Type", le_u32),
+            context("SizeOfData", le_u32),
+            context("AddressOfRawData", le_u32),
+            context("PointerToRawData", le_u32),
+        ))(i)?;
+        Ok((
+            i,
+            Self {
+                characteristics,
+                time_date_stamp,
+                major_version,
+                minor_version,
+                typ,
+                size_of_data,
+                address_of_raw_data,
+                pointer_to_raw_data,
+            },
+        ))
+    }
+
+    /// The type of the debug data, or None for types unknown to the parser
+    pub fn debug_type(&self) -> Option<DebugType> {
+        DebugType::try_from(self.typ).ok()
+    }
+
+    /// Decodes the debug data of the CodeView, VC_FEATURE and FPO types.
+    /// The data of other types, or which fails to decode, is returned raw.
+    pub fn data<'a>(&self, pe_file: &'a [u8]) -> DebugData<'a> {
+        let raw_data = self.raw_data(pe_file);
+        let decoded = match self.debug_type() {
+            Some(DebugType::CodeView) => CodeView::parse(raw_data)
+                .ok()
+                .map(|(_, codeview)| DebugData::CodeView(codeview)),
+            Some(DebugType::VcFeature) => VcFeature::parse(raw_data)
+                .ok()
+                .map(|(_, vc_feature)| DebugData::VcFeature(vc_feature)),
+            Some(DebugType::Fpo) => Some(DebugData::Fpo(FpoData::parse_array(raw_data))),
+            _ => None,
+        };
+        decoded.unwrap_or(DebugData::Raw(raw_data))
+    }
+
+    /// The debug data, truncated when the file is shorter than declared
+    pub fn raw_data<'a>(&self, pe_file: &'a [u8]) -> &'a [u8] {
+        let start = (self.pointer_to_raw_data as usize).min(pe_file.len());
+        let end = start
+            .saturating_add(self.size_of_data as usize)
+            .min(pe_file.len());
+        &pe_file[start..end]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, Display)]
+#[repr(u32)]
+pub enum DebugType {
+    Unknown = 0,
+    Coff = 1,
+    CodeView = 2,
+    Fpo = 3,
+    Misc = 4,
+    Exception = 5,
+    Fixup = 6,
+    OmapToSrc = 7,
+    OmapFromSrc = 8,
+    Borland = 9,
+    Reserved10 = 10,
+    Clsid = 11,
+    VcFeature = 12,
+    Pogo = 13,
+    Iltcg = 14,
+    Mpx = 15,
+    Repro = 16,
+    EmbeddedPortablePdb = 17,
+    Spgo = 18,
+    PdbChecksum = 19,
+    ExDllCharacteristics = 20,
+}
+
+impl fmt::Display for DebugDirectory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "DebugDirectory")?;
+        for entry in &self.entries {
+            writeln!(f, "{}", entry)?;
+        }
+        if let Some(ex_dll_characteristics) = self.ex_dll_characteristics {
+            writeln!(f, "  ExDllCharacteristics: {:#x}", ex_dll_characteristics)?;
+        }
+        if let Some(codeview) = &self.codeview {
+            writeln!(f, "  {}", codeview)?;
+        }
+        if let Some(vc_feature) = &self.vc_feature {
+            writeln!(f, "  VcFeature: {}", vc_feature)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DebugDirectoryEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.debug_type() {
+            Some(debug_type) => write!(f, "  Type: {}", debug_type)?,
+            None => write!(f, "  Type: {}", self.typ)?,
+        }
+        write!(
+            f,
+            ", TimeDateStamp: {}, SizeOfData: {}, AddressOfRawData: {:#x}, PointerToRawData: {:#x}",
+            self.time_date_stamp,
+            self.size_of_data,
+            self.address_of_raw_data,
+            self.pointer_to_raw_data
+        )
+    }
+}
@@ -0,0 +1,3 @@
+pub mod codeview;
+pub mod debug_data;
+pub mod debug_directory;
@@ -0,0 +1,165 @@
+use crate::dotnet::metadata::MetadataRoot;
+use crate::dotnet::tables::{self, MetadataTables};
+use std::fmt;
+use std::fmt::Formatter;
+
+/// The identity of a .NET assembly read from the first row of the Assembly metadata table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssemblyInfo {
+    pub name: String,
+    pub version: AssemblyVersion,
+    pub culture: Option<String>,
+    pub public_key_token: Option<[u8; 8]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssemblyVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub build: u16,
+    pub revision: u16,
+}
+
+impl AssemblyInfo {
+    pub fn parse(pe_file: &[u8], metadata: &MetadataRoot) -> Option<Self> {
+        let tables = MetadataTables::parse(metadata.tables_stream(pe_file)?)?;
+        let strings = metadata.stream_data(pe_file, "#Strings").unwrap_or(&[]);
+        let blobs = metadata.stream_data(pe_file, "#Blob").unwrap_or(&[]);
+
+        let row = tables.row(tables::ASSEMBLY, 1)?;
+        let column = |index| tables.column(tables::ASSEMBLY, row, index);
+        let version = AssemblyVersion {
+            major: column(1)? as u16,
+            minor: column(2)? as u16,
+            build: column(3)? as u16,
+            revision: column(4)? as u16,
+        };
+        let public_key_token = tables::read_blob(blobs, column(6)?)
+            .filter(|public_key| !public_key.is_empty())
+            .map(Self::public_key_token);
+        let name = tables::read_string(strings, column(7)?)?;
+        let culture = tables::read_string(strings, column(8)?).filter(|c| !c.is_empty());
+
+        Some(Self {
+            name,
+            version,
+            culture,
+            public_key_token,
+        })
+    }
+
+    /// The public key token is the last 8 bytes of the SHA-1 hash of the public key in reverse order
+    fn public_key_token(public_key: &[u8]) -> [u8; 8] {
+        let hash = sha1(public_key);
+        let mut token = [0u8; 8];
+        for (i, byte) in hash[12..].iter().rev().enumerate() {
+            token[i] = *byte;
+        }
+        token
+    }
+}
+
+/// SHA-1 digest, only used to derive public key tokens
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_be_bytes());
+
+    for chunk in message.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in chunk.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a827999),
+                20..=39 => (b ^ c ^ d, 0x6ed9eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (i, state) in h.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&state.to_be_bytes());
+    }
+    digest
+}
+
+impl fmt::Display for AssemblyVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.major, self.minor, self.build, self.revision
+        )
+    }
+}
+
+impl fmt::Display for AssemblyInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, Version={}, Culture={}, PublicKeyToken=",
+            self.name,
+            self.version,
+            self.culture.as_deref().unwrap_or("neutral")
+        )?;
+        match self.public_key_token {
+            Some(token) => token.iter().try_for_each(|byte| write!(f, "{:02x}", byte)),
+            None => write!(f, "null"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sha1, AssemblyInfo};
+
+    #[test]
+    fn sha1_digest() {
+        let digest = sha1(b"abc");
+        assert_eq!(
+            digest,
+            [
+                0xa9, 0x99, 0x3e, 0x36, 0x47, 0x06, 0x81, 0x6a, 0xba, 0x3e, 0x25, 0x71, 0x78, 0x50,
+                0xc2, 0x6c, 0x9c, 0xd0, 0xd8, 0x9d
+            ]
+        );
+    }
+
+    #[test]
+    fn ecma_public_key_token() {
+        // the ECMA standard public key used by the framework assemblies
+        let ecma_key = [0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(
+            AssemblyInfo::public_key_token(&ecma_key),
+            [0xb7, 0x7a, 0x5c, 0x56, 0x19, 0x34, 0xe0, 0x89]
+        );
+    }
+}
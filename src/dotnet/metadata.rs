@@ -0,0 +1,127 @@
+use crate::{errors, parse, utils};
+use nom::bytes::complete::{tag, take};
+use nom::error::context;
+use nom::number::complete::{le_u16, le_u32};
+use nom::sequence::tuple;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// The metadata root ("BSJB") followed by the stream headers
+#[derive(Debug)]
+pub struct MetadataRoot {
+    pub offset: usize, // file offset of the metadata root, stream offsets are relative to it
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub version: String, // e.g. "v4.0.30319"
+    pub flags: u16,
+    pub streams: Vec<StreamHeader>,
+}
+
+impl MetadataRoot {
+    const SIGNATURE: &'static [u8] = &[0x42, 0x53, 0x4a, 0x42]; // BSJB
+
+    pub fn parse(pe_file: parse::Input, offset: usize) -> parse::Result<Self> {
+        let input = pe_file.get(offset..).ok_or_else(|| {
+            errors::PEError::offset_out_of_bounds(pe_file, offset, pe_file.len(), "metadata root")
+        })?;
+        let (i, (_, major_version, minor_version, _, length)) = tuple((
+            context("Signature", tag(Self::SIGNATURE)),
+            context("MajorVersion", le_u16),
+            context("MinorVersion", le_u16),
+            context("Reserved", le_u32),
+            context("Length", le_u32),
+        ))(input)?;
+        let (i, version) = context("Version", take(length as usize))(i)?;
+        let (i, (flags, num_of_streams)) =
+            tuple((context("Flags", le_u16), context("Streams", le_u16)))(i)?;
+
+        let mut streams = Vec::new();
+        let mut input = i;
+        for _ in 0..num_of_streams {
+            let (new_input, stream) = StreamHeader::parse(input)?;
+            streams.push(stream);
+            input = new_input;
+        }
+
+        Ok((
+            input,
+            Self {
+                offset,
+                major_version,
+                minor_version,
+                version: utils::read_null_terminated_string(version),
+                flags,
+                streams,
+            },
+        ))
+    }
+
+    pub fn find_stream(&self, name: &str) -> Option<&StreamHeader> {
+        self.streams.iter().find(|stream| stream.name == name)
+    }
+
+    /// Returns the data of the stream like `#Strings` or `#~`, or None when the stream is absent or out of file
+    pub fn stream_data<'a>(&self, pe_file: &'a [u8], name: &str) -> Option<&'a [u8]> {
+        let stream = self.find_stream(name)?;
+        let start = self.offset.checked_add(stream.offset as usize)?;
+        pe_file.get(start..start.checked_add(stream.size as usize)?)
+    }
+
+    /// The metadata tables stream, either compressed (`#~`) or uncompressed (`#-`)
+    pub fn tables_stream<'a>(&self, pe_file: &'a [u8]) -> Option<&'a [u8]> {
+        self.stream_data(pe_file, "#~")
+            .or_else(|| self.stream_data(pe_file, "#-"))
+    }
+}
+
+#[derive(Debug)]
+pub struct StreamHeader {
+    pub offset: u32, // relative to the metadata root
+    pub size: u32,
+    pub name: String, // "#~", "#Strings", "#US", "#Blob" or "#GUID"
+}
+
+impl StreamHeader {
+    // the name is at most 32 bytes including the null terminator (ECMA-335 II.24.2.2)
+    const MAX_NAME_LENGTH: usize = 31;
+
+    pub fn parse(i: parse::Input) -> parse::Result<Self> {
+        let (i, (offset, size)) = tuple((context("Offset", le_u32), context("Size", le_u32)))(i)?;
+        let (name, truncated) = utils::read_c_string(i, Self::MAX_NAME_LENGTH);
+        if truncated {
+            return Err(errors::PEError::from_string(
+                i,
+                "stream name is not null-terminated within 32 bytes",
+            ));
+        }
+        // the name is null-terminated and padded to the next 4-byte boundary
+        let name_size = utils::align_up(name.len() as u32 + 1, 4);
+        let (i, _) = context("Name", take(name_size as usize))(i)?;
+        Ok((i, Self { offset, size, name }))
+    }
+}
+
+impl fmt::Display for MetadataRoot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "  MetadataRoot: Version: {}, MajorVersion: {}, MinorVersion: {}",
+            self.version, self.major_version, self.minor_version
+        )?;
+        writeln!(f, "    Streams:")?;
+        for stream in &self.streams {
+            writeln!(f, "{}", stream)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for StreamHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "      name: {}, offset: {:#x}, size: {}",
+            self.name, self.offset, self.size
+        )
+    }
+}
@@ -0,0 +1,288 @@
+use crate::dotnet::metadata::MetadataRoot;
+use crate::dotnet::ready_to_run::ReadyToRunHeader;
+use crate::headers::nt::DataDirectory;
+use crate::headers::sections::Sections;
+use crate::parse;
+use nom::error::context;
+use nom::number::complete::{le_u16, le_u32};
+use nom::sequence::tuple;
+use std::fmt;
+use std::fmt::Formatter;
+
+pub mod assembly;
+pub mod metadata;
+pub mod ready_to_run;
+pub mod tables;
+
+/// The image contains only IL code, which mixed-mode assemblies don't set
+pub const COMIMAGE_FLAGS_ILONLY: u32 = 0x1;
+/// The entry point is the RVA of a native function instead of the token of a managed method
+pub const COMIMAGE_FLAGS_NATIVE_ENTRYPOINT: u32 = 0x10;
+
+/// IMAGE_COR20_HEADER pointed by the CLR runtime data directory of .NET images
+#[derive(Debug)]
+pub struct ClrHeader {
+    pub cb: u32, // size of the header in bytes
+    pub major_runtime_version: u16,
+    pub minor_runtime_version: u16,
+    pub metadata_rva: u32,
+    pub metadata_size: u32,
+    pub flags: u32,
+    pub entry_point_token: u32, // token of the entry point method, or its RVA for native entry points
+    pub resources_rva: u32,
+    pub resources_size: u32,
+    pub strong_name_signature_rva: u32,
+    pub strong_name_signature_size: u32,
+    pub code_manager_table_rva: u32,
+    pub code_manager_table_size: u32,
+    pub vtable_fixups_rva: u32,
+    pub vtable_fixups_size: u32,
+    pub export_address_table_jumps_rva: u32,
+    pub export_address_table_jumps_size: u32,
+    pub managed_native_header_rva: u32,
+    pub managed_native_header_size: u32,
+    pub metadata: Option<MetadataRoot>,
+    pub ready_to_run: Option<ReadyToRunHeader>,
+}
+
+impl ClrHeader {
+    pub fn parse<'a>(
+        pe_file: parse::Input<'a>,
+        clr_directory: DataDirectory,
+        sections: &Sections,
+    ) -> parse::Result<'a, Option<Self>> {
+        sections.check_directory_mapped(pe_file, clr_directory, "CLR header")?;
+        let offset = match sections.rva_to_offset(clr_directory.virtual_address) {
+            Some(offset) if (offset as usize) < pe_file.len() => offset as usize,
+            _ => return Ok((pe_file, None)),
+        };
+
+        let (
+            i,
+            (
+                cb,
+                major_runtime_version,
+                minor_runtime_version,
+                metadata_rva,
+                metadata_size,
+                flags,
+                entry_point_token,
+                resources_rva,
+                resources_size,
+                strong_name_signature_rva,
+                strong_name_signature_size,
+            ),
+        ) = tuple((
+            context("Cb", le_u32),
+            context("MajorRuntimeVersion", le_u16),
+            context("MinorRuntimeVersion", le_u16),
+            context("MetaDataRva", le_u32),
+            context("MetaDataSize", le_u32),
+            context("Flags", le_u32),
+            context("EntryPointToken", le_u32),
+            context("ResourcesRva", le_u32),
+            context("ResourcesSize", le_u32),
+            context("StrongNameSignatureRva", le_u32),
+            context("StrongNameSignatureSize", le_u32),
+        ))(&pe_file[offset..])?;
+
+        let (
+            i,
+            (
+                code_manager_table_rva,
+                code_manager_table_size,
+                vtable_fixups_rva,
+                vtable_fixups_size,
+                export_address_table_jumps_rva,
+                export_address_table_jumps_size,
+                managed_native_header_rva,
+                managed_native_header_size,
+            ),
+        ) = tuple((
+            context("CodeManagerTableRva", le_u32),
+            context("CodeManagerTableSize", le_u32),
+            context("VTableFixupsRva", le_u32),
+            context("VTableFixupsSize", le_u32),
+            context("ExportAddressTableJumpsRva", le_u32),
+            context("ExportAddressTableJumpsSize", le_u32),
+            context("ManagedNativeHeaderRva", le_u32),
+            context("ManagedNativeHeaderSize", le_u32),
+        ))(i)?;
+
+        let metadata = match sections.rva_to_offset(metadata_rva) {
+            Some(metadata_offset) => {
+                let (_, metadata) = MetadataRoot::parse(pe_file, metadata_offset as usize)?;
+                Some(metadata)
+            }
+            None => None,
+        };
+        // the managed native header is also the NGen header of the .NET Framework, which isn't parsed
+        let ready_to_run = match sections.rva_to_offset(managed_native_header_rva) {
+            Some(header_offset) if managed_native_header_rva != 0 => pe_file
+                .get(header_offset as usize..)
+                .and_then(ReadyToRunHeader::parse),
+            _ => None,
+        };
+
+        Ok((
+            i,
+            Some(Self {
+                cb,
+                major_runtime_version,
+                minor_runtime_version,
+                metadata_rva,
+                metadata_size,
+                flags,
+                entry_point_token,
+                resources_rva,
+                resources_size,
+                strong_name_signature_rva,
+                strong_name_signature_size,
+                code_manager_table_rva,
+                code_manager_table_size,
+                vtable_fixups_rva,
+                vtable_fixups_size,
+                export_address_table_jumps_rva,
+                export_address_table_jumps_size,
+                managed_native_header_rva,
+                managed_native_header_size,
+                metadata,
+                ready_to_run,
+            }),
+        ))
+    }
+
+    /// Whether the assembly contains only IL code, false for mixed-mode assemblies mixing IL and native code
+    pub fn is_il_only(&self) -> bool {
+        self.flags & COMIMAGE_FLAGS_ILONLY != 0
+    }
+
+    /// Whether the methods are precompiled to native code, as opposed to pure IL assemblies
+    pub fn is_ready_to_run(&self) -> bool {
+        self.ready_to_run.is_some()
+    }
+
+    /// Whether `entry_point_token` is the RVA of a native entry point rather than a method token
+    pub fn has_native_entry_point(&self) -> bool {
+        self.flags & COMIMAGE_FLAGS_NATIVE_ENTRYPOINT != 0
+    }
+}
+
+impl fmt::Display for ClrHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "ClrHeader")?;
+        writeln!(
+            f,
+            "  RuntimeVersion: {}.{}, MetaData: {:#x} ({} bytes), Flags: {:#x}, EntryPointToken: {:#x}",
+            self.major_runtime_version,
+            self.minor_runtime_version,
+            self.metadata_rva,
+            self.metadata_size,
+            self.flags,
+            self.entry_point_token
+        )?;
+        if let Some(ready_to_run) = &self.ready_to_run {
+            writeln!(f, "  {}", ready_to_run)?;
+        }
+        match &self.metadata {
+            Some(metadata) => write!(f, "{}", metadata),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClrHeader, COMIMAGE_FLAGS_ILONLY};
+    use crate::errors::ErrorKind;
+    use crate::headers::nt::{DataDirectory, DirectoryEntry};
+    use crate::headers::sections::{Section, Sections};
+
+    fn put(data: &mut [u8], offset: usize, bytes: &[u8]) {
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    #[test]
+    fn clr_header_with_metadata() {
+        // the CLR header at 0x2000 and the metadata root at 0x2050, with the section at offset 0
+        let mut pe_file = vec![0u8; 0x200];
+        put(&mut pe_file, 0, &72u32.to_le_bytes());
+        put(&mut pe_file, 4, &2u16.to_le_bytes());
+        put(&mut pe_file, 6, &5u16.to_le_bytes());
+        put(&mut pe_file, 8, &0x2050u32.to_le_bytes());
+        put(&mut pe_file, 12, &0x58u32.to_le_bytes());
+        put(&mut pe_file, 16, &COMIMAGE_FLAGS_ILONLY.to_le_bytes());
+        put(&mut pe_file, 20, &0x06000001u32.to_le_bytes());
+        let root = 0x50;
+        put(&mut pe_file, root, b"BSJB");
+        put(&mut pe_file, root + 4, &1u16.to_le_bytes());
+        put(&mut pe_file, root + 6, &1u16.to_le_bytes());
+        put(&mut pe_file, root + 12, &12u32.to_le_bytes());
+        put(&mut pe_file, root + 16, b"v4.0.30319");
+        put(&mut pe_file, root + 30, &2u16.to_le_bytes());
+        put(&mut pe_file, root + 32, &0x40u32.to_le_bytes());
+        put(&mut pe_file, root + 36, &0x10u32.to_le_bytes());
+        put(&mut pe_file, root + 40, b"#~");
+        put(&mut pe_file, root + 44, &0x50u32.to_le_bytes());
+        put(&mut pe_file, root + 48, &8u32.to_le_bytes());
+        put(&mut pe_file, root + 52, b"#Strings");
+        put(&mut pe_file, root + 0x50, b"\0<Module>");
+
+        let sections = Sections::new(
+            vec![Section::for_test(".text", 0x2000, 0x200, 0, 0x200, 0)],
+            0,
+        );
+        let directory = DataDirectory {
+            entry: DirectoryEntry::ClrRuntime,
+            virtual_address: 0x2000,
+            size: 72,
+        };
+        let (_, clr) = ClrHeader::parse(&pe_file, directory, &sections).unwrap();
+        let clr = clr.unwrap();
+        assert_eq!(
+            (clr.major_runtime_version, clr.minor_runtime_version),
+            (2, 5)
+        );
+        assert!(clr.is_il_only());
+        assert!(!clr.has_native_entry_point());
+        assert!(!clr.is_ready_to_run());
+        assert_eq!(clr.entry_point_token, 0x06000001);
+
+        let metadata = clr.metadata.unwrap();
+        assert_eq!(metadata.offset, root);
+        assert_eq!(metadata.version, "v4.0.30319");
+        let streams: Vec<(&str, u32, u32)> = metadata
+            .streams
+            .iter()
+            .map(|stream| (stream.name.as_str(), stream.offset, stream.size))
+            .collect();
+        assert_eq!(streams, vec![("#~", 0x40, 0x10), ("#Strings", 0x50, 8)]);
+        assert_eq!(
+            metadata.tables_stream(&pe_file).map(<[u8]>::len),
+            Some(0x10)
+        );
+        assert_eq!(
+            metadata.stream_data(&pe_file, "#Strings"),
+            Some(&b"\0<Module"[..])
+        );
+        assert!(metadata.stream_data(&pe_file, "#US").is_none());
+
+        // a CLR header outside of the sections is an error
+        let directory = DataDirectory {
+            virtual_address: 0x4000,
+            ..directory
+        };
+        let err = match ClrHeader::parse(&pe_file, directory, &sections) {
+            Err(nom::Err::Failure(err)) => err,
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        };
+        assert!(matches!(
+            err.errors[0].1,
+            ErrorKind::RvaOutOfBounds {
+                rva: 0x4000,
+                size: 0x2200
+            }
+        ));
+        assert!(matches!(err.errors[1].1, ErrorKind::Context("CLR header")));
+    }
+}
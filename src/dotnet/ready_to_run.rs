@@ -0,0 +1,63 @@
+use crate::utils;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// READYTORUN_HEADER pointed by the managed native header of ReadyToRun images,
+/// whose methods are precompiled to native code by crossgen in addition to their IL
+///
+/// Reference: https://github.com/dotnet/runtime/blob/main/docs/design/coreclr/botr/readytorun-format.md
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadyToRunHeader {
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub flags: u32,
+    pub num_of_sections: u32,
+}
+
+impl ReadyToRunHeader {
+    /// "RTR" followed by a null byte
+    pub const SIGNATURE: u32 = 0x00525452;
+
+    /// None when the data doesn't start with the signature, e.g. the NGen header of the .NET Framework
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if utils::read_u32_at(data, 0)? != Self::SIGNATURE {
+            return None;
+        }
+        Some(Self {
+            major_version: utils::read_u16_at(data, 4)?,
+            minor_version: utils::read_u16_at(data, 6)?,
+            flags: utils::read_u32_at(data, 8)?,
+            num_of_sections: utils::read_u32_at(data, 12)?,
+        })
+    }
+}
+
+impl fmt::Display for ReadyToRunHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ReadyToRun: {}.{}, Flags: {:#x}, Sections: {}",
+            self.major_version, self.minor_version, self.flags, self.num_of_sections
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReadyToRunHeader;
+
+    #[test]
+    fn ready_to_run_header() {
+        let mut data = b"RTR\0".to_vec();
+        data.extend_from_slice(&9u16.to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes());
+        data.extend_from_slice(&0x1u32.to_le_bytes());
+        data.extend_from_slice(&12u32.to_le_bytes());
+        let header = ReadyToRunHeader::parse(&data).unwrap();
+        assert_eq!((header.major_version, header.minor_version), (9, 2));
+        assert_eq!(header.num_of_sections, 12);
+        assert!(ReadyToRunHeader::parse(&data[..12]).is_none());
+        data[0] = b'N';
+        assert!(ReadyToRunHeader::parse(&data).is_none());
+    }
+}
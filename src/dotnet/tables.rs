@@ -0,0 +1,298 @@
+use crate::utils;
+use byteorder::{ByteOrder, LittleEndian};
+
+pub const MODULE: usize = 0x00;
+pub const TYPE_REF: usize = 0x01;
+pub const TYPE_DEF: usize = 0x02;
+pub const FIELD: usize = 0x04;
+pub const METHOD_DEF: usize = 0x06;
+pub const PARAM: usize = 0x08;
+pub const INTERFACE_IMPL: usize = 0x09;
+pub const MEMBER_REF: usize = 0x0a;
+pub const DECL_SECURITY: usize = 0x0e;
+pub const STAND_ALONE_SIG: usize = 0x11;
+pub const EVENT: usize = 0x14;
+pub const PROPERTY: usize = 0x17;
+pub const MODULE_REF: usize = 0x1a;
+pub const TYPE_SPEC: usize = 0x1b;
+pub const ASSEMBLY: usize = 0x20;
+pub const ASSEMBLY_REF: usize = 0x23;
+pub const FILE: usize = 0x26;
+pub const EXPORTED_TYPE: usize = 0x27;
+pub const MANIFEST_RESOURCE: usize = 0x28;
+pub const GENERIC_PARAM: usize = 0x2a;
+pub const METHOD_SPEC: usize = 0x2b;
+pub const GENERIC_PARAM_CONSTRAINT: usize = 0x2c;
+
+/// A column of a metadata table row
+#[derive(Debug, Clone, Copy)]
+enum Column {
+    Fixed(usize),
+    String,
+    Guid,
+    Blob,
+    Table(usize),
+    Coded(CodedIndex),
+}
+
+/// Coded indexes with the number of tag bits and the tables they can refer to
+#[derive(Debug, Clone, Copy)]
+enum CodedIndex {
+    TypeDefOrRef,
+    HasConstant,
+    HasCustomAttribute,
+    HasFieldMarshal,
+    HasDeclSecurity,
+    MemberRefParent,
+    HasSemantics,
+    MethodDefOrRef,
+    MemberForwarded,
+    CustomAttributeType,
+    ResolutionScope,
+}
+
+impl CodedIndex {
+    fn tag_bits_and_tables(&self) -> (u32, &'static [usize]) {
+        match self {
+            Self::TypeDefOrRef => (2, &[TYPE_DEF, TYPE_REF, TYPE_SPEC]),
+            Self::HasConstant => (2, &[FIELD, PARAM, PROPERTY]),
+            Self::HasCustomAttribute => (
+                5,
+                &[
+                    METHOD_DEF,
+                    FIELD,
+                    TYPE_REF,
+                    TYPE_DEF,
+                    PARAM,
+                    INTERFACE_IMPL,
+                    MEMBER_REF,
+                    MODULE,
+                    DECL_SECURITY,
+                    PROPERTY,
+                    EVENT,
+                    STAND_ALONE_SIG,
+                    MODULE_REF,
+                    TYPE_SPEC,
+                    ASSEMBLY,
+                    ASSEMBLY_REF,
+                    FILE,
+                    EXPORTED_TYPE,
+                    MANIFEST_RESOURCE,
+                    GENERIC_PARAM,
+                    GENERIC_PARAM_CONSTRAINT,
+                    METHOD_SPEC,
+                ],
+            ),
+            Self::HasFieldMarshal => (1, &[FIELD, PARAM]),
+            Self::HasDeclSecurity => (2, &[TYPE_DEF, METHOD_DEF, ASSEMBLY]),
+            Self::MemberRefParent => (3, &[TYPE_DEF, TYPE_REF, MODULE_REF, METHOD_DEF, TYPE_SPEC]),
+            Self::HasSemantics => (1, &[EVENT, PROPERTY]),
+            Self::MethodDefOrRef => (1, &[METHOD_DEF, MEMBER_REF]),
+            Self::MemberForwarded => (1, &[FIELD, METHOD_DEF]),
+            Self::CustomAttributeType => (3, &[METHOD_DEF, MEMBER_REF]),
+            Self::ResolutionScope => (2, &[MODULE, MODULE_REF, ASSEMBLY_REF, TYPE_REF]),
+        }
+    }
+}
+
+/// Row layouts of the tables up to Assembly (ECMA-335 II.22)
+fn columns(table: usize) -> &'static [Column] {
+    use CodedIndex::*;
+    use Column::*;
+    match table {
+        0x00 => &[Fixed(2), String, Guid, Guid, Guid],
+        0x01 => &[Coded(ResolutionScope), String, String],
+        0x02 => &[
+            Fixed(4),
+            String,
+            String,
+            Coded(TypeDefOrRef),
+            Table(FIELD),
+            Table(METHOD_DEF),
+        ],
+        0x03 => &[Table(FIELD)],
+        0x04 => &[Fixed(2), String, Blob],
+        0x05 => &[Table(METHOD_DEF)],
+        0x06 => &[Fixed(4), Fixed(2), Fixed(2), String, Blob, Table(PARAM)],
+        0x07 => &[Table(PARAM)],
+        0x08 => &[Fixed(2), Fixed(2), String],
+        0x09 => &[Table(TYPE_DEF), Coded(TypeDefOrRef)],
+        0x0a => &[Coded(MemberRefParent), String, Blob],
+        0x0b => &[Fixed(2), Coded(HasConstant), Blob],
+        0x0c => &[Coded(HasCustomAttribute), Coded(CustomAttributeType), Blob],
+        0x0d => &[Coded(HasFieldMarshal), Blob],
+        0x0e => &[Fixed(2), Coded(HasDeclSecurity), Blob],
+        0x0f => &[Fixed(2), Fixed(4), Table(TYPE_DEF)],
+        0x10 => &[Fixed(4), Table(FIELD)],
+        0x11 => &[Blob],
+        0x12 => &[Table(TYPE_DEF), Table(EVENT)],
+        0x13 => &[Table(EVENT)],
+        0x14 => &[Fixed(2), String, Coded(TypeDefOrRef)],
+        0x15 => &[Table(TYPE_DEF), Table(PROPERTY)],
+        0x16 => &[Table(PROPERTY)],
+        0x17 => &[Fixed(2), String, Blob],
+        0x18 => &[Fixed(2), Table(METHOD_DEF), Coded(HasSemantics)],
+        0x19 => &[
+            Table(TYPE_DEF),
+            Coded(MethodDefOrRef),
+            Coded(MethodDefOrRef),
+        ],
+        0x1a => &[String],
+        0x1b => &[Blob],
+        0x1c => &[Fixed(2), Coded(MemberForwarded), String, Table(MODULE_REF)],
+        0x1d => &[Fixed(4), Table(FIELD)],
+        0x1e => &[Fixed(4), Fixed(4)],
+        0x1f => &[Fixed(4)],
+        0x20 => &[
+            Fixed(4),
+            Fixed(2),
+            Fixed(2),
+            Fixed(2),
+            Fixed(2),
+            Fixed(4),
+            Blob,
+            String,
+            String,
+        ],
+        _ => &[],
+    }
+}
+
+/// The header of the `#~` stream, which locates the rows of each metadata table
+#[derive(Debug)]
+pub struct MetadataTables<'a> {
+    pub heap_sizes: u8,
+    pub row_counts: [u32; 64],
+    rows: &'a [u8], // the stream data following the row counts
+}
+
+impl<'a> MetadataTables<'a> {
+    const HEAP_STRING_WIDE: u8 = 0x01;
+    const HEAP_GUID_WIDE: u8 = 0x02;
+    const HEAP_BLOB_WIDE: u8 = 0x04;
+    const EXTRA_DATA: u8 = 0x40;
+
+    pub fn parse(stream: &'a [u8]) -> Option<Self> {
+        // reserved (4), major version (1) and minor version (1) precede the heap sizes
+        let heap_sizes = *stream.get(6)?;
+        // bit vector of present tables, followed by the bit vector of sorted tables
+        let valid = utils::read_u64_at(stream, 8)?;
+
+        let mut row_counts = [0u32; 64];
+        let mut offset = 24;
+        for (table, count) in row_counts.iter_mut().enumerate() {
+            if valid & (1 << table) != 0 {
+                *count = utils::read_u32_at(stream, offset)?;
+                offset += 4;
+            }
+        }
+        if heap_sizes & Self::EXTRA_DATA != 0 {
+            offset += 4;
+        }
+
+        Some(Self {
+            heap_sizes,
+            row_counts,
+            rows: stream.get(offset..)?,
+        })
+    }
+
+    /// Returns the row of the table with the 1-based index, or None for unsupported tables or out-of-range rows
+    pub fn row(&self, table: usize, index: u32) -> Option<&'a [u8]> {
+        if table > ASSEMBLY || index == 0 || index > self.row_counts[table] {
+            return None;
+        }
+        let mut offset = 0usize;
+        for preceding in 0..table {
+            offset = offset.checked_add(
+                self.row_size(preceding)
+                    .checked_mul(self.row_counts[preceding] as usize)?,
+            )?;
+        }
+        let row_size = self.row_size(table);
+        let start = offset.checked_add(row_size.checked_mul(index as usize - 1)?)?;
+        self.rows.get(start..start.checked_add(row_size)?)
+    }
+
+    /// Reads the column of the row as a u32, widening 2-byte values
+    pub fn column(&self, table: usize, row: &[u8], column: usize) -> Option<u32> {
+        let columns = columns(table);
+        let offset: usize = columns[..column].iter().map(|c| self.column_size(*c)).sum();
+        match self.column_size(*columns.get(column)?) {
+            1 => row.get(offset).map(|v| *v as u32),
+            2 => utils::read_u16_at(row, offset).map(|v| v as u32),
+            _ => row.get(offset..offset + 4).map(LittleEndian::read_u32),
+        }
+    }
+
+    fn row_size(&self, table: usize) -> usize {
+        columns(table)
+            .iter()
+            .map(|column| self.column_size(*column))
+            .sum()
+    }
+
+    fn column_size(&self, column: Column) -> usize {
+        match column {
+            Column::Fixed(size) => size,
+            Column::String => self.heap_index_size(Self::HEAP_STRING_WIDE),
+            Column::Guid => self.heap_index_size(Self::HEAP_GUID_WIDE),
+            Column::Blob => self.heap_index_size(Self::HEAP_BLOB_WIDE),
+            Column::Table(table) => {
+                if self.row_counts[table] < 0x10000 {
+                    2
+                } else {
+                    4
+                }
+            }
+            Column::Coded(coded) => {
+                let (tag_bits, tables) = coded.tag_bits_and_tables();
+                let max_rows = tables
+                    .iter()
+                    .map(|table| self.row_counts[*table])
+                    .max()
+                    .unwrap_or(0);
+                if max_rows < (1 << (16 - tag_bits)) {
+                    2
+                } else {
+                    4
+                }
+            }
+        }
+    }
+
+    fn heap_index_size(&self, flag: u8) -> usize {
+        if self.heap_sizes & flag != 0 {
+            4
+        } else {
+            2
+        }
+    }
+}
+
+/// Reads the null-terminated string at the index of the `#Strings` heap
+pub fn read_string(strings_heap: &[u8], index: u32) -> Option<String> {
+    strings_heap
+        .get(index as usize..)
+        .map(utils::read_null_terminated_string)
+}
+
+/// Reads the blob at the index of the `#Blob` heap, which is prefixed by its compressed length
+pub fn read_blob(blob_heap: &[u8], index: u32) -> Option<&[u8]> {
+    let data = blob_heap.get(index as usize..)?;
+    let first = *data.first()? as usize;
+    let (length, header): (usize, usize) = if first & 0x80 == 0 {
+        (first, 1)
+    } else if first & 0xc0 == 0x80 {
+        (((first & 0x3f) << 8) | *data.get(1)? as usize, 2)
+    } else {
+        (
+            ((first & 0x1f) << 24)
+                | (*data.get(1)? as usize) << 16
+                | (*data.get(2)? as usize) << 8
+                | *data.get(3)? as usize,
+            4,
+        )
+    };
+    data.get(header..header.checked_add(length)?)
+}
@@ -7,6 +7,10 @@ pub enum ErrorKind {
     Nom(nom::error::ErrorKind),
     Context(&'static str),
     String(String),
+    // an RVA not mapped by any section, or beyond the size of the image
+    RvaOutOfBounds { rva: u32, size: u32 },
+    // a file offset beyond the end of the data of `size` bytes
+    OffsetOutOfBounds { offset: usize, size: usize },
 }
 
 impl fmt::Display for ErrorKind {
@@ -15,6 +19,14 @@ impl fmt::Display for ErrorKind {
             Self::Nom(n) => fmt::Display::fmt(n.description(), f),
             Self::Context(c) => fmt::Display::fmt(c, f),
             Self::String(s) => fmt::Display::fmt(s, f),
+            Self::RvaOutOfBounds { rva, size } => {
+                write!(f, "RVA {:#x} is out of bounds (size {:#x})", rva, size)
+            }
+            Self::OffsetOutOfBounds { offset, size } => write!(
+                f,
+                "offset {} is beyond the end of the data ({} bytes)",
+                offset, size
+            ),
         }
     }
 }
@@ -29,6 +41,35 @@ impl<I> PEError<I> {
         let errors = vec![(input, ErrorKind::String(s.into()))];
         nom::Err::Failure(Self { errors })
     }
+
+    /// An RVA that doesn't map, followed by the context naming what the RVA points to
+    pub fn rva_out_of_bounds(input: I, rva: u32, size: u32, ctx: &'static str) -> nom::Err<Self>
+    where
+        I: Clone,
+    {
+        Self::out_of_bounds(input, ErrorKind::RvaOutOfBounds { rva, size }, ctx)
+    }
+
+    /// A file offset beyond the end of the data, followed by the context naming what the offset points to
+    pub fn offset_out_of_bounds(
+        input: I,
+        offset: usize,
+        size: usize,
+        ctx: &'static str,
+    ) -> nom::Err<Self>
+    where
+        I: Clone,
+    {
+        Self::out_of_bounds(input, ErrorKind::OffsetOutOfBounds { offset, size }, ctx)
+    }
+
+    fn out_of_bounds(input: I, kind: ErrorKind, ctx: &'static str) -> nom::Err<Self>
+    where
+        I: Clone,
+    {
+        let errors = vec![(input.clone(), kind), (input, ErrorKind::Context(ctx))];
+        nom::Err::Failure(Self { errors })
+    }
 }
 
 impl<'a> From<PEError<Input<'a>>> for nom::Err<PEError<Input<'a>>> {
@@ -37,6 +78,25 @@ impl<'a> From<PEError<Input<'a>>> for nom::Err<PEError<Input<'a>>> {
     }
 }
 
+/// A file shorter than its headers declare, e.g. a partial download, see `PE::check_completeness`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncation {
+    pub expected_len: usize,
+    pub actual_len: usize,
+}
+
+impl fmt::Display for Truncation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "file truncated: the headers declare {} bytes but the file has {} bytes",
+            self.expected_len, self.actual_len
+        )
+    }
+}
+
+impl std::error::Error for Truncation {}
+
 impl<I> nom::error::ParseError<I> for PEError<I> {
     fn from_error_kind(input: I, kind: nom::error::ErrorKind) -> Self {
         let errors = vec![(input, ErrorKind::Nom(kind))];
@@ -1,17 +1,21 @@
 use crate::headers::nt::DataDirectory;
 use crate::headers::sections::Sections;
-use crate::{errors, parse};
+use crate::options::ParseContext;
+use crate::{errors, parse, utils};
 use chrono::{DateTime, Utc};
+use derive_more::Display;
 use nom::error::context;
 use nom::number::complete::{le_u16, le_u32};
 use nom::sequence::tuple;
+use std::collections::HashSet;
 use std::fmt;
 use std::fmt::Formatter;
 
 #[derive(Debug)]
 pub struct ExportDirectoryTable {
     pub characteristics: u32,
-    pub datetime: DateTime<Utc>,
+    pub time_date_stamp: u32,
+    pub datetime: DateTime<Utc>, // the Unix epoch when timestamps are not decoded
     pub major_version: u16,
     pub minor_version: u16,
     pub name: u32, // RVA to the name of the DLL
@@ -21,21 +25,29 @@ pub struct ExportDirectoryTable {
     pub addr_of_funcs: u32,     // RVA to the address of the Export Address Table
     pub addr_of_names: u32,     // RVA to the address of the Export Names Table
     pub addr_of_name_ordi: u32, // RVA to the address of the Export Ordinals Table
+    pub dll_name: String,
+    pub functions: Vec<ExportedFunction>,
+    // the entries of the Export Ordinal Table that are beyond num_of_funcs, which are skipped
+    pub invalid_name_ordinals: Vec<u16>,
 }
 
 impl ExportDirectoryTable {
-    pub fn parse(
-        pe_file: parse::Input,
-        export_directory: DataDirectory,
-        sections: Sections,
-    ) -> parse::Result<Option<Self>> {
-        match sections.find_by_address(export_directory.virtual_address) {
-            Some(section) => {
-                let offset = section
-                    .rva_to_offset(export_directory.virtual_address)
-                    .unwrap(); // @todo remove it
-                let section_data = &pe_file[offset as usize..];
+    pub const SIZE: usize = 40;
 
+    pub fn parse<'a>(
+        pe_file: parse::Input<'a>,
+        export_directory: DataDirectory,
+        sections: &Sections,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Option<Self>> {
+        sections.check_directory_mapped(pe_file, export_directory, "export directory")?;
+        // the whole fixed-size header must be mapped by the section containing its start
+        match sections
+            .range_in_single_section(export_directory.virtual_address, Self::SIZE as u32)
+            .and_then(|section| section.rva_to_offset(export_directory.virtual_address))
+            .and_then(|offset| pe_file.get(offset as usize..))
+        {
+            Some(section_data) => {
                 let (
                     i,
                     (
@@ -65,12 +77,17 @@ impl ExportDirectoryTable {
                     context("AddressOfNameOrdinals", le_u32),
                 ))(section_data)?;
 
-                let datetime = chrono::NaiveDateTime::from_timestamp_opt(time_date_stamp as i64, 0)
-                    .ok_or(errors::PEError::from_string(i, "wrong timestamp format"))?;
+                let datetime = if ctx.decode_timestamps() {
+                    DateTime::<Utc>::from_timestamp(time_date_stamp as i64, 0)
+                        .ok_or(errors::PEError::from_string(i, "wrong timestamp format"))?
+                } else {
+                    DateTime::<Utc>::default()
+                };
 
-                let export_directory_table = Self {
+                let mut export_directory_table = Self {
                     characteristics,
-                    datetime: DateTime::from_utc(datetime, Utc),
+                    time_date_stamp,
+                    datetime,
                     major_version,
                     minor_version,
                     name,
@@ -80,20 +97,611 @@ impl ExportDirectoryTable {
                     addr_of_funcs,
                     addr_of_names,
                     addr_of_name_ordi,
+                    dll_name: Self::read_name(pe_file, name, sections, ctx, "DLL name")?
+                        .unwrap_or_default(),
+                    functions: vec![],
+                    invalid_name_ordinals: vec![],
                 };
+                export_directory_table.resolve_functions(
+                    pe_file,
+                    export_directory,
+                    sections,
+                    ctx,
+                )?;
 
                 Ok((i, Some(export_directory_table)))
             }
             None => Ok((pe_file, None)),
         }
     }
+
+    /// Resolves the Export Address Table, the Export Name Pointer Table and the Export Ordinal Table
+    /// into a list of exported functions, one per non-empty Export Address Table entry.
+    ///
+    /// An entry whose RVA points inside the export directory is a forwarder,
+    /// in which case the RVA refers to a forwarder string like `NTDLL.RtlAllocateHeap`.
+    ///
+    /// A DLL exporting by ordinal only has no name tables, leaving `num_of_names` and `addr_of_names` zero,
+    /// so the ordinals are assigned from `base` alone.
+    ///
+    /// A crafted Export Ordinal Table can index beyond `num_of_funcs`, in which case the name is skipped
+    /// and the ordinal is recorded in `invalid_name_ordinals`.
+    fn resolve_functions<'a>(
+        &mut self,
+        pe_file: parse::Input<'a>,
+        export_directory: DataDirectory,
+        sections: &Sections,
+        ctx: &ParseContext,
+    ) -> Result<(), nom::Err<errors::PEError<parse::Input<'a>>>> {
+        let addresses_offset = match sections.rva_to_offset(self.addr_of_funcs) {
+            Some(offset) => offset,
+            None => return Ok(()),
+        };
+        let num_of_funcs = Self::capped_count(
+            self.num_of_funcs,
+            4,
+            export_directory.size,
+            pe_file,
+            addresses_offset,
+        );
+        ctx.budget()
+            .table("export address table")
+            .reserve(pe_file, num_of_funcs)?;
+        let addresses = utils::read_array(pe_file, addresses_offset as usize, num_of_funcs);
+
+        let names_offset = sections.rva_to_offset(self.addr_of_names);
+        let ordinals_offset = sections.rva_to_offset(self.addr_of_name_ordi);
+        let num_of_names = match (names_offset, ordinals_offset) {
+            (Some(names_offset), Some(ordinals_offset)) => Self::capped_count(
+                self.num_of_names,
+                4,
+                export_directory.size,
+                pe_file,
+                names_offset,
+            )
+            .min(Self::capped_count(
+                self.num_of_names,
+                2,
+                export_directory.size,
+                pe_file,
+                ordinals_offset,
+            )),
+            _ => 0,
+        };
+        ctx.budget()
+            .table("export name pointer table")
+            .reserve(pe_file, num_of_names)?;
+        let name_pointers = names_offset
+            .map(|offset| utils::read_array(pe_file, offset as usize, num_of_names))
+            .unwrap_or_default();
+        let name_ordinals = ordinals_offset
+            .map(|offset| utils::read_u16_array(pe_file, offset as usize, num_of_names))
+            .unwrap_or_default();
+
+        let mut names: Vec<Option<String>> = vec![None; addresses.len()];
+        for (name_rva, ordinal) in name_pointers.iter().zip(name_ordinals.iter()) {
+            if *ordinal as u32 >= self.num_of_funcs {
+                self.invalid_name_ordinals.push(*ordinal);
+                continue;
+            }
+            if let Some(slot) = names.get_mut(*ordinal as usize) {
+                if slot.is_none() {
+                    *slot = Self::read_name(
+                        pe_file,
+                        *name_rva,
+                        sections,
+                        ctx,
+                        "exported function name",
+                    )?;
+                }
+            }
+        }
+
+        let directory_start = export_directory.virtual_address;
+        let directory_end = directory_start.saturating_add(export_directory.size);
+        self.functions = addresses
+            .into_iter()
+            .zip(names)
+            .enumerate()
+            .filter(|(_, (rva, _))| *rva != 0)
+            .map(|(index, (rva, name))| {
+                let forwarder = if rva >= directory_start && rva < directory_end {
+                    Self::read_name(pe_file, rva, sections, ctx, "forwarder")?
+                } else {
+                    None
+                };
+                let function = ExportedFunction {
+                    ordinal: self.base.wrapping_add(index as u32),
+                    name,
+                    rva,
+                    forwarder,
+                };
+                ctx.visit(|visitor| visitor.on_export(&function));
+                Ok(function)
+            })
+            .collect::<Result<_, nom::Err<errors::PEError<parse::Input<'a>>>>>()?;
+        Ok(())
+    }
+
+    /// The `(export name, forward target)` pairs of the forwarded exports, e.g. `("HeapAlloc", "NTDLL.RtlAllocateHeap")`.
+    /// Exports forwarded only by ordinal are named `#<ordinal>`.
+    /// The forwarders are resolved while parsing, so the file is not needed again.
+    pub fn forwarders(&self) -> Vec<(String, String)> {
+        self.functions
+            .iter()
+            .filter_map(|function| {
+                let forwarder = function.forwarder.as_ref()?;
+                let name = function
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("#{}", function.ordinal));
+                Some((name, forwarder.clone()))
+            })
+            .collect()
+    }
+
+    /// The number of exported functions having a name.
+    /// Unlike `num_of_names`, a function exported under several names is counted once.
+    pub fn name_count(&self) -> usize {
+        self.functions
+            .iter()
+            .filter(|function| function.name.is_some())
+            .count()
+    }
+
+    /// The number of functions exported only by ordinal.
+    /// This is not `num_of_funcs - num_of_names` since the empty slots of the export address table
+    /// are not functions, and several names can point to the same function.
+    pub fn ordinal_only_count(&self) -> usize {
+        self.functions.len() - self.name_count()
+    }
+
+    /// The exported functions which no entry of the Export Ordinal Table refers to, i.e. exported only by ordinal,
+    /// as DLLs hiding the purpose of their functions do. Unlike the functions without `name`,
+    /// this excludes the functions whose name is referenced but couldn't be read.
+    pub fn ordinal_only_exports(
+        &self,
+        pe_file: parse::Input,
+        sections: &Sections,
+    ) -> Vec<ExportedFunction> {
+        let named: HashSet<u16> = match sections.rva_to_offset(self.addr_of_name_ordi) {
+            Some(offset) => {
+                let available = pe_file.len().saturating_sub(offset as usize) / 2;
+                let count = (self.num_of_names as usize).min(available);
+                utils::read_u16_array(pe_file, offset as usize, count)
+                    .into_iter()
+                    .collect()
+            }
+            None => HashSet::new(),
+        };
+        self.functions
+            .iter()
+            .filter(|function| {
+                let index = function.ordinal.wrapping_sub(self.base);
+                u16::try_from(index).map_or(true, |index| !named.contains(&index))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The first and the last valid ordinal, both inclusive, i.e. `(base, base + num_of_funcs - 1)`.
+    /// Returns None when no function is exported or the range doesn't fit in 16-bit ordinals.
+    pub fn ordinal_range(&self) -> Option<(u16, u16)> {
+        let last = self.base.checked_add(self.num_of_funcs.checked_sub(1)?)?;
+        Some((u16::try_from(self.base).ok()?, u16::try_from(last).ok()?))
+    }
+
+    /// Whether the ordinal is within `ordinal_range`, i.e. the export address table has a slot for it
+    pub fn contains_ordinal(&self, ordinal: u16) -> bool {
+        self.ordinal_range()
+            .is_some_and(|(first, last)| (first..=last).contains(&ordinal))
+    }
+
+    /// Reads the name at the index of the Export Name Pointer Table, which is what import hints refer to
+    pub fn name_at(
+        &self,
+        pe_file: parse::Input,
+        sections: &Sections,
+        index: u32,
+    ) -> Option<String> {
+        if index >= self.num_of_names {
+            return None;
+        }
+        let offset =
+            sections.rva_to_offset(self.addr_of_names.checked_add(index.checked_mul(4)?)?)?;
+        let name_rva = utils::read_u32_at(pe_file, offset as usize)?;
+        Self::read_string(pe_file, name_rva, sections)
+    }
+
+    /// Whether the Export Name Pointer Table is sorted in ascending byte order, as the loader requires
+    /// to look names up by binary search. Unsorted names, or names which can't be read, indicate
+    /// a hand-built or corrupted export table.
+    pub fn names_are_sorted(&self, pe_file: parse::Input, sections: &Sections) -> bool {
+        let names_offset = match sections.rva_to_offset(self.addr_of_names) {
+            Some(offset) => offset,
+            None => return self.num_of_names == 0,
+        };
+        let num_of_names = (self.num_of_names as usize)
+            .min(pe_file.len().saturating_sub(names_offset as usize) / 4);
+        let names: Option<Vec<&[u8]>> =
+            utils::read_array(pe_file, names_offset as usize, num_of_names)
+                .into_iter()
+                .map(|name_rva| {
+                    let offset = sections.rva_to_offset(name_rva)? as usize;
+                    let (name, _) =
+                        utils::c_string_bytes(pe_file.get(offset..)?, utils::MAX_STRING_LENGTH);
+                    Some(name)
+                })
+                .collect();
+        match names {
+            Some(names) => names.windows(2).all(|pair| pair[0] <= pair[1]),
+            None => false,
+        }
+    }
+
+    /// Every exported name whose Export Address Table entry is `rva`, in the order of the Export Name Pointer Table.
+    /// Unlike `functions`, which keep a single name per slot, this finds both the names sharing a slot
+    /// and the names of other slots pointing to the same address.
+    pub fn aliases_of(&self, rva: u32, pe_file: parse::Input, sections: &Sections) -> Vec<String> {
+        let (names_offset, ordinals_offset, addresses_offset) = match (
+            sections.rva_to_offset(self.addr_of_names),
+            sections.rva_to_offset(self.addr_of_name_ordi),
+            sections.rva_to_offset(self.addr_of_funcs),
+        ) {
+            (Some(names), Some(ordinals), Some(addresses)) => (names, ordinals, addresses),
+            _ => return vec![],
+        };
+        let remaining = |offset: u32, entry_size: usize| {
+            pe_file.len().saturating_sub(offset as usize) / entry_size
+        };
+        let num_of_names = (self.num_of_names as usize)
+            .min(remaining(names_offset, 4))
+            .min(remaining(ordinals_offset, 2));
+        let name_pointers = utils::read_array(pe_file, names_offset as usize, num_of_names);
+        let name_ordinals = utils::read_u16_array(pe_file, ordinals_offset as usize, num_of_names);
+        name_pointers
+            .iter()
+            .zip(name_ordinals.iter())
+            .filter(|(_, ordinal)| (**ordinal as u32) < self.num_of_funcs)
+            .filter(|(_, ordinal)| {
+                utils::read_u32_at(pe_file, addresses_offset as usize + **ordinal as usize * 4)
+                    == Some(rva)
+            })
+            .filter_map(|(name_rva, _)| Self::read_string(pe_file, *name_rva, sections))
+            .collect()
+    }
+
+    /// Caps a count read from the file by the number of entries that fit in the export directory
+    /// and in the rest of the file, so that a crafted count doesn't cause a huge allocation.
+    fn capped_count(
+        count: u32,
+        entry_size: usize,
+        directory_size: u32,
+        pe_file: parse::Input,
+        offset: u32,
+    ) -> usize {
+        let remaining = pe_file.len().saturating_sub(offset as usize);
+        (count as usize)
+            .min(directory_size as usize / entry_size)
+            .min(remaining / entry_size)
+    }
+
+    /// Reads the string at the rva in the string mode of the parse, see `ParseContext::read_name`
+    fn read_name<'a>(
+        pe_file: parse::Input<'a>,
+        rva: u32,
+        sections: &Sections,
+        ctx: &ParseContext,
+        what: &'static str,
+    ) -> Result<Option<String>, nom::Err<errors::PEError<parse::Input<'a>>>> {
+        match sections
+            .rva_to_offset(rva)
+            .and_then(|offset| pe_file.get(offset as usize..))
+        {
+            Some(slice) => ctx.read_name(pe_file, slice, what).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn read_string(pe_file: parse::Input, rva: u32, sections: &Sections) -> Option<String> {
+        sections
+            .rva_to_offset(rva)
+            .filter(|offset| (*offset as usize) < pe_file.len())
+            .map(|offset| utils::read_null_terminated_string(&pe_file[offset as usize..]))
+    }
+}
+
+/// Whether the export directory timestamp agrees with the file header timestamp.
+/// Linkers write the same value to both, so a difference suggests that one of them was modified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum TimestampConsistency {
+    NoExports,
+    Consistent,
+    ExportZeroed,
+    Mismatch,
+}
+
+/// An entry of the Export Address Table resolved with its name and forwarder.
+#[derive(Debug, Clone)]
+pub struct ExportedFunction {
+    pub ordinal: u32,
+    pub name: Option<String>, // None when the function is exported only by ordinal
+    pub rva: u32,
+    pub forwarder: Option<String>, // e.g. "NTDLL.RtlAllocateHeap"
+}
+
+impl ExportedFunction {
+    /// Whether the function is forwarded to an API set like `api-ms-win-core-heap-l1-1-0.HeapAlloc`
+    /// rather than to a DLL. Resolving the API set to its host DLL requires the API set schema of
+    /// the running OS, which is out of the scope of the parser.
+    pub fn is_apiset_forwarder(&self) -> bool {
+        self.forwarder.as_deref().is_some_and(|forwarder| {
+            let forwarder = forwarder.to_ascii_lowercase();
+            forwarder.starts_with("api-ms-") || forwarder.starts_with("ext-ms-")
+        })
+    }
 }
 
 impl fmt::Display for ExportDirectoryTable {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        writeln!(f, "  ExportDirectoryTable: ")?;
-        writeln!(f, "    Characteristics: {}, DateTime: {}, MajorVersion: {}, MinorVersion: {}, Name: {}, Base: {}, NumberOfFunctions: {}, NumberOfNames: {}, AddressOfFunctions: {}, AddressOfNames: {}, AddressOfNameOrdinals: {}",
-            self.characteristics, self.datetime, self.major_version, self.minor_version, self.name, self.base, self.num_of_funcs, self.num_of_names, self.addr_of_funcs, self.addr_of_names, self.addr_of_name_ordi
-        )
+        writeln!(f, "  ExportDirectoryTable:")?;
+        if f.alternate() {
+            return writeln!(
+                f,
+                "    Name: {}, Functions: {}, Named: {}",
+                self.dll_name,
+                self.functions.len(),
+                self.name_count()
+            );
+        }
+        writeln!(f, "    Name: {}, Characteristics: {}, DateTime: {}, MajorVersion: {}, MinorVersion: {}, Base: {}, NumberOfFunctions: {}, NumberOfNames: {}",
+            self.dll_name, self.characteristics, self.datetime, self.major_version, self.minor_version, self.base, self.num_of_funcs, self.num_of_names
+        )?;
+        if !self.invalid_name_ordinals.is_empty() {
+            writeln!(
+                f,
+                "      Skipped name ordinals beyond NumberOfFunctions: {:?}",
+                self.invalid_name_ordinals
+            )?;
+        }
+        writeln!(f, "      ExportedFunctions:")?;
+        for function in &self.functions {
+            writeln!(f, "{}", function)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ExportedFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "        ordinal: {}, name: {}, rva: {:#x}",
+            self.ordinal,
+            self.name.as_deref().unwrap_or("-"),
+            self.rva
+        )?;
+        match &self.forwarder {
+            Some(forwarder) => write!(f, ", forwarder: {}", forwarder),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExportDirectoryTable, ExportedFunction};
+    use crate::headers::nt::{DataDirectory, DirectoryEntry};
+    use crate::headers::sections::{Section, Sections};
+    use crate::options::ParseContext;
+    use chrono::{DateTime, Utc};
+
+    fn table(base: u32, num_of_funcs: u32) -> ExportDirectoryTable {
+        ExportDirectoryTable {
+            characteristics: 0,
+            time_date_stamp: 0,
+            datetime: DateTime::<Utc>::default(),
+            major_version: 0,
+            minor_version: 0,
+            name: 0,
+            base,
+            num_of_funcs,
+            num_of_names: 0,
+            addr_of_funcs: 0,
+            addr_of_names: 0,
+            addr_of_name_ordi: 0,
+            dll_name: String::new(),
+            functions: vec![],
+            invalid_name_ordinals: vec![],
+        }
+    }
+
+    #[test]
+    fn ordinal_range() {
+        assert_eq!(table(1, 3).ordinal_range(), Some((1, 3)));
+        assert_eq!(table(100, 1).ordinal_range(), Some((100, 100)));
+        assert_eq!(table(1, 0).ordinal_range(), None);
+        assert_eq!(table(0xfff0, 0x20).ordinal_range(), None);
+        assert!(table(100, 1).contains_ordinal(100));
+        assert!(!table(100, 1).contains_ordinal(99));
+        assert!(!table(1, 0).contains_ordinal(1));
+    }
+
+    #[test]
+    fn export_counts() {
+        let function = |ordinal, name: Option<&str>| ExportedFunction {
+            ordinal,
+            name: name.map(String::from),
+            rva: 0x1000 * ordinal,
+            forwarder: None,
+        };
+        // 5 slots with 2 empty ones, 2 names and an ordinal-only function
+        let mut table = table(1, 5);
+        table.num_of_names = 2;
+        table.functions = vec![
+            function(1, Some("Alpha")),
+            function(3, Some("Beta")),
+            function(4, None),
+        ];
+        assert_eq!(table.name_count(), 2);
+        assert_eq!(table.ordinal_only_count(), 1);
+    }
+
+    #[test]
+    fn apiset_forwarders() {
+        let function = |forwarder: Option<&str>| ExportedFunction {
+            ordinal: 1,
+            name: None,
+            rva: 0x1000,
+            forwarder: forwarder.map(String::from),
+        };
+        assert!(function(Some("api-ms-win-core-heap-l1-1-0.HeapAlloc")).is_apiset_forwarder());
+        assert!(function(Some("EXT-MS-WIN-NTUSER-WINDOW-L1-1-0.Foo")).is_apiset_forwarder());
+        assert!(!function(Some("NTDLL.RtlAllocateHeap")).is_apiset_forwarder());
+        assert!(!function(None).is_apiset_forwarder());
+    }
+
+    #[test]
+    fn capped_count() {
+        let pe_file = [0u8; 0x100];
+        assert_eq!(
+            ExportDirectoryTable::capped_count(3, 4, 0x100, &pe_file, 0x10),
+            3
+        );
+        // bounded by the directory size
+        assert_eq!(
+            ExportDirectoryTable::capped_count(u32::MAX, 4, 0x40, &pe_file, 0x10),
+            0x10
+        );
+        // bounded by the rest of the file
+        assert_eq!(
+            ExportDirectoryTable::capped_count(u32::MAX, 2, 0x1000, &pe_file, 0xf0),
+            8
+        );
+        assert_eq!(
+            ExportDirectoryTable::capped_count(u32::MAX, 4, 0x1000, &pe_file, 0x200),
+            0
+        );
+    }
+
+    #[test]
+    fn name_ordinal_out_of_range() {
+        let mut pe_file = vec![0u8; 0x10];
+        for address in [0x1000u32, 0x2000] {
+            pe_file.extend_from_slice(&address.to_le_bytes());
+        }
+        pe_file.resize(0x20, 0);
+        for name_rva in [0x40u32, 0x48] {
+            pe_file.extend_from_slice(&name_rva.to_le_bytes());
+        }
+        pe_file.resize(0x30, 0);
+        // the second ordinal is beyond the 2 functions
+        for ordinal in [1u16, 5] {
+            pe_file.extend_from_slice(&ordinal.to_le_bytes());
+        }
+        pe_file.resize(0x40, 0);
+        pe_file.extend_from_slice(b"Alpha\0\0\0Beta\0");
+        pe_file.resize(0x100, 0);
+        let sections = Sections::new(vec![Section::for_test(".edata", 0, 0x100, 0, 0x100, 0)], 0);
+        let directory = DataDirectory {
+            entry: DirectoryEntry::Export,
+            virtual_address: 0,
+            size: 0x100,
+        };
+        let mut table = table(1, 2);
+        table.num_of_names = 2;
+        table.addr_of_funcs = 0x10;
+        table.addr_of_names = 0x20;
+        table.addr_of_name_ordi = 0x30;
+        table
+            .resolve_functions(&pe_file, directory, &sections, &ParseContext::default())
+            .unwrap();
+        assert_eq!(table.functions.len(), 2);
+        assert_eq!(table.functions[0].name, None);
+        assert_eq!(table.functions[1].name.as_deref(), Some("Alpha"));
+        assert_eq!(table.invalid_name_ordinals, vec![5]);
+    }
+
+    #[test]
+    fn ordinal_only_directory() {
+        // the headers at RVA 0, where the zero addr_of_names points, must not be read as names
+        let mut pe_file = b"MZ\x90\0\x03\0\0\0".to_vec();
+        pe_file.resize(0x10, 0);
+        for address in [0x1000u32, 0, 0x3000] {
+            pe_file.extend_from_slice(&address.to_le_bytes());
+        }
+        pe_file.resize(0x100, 0);
+        let sections = Sections::new(vec![Section::for_test(".edata", 0, 0x100, 0, 0x100, 0)], 0);
+        let directory = DataDirectory {
+            entry: DirectoryEntry::Export,
+            virtual_address: 0,
+            size: 0x100,
+        };
+        let mut table = table(10, 3);
+        table.addr_of_funcs = 0x10;
+        table
+            .resolve_functions(&pe_file, directory, &sections, &ParseContext::default())
+            .unwrap();
+        let exports = table
+            .functions
+            .iter()
+            .map(|function| (function.ordinal, function.rva, function.name.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(exports, vec![(10, 0x1000, None), (12, 0x3000, None)]);
+        assert!(table.invalid_name_ordinals.is_empty());
+        assert_eq!(table.ordinal_only_count(), 2);
+        assert_eq!(table.name_at(&pe_file, &sections, 0), None);
+        assert!(table.names_are_sorted(&pe_file, &sections));
+    }
+
+    #[test]
+    fn aliases() {
+        let mut pe_file = vec![0u8; 0x10];
+        for address in [0x1000u32, 0x2000, 0x1000] {
+            pe_file.extend_from_slice(&address.to_le_bytes());
+        }
+        pe_file.resize(0x20, 0);
+        for name_rva in [0x40u32, 0x48, 0x50, 0x58] {
+            pe_file.extend_from_slice(&name_rva.to_le_bytes());
+        }
+        // Alpha and Delta share the first slot, Gamma is in another slot at the same address
+        for ordinal in [0u16, 1, 2, 0] {
+            pe_file.extend_from_slice(&ordinal.to_le_bytes());
+        }
+        pe_file.resize(0x40, 0);
+        pe_file.extend_from_slice(b"Alpha\0\0\0Beta\0\0\0\0Gamma\0\0\0Delta\0");
+        pe_file.resize(0x100, 0);
+        let sections = Sections::new(vec![Section::for_test(".edata", 0, 0x100, 0, 0x100, 0)], 0);
+        let mut table = table(1, 3);
+        table.num_of_names = 4;
+        table.addr_of_funcs = 0x10;
+        table.addr_of_names = 0x20;
+        table.addr_of_name_ordi = 0x30;
+        assert_eq!(
+            table.aliases_of(0x1000, &pe_file, &sections),
+            vec!["Alpha", "Gamma", "Delta"]
+        );
+        assert_eq!(table.aliases_of(0x2000, &pe_file, &sections), vec!["Beta"]);
+        assert!(table.aliases_of(0x3000, &pe_file, &sections).is_empty());
+    }
+
+    #[test]
+    fn sorted_names() {
+        let mut pe_file = vec![];
+        for name_rva in [0x20u32, 0x28, 0x30] {
+            pe_file.extend_from_slice(&name_rva.to_le_bytes());
+        }
+        pe_file.resize(0x20, 0);
+        pe_file.extend_from_slice(b"Alpha\0\0\0Beta\0\0\0\0alpha\0");
+        pe_file.resize(0x100, 0);
+        let sections = Sections::new(vec![Section::for_test(".edata", 0, 0x100, 0, 0x100, 0)], 0);
+        // uppercase letters sort before lowercase ones
+        let mut table = table(1, 3);
+        table.num_of_names = 3;
+        assert!(table.names_are_sorted(&pe_file, &sections));
+
+        pe_file[0..4].copy_from_slice(&0x30u32.to_le_bytes());
+        pe_file[8..12].copy_from_slice(&0x20u32.to_le_bytes());
+        assert!(!table.names_are_sorted(&pe_file, &sections));
     }
 }
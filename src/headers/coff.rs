@@ -0,0 +1,149 @@
+use crate::parse;
+use nom::error::context;
+use nom::number::complete::{le_u16, le_u32};
+use nom::sequence::tuple;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// An IMAGE_LINENUMBER record of a section, found in object files and old debug builds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoffLineNumber {
+    /// The beginning of a function, whose line numbers follow this record
+    Function { symbol_index: u32 },
+    /// A line relative to the beginning of the function and the address of its code
+    Line {
+        virtual_address: u32,
+        line_number: u16,
+    },
+}
+
+impl CoffLineNumber {
+    pub const SIZE: usize = 6;
+
+    pub fn parse(i: parse::Input) -> parse::Result<Self> {
+        let (i, (symbol_index_or_address, line_number)) = tuple((
+            context("SymbolTableIndex/VirtualAddress", le_u32),
+            context("Linenumber", le_u16),
+        ))(i)?;
+        // a zero line number marks the record of a function
+        let line_number = if line_number == 0 {
+            Self::Function {
+                symbol_index: symbol_index_or_address,
+            }
+        } else {
+            Self::Line {
+                virtual_address: symbol_index_or_address,
+                line_number,
+            }
+        };
+        Ok((i, line_number))
+    }
+
+    /// Parses `count` records at the offset, stopping at the end of the file
+    pub fn parse_array(pe_file: &[u8], offset: usize, count: usize) -> Vec<Self> {
+        let mut i = pe_file.get(offset..).unwrap_or_default();
+        let mut line_numbers = Vec::with_capacity(count.min(i.len() / Self::SIZE));
+        for _ in 0..count {
+            match Self::parse(i) {
+                Ok((next, line_number)) => {
+                    line_numbers.push(line_number);
+                    i = next;
+                }
+                Err(_) => break,
+            }
+        }
+        line_numbers
+    }
+}
+
+/// An IMAGE_RELOCATION record of a section, which is only found in object files.
+/// Images use base relocations instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoffRelocation {
+    pub virtual_address: u32, // the address of the item to be relocated
+    pub symbol_index: u32,    // the index of the symbol of the target in the symbol table
+    pub typ: u16,             // machine specific type of the relocation
+}
+
+impl CoffRelocation {
+    pub const SIZE: usize = 10;
+
+    pub fn parse(i: parse::Input) -> parse::Result<Self> {
+        let (i, (virtual_address, symbol_index, typ)) = tuple((
+            context("VirtualAddress", le_u32),
+            context("SymbolTableIndex", le_u32),
+            context("Type", le_u16),
+        ))(i)?;
+        Ok((
+            i,
+            Self {
+                virtual_address,
+                symbol_index,
+                typ,
+            },
+        ))
+    }
+
+    /// Parses `count` records at the offset, stopping at the end of the file
+    pub fn parse_array(pe_file: &[u8], offset: usize, count: usize) -> Vec<Self> {
+        let mut i = pe_file.get(offset..).unwrap_or_default();
+        let mut relocations = Vec::with_capacity(count.min(i.len() / Self::SIZE));
+        for _ in 0..count {
+            match Self::parse(i) {
+                Ok((next, relocation)) => {
+                    relocations.push(relocation);
+                    i = next;
+                }
+                Err(_) => break,
+            }
+        }
+        relocations
+    }
+}
+
+impl fmt::Display for CoffRelocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:#x}: symbol {}, type {}",
+            self.virtual_address, self.symbol_index, self.typ
+        )
+    }
+}
+
+impl fmt::Display for CoffLineNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Function { symbol_index } => write!(f, "function: symbol {}", symbol_index),
+            Self::Line {
+                virtual_address,
+                line_number,
+            } => write!(f, "line {}: {:#x}", line_number, virtual_address),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CoffLineNumber;
+
+    #[test]
+    fn line_numbers() {
+        let data: &[u8] = &[
+            0xff, 0xff, // preceding data
+            7, 0, 0, 0, 0, 0, // function of symbol 7
+            0x10, 0x10, 0, 0, 3, 0, // line 3 at 0x1010
+            0x20, 0x10, // truncated
+        ];
+        assert_eq!(
+            CoffLineNumber::parse_array(data, 2, 3),
+            vec![
+                CoffLineNumber::Function { symbol_index: 7 },
+                CoffLineNumber::Line {
+                    virtual_address: 0x1010,
+                    line_number: 3
+                }
+            ]
+        );
+    }
+}
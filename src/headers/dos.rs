@@ -1,8 +1,9 @@
-use crate::parse;
+use crate::headers::rich::RichHeader;
+use crate::options::ParseContext;
+use crate::{errors, parse};
 use nom::number::complete::le_u32;
 use nom::{
     bytes::complete::{tag, take},
-    error::context,
     number::complete::le_u16,
     sequence::tuple,
 };
@@ -31,12 +32,14 @@ pub struct DosHeader<'a> {
     pub oeminfo: u16,
     pub res2: &'a [u8],
     pub lfanew: u32,
+    pub stub: &'a [u8],
 }
 
 impl<'a> DosHeader<'a> {
     const MAGIC: &'static [u8] = &[0x4d, 0x5a];
+    pub const SIZE: usize = 64;
 
-    pub fn parse(i: parse::Input<'a>) -> parse::Result<Self> {
+    pub fn parse(pe_file: parse::Input<'a>, ctx: &ParseContext) -> parse::Result<'a, Self> {
         let (
             i,
             (
@@ -59,30 +62,50 @@ impl<'a> DosHeader<'a> {
                 oeminfo,
                 res2,
                 lfanew,
-                _,
             ),
         ) = tuple((
-            context("magic", tag(Self::MAGIC)),
-            context("cblp", le_u16),
-            context("cp", le_u16),
-            context("crlc", le_u16),
-            context("cparhdr", le_u16),
-            context("minalloc", le_u16),
-            context("maxalloc", le_u16),
-            context("ss", le_u16),
-            context("sp", le_u16),
-            context("csum", le_u16),
-            context("ip", le_u16),
-            context("cs", le_u16),
-            context("lfarlc", le_u16),
-            context("ovno", le_u16),
-            context("res", take(8usize)),
-            context("oemid", le_u16),
-            context("oeminfo", le_u16),
-            context("res2", take(20usize)),
-            context("lfanew", le_u32),
-            context("stub", take(64usize)),
-        ))(i)?;
+            ctx.field("magic", tag(Self::MAGIC)),
+            ctx.field("cblp", le_u16),
+            ctx.field("cp", le_u16),
+            ctx.field("crlc", le_u16),
+            ctx.field("cparhdr", le_u16),
+            ctx.field("minalloc", le_u16),
+            ctx.field("maxalloc", le_u16),
+            ctx.field("ss", le_u16),
+            ctx.field("sp", le_u16),
+            ctx.field("csum", le_u16),
+            ctx.field("ip", le_u16),
+            ctx.field("cs", le_u16),
+            ctx.field("lfarlc", le_u16),
+            ctx.field("ovno", le_u16),
+            ctx.field("res", take(8usize)),
+            ctx.field("oemid", le_u16),
+            ctx.field("oeminfo", le_u16),
+            ctx.field("res2", take(20usize)),
+            ctx.field("lfanew", le_u32),
+        ))(pe_file)?;
+
+        // the DOS stub fills the region between the DOS header and the NT header.
+        // The Windows loader accepts an NT header overlapping the DOS header, e.g. in tiny PE files,
+        // but tools disagree on which fields win, so the overlap is rejected rather than guessed.
+        let stub_size = lfanew.checked_sub(Self::SIZE as u32).ok_or_else(|| {
+            errors::PEError::from_string(
+                i,
+                format!(
+                    "lfanew {} points inside the DOS header, overlapping it with the NT header",
+                    lfanew
+                ),
+            )
+        })?;
+        if lfanew as usize > pe_file.len() {
+            return Err(errors::PEError::offset_out_of_bounds(
+                i,
+                lfanew as usize,
+                pe_file.len(),
+                "lfanew",
+            ));
+        }
+        let (i, stub) = ctx.field("stub", take(stub_size as usize))(i)?;
 
         let dos_header = Self {
             magic,
@@ -104,13 +127,44 @@ impl<'a> DosHeader<'a> {
             oeminfo,
             res2,
             lfanew,
+            stub,
         };
         Ok((i, dos_header))
     }
+
+    /// The 4 reserved words `e_res`
+    pub fn reserved1(&self) -> &[u8] {
+        self.res
+    }
+
+    /// The 10 reserved words `e_res2`
+    pub fn reserved2(&self) -> &[u8] {
+        self.res2
+    }
+
+    /// Whether the reserved words are not all zero, which linkers never write
+    /// and may indicate a watermark or data injected by a tool
+    pub fn has_nonstandard_reserved(&self) -> bool {
+        self.res.iter().chain(self.res2).any(|byte| *byte != 0)
+    }
+
+    /// Whether the DOS stub has a Rich header, the undocumented record of the tools
+    /// written by the Microsoft linker which ends with the "Rich" marker
+    pub fn has_rich_header(&self) -> bool {
+        self.stub.windows(4).any(|window| window == b"Rich")
+    }
+
+    /// The Rich header parsed from the DOS stub, which follows the DOS header
+    pub fn rich_header(&self) -> Option<RichHeader> {
+        RichHeader::parse(self.stub, Self::SIZE)
+    }
 }
 
 impl<'a> fmt::Display for DosHeader<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            return writeln!(f, "DosHeader: lfanew: {:#x}", self.lfanew);
+        }
         writeln!(f, "DosHeader:")?;
         writeln!(f, "  Magic number: {:?}", self.magic)?;
         writeln!(f, "  Bytes on last page of file: {}", self.cblp)?;
@@ -133,3 +187,29 @@ impl<'a> fmt::Display for DosHeader<'a> {
         writeln!(f, "  File address of new exe header: {:?}", self.lfanew)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::DosHeader;
+    use crate::errors::ErrorKind;
+    use crate::options::ParseContext;
+
+    #[test]
+    fn overlapping_nt_header() {
+        let mut pe_file = vec![0u8; 0x80];
+        pe_file[..2].copy_from_slice(b"MZ");
+        pe_file[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        assert!(DosHeader::parse(&pe_file, &ParseContext::default()).is_ok());
+
+        // the NT header would start at e_lfarlc
+        pe_file[0x3c..0x40].copy_from_slice(&0x18u32.to_le_bytes());
+        let err = match DosHeader::parse(&pe_file, &ParseContext::default()) {
+            Err(nom::Err::Failure(err)) => err,
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        };
+        assert!(matches!(
+            &err.errors[0].1,
+            ErrorKind::String(message) if message.contains("overlapping")
+        ));
+    }
+}
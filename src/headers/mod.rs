@@ -1,8 +1,11 @@
 use std::fmt;
 
-use crate::parse;
+use crate::options::ParseContext;
+use crate::{errors, parse};
+pub mod coff;
 pub mod dos;
 pub mod nt;
+pub mod rich;
 pub mod sections;
 
 #[derive(Debug)]
@@ -13,11 +16,39 @@ pub struct PEHeader<'a> {
 }
 
 impl<'a> PEHeader<'a> {
-    pub fn parse(pe_file: parse::Input<'a>) -> parse::Result<Self> {
-        let (_, dos_header) = dos::DosHeader::parse(pe_file)?;
-        // lfanew value is the offset of the PE signature
-        let (i, nt_header) = nt::NTHeader::parse(&pe_file[dos_header.lfanew as usize..])?;
-        let (i, sections) = sections::Sections::parse(i, nt_header.file_header.num_of_sections)?;
+    pub fn parse(pe_file: parse::Input<'a>, ctx: &ParseContext) -> parse::Result<'a, Self> {
+        let (_, dos_header) = dos::DosHeader::parse(pe_file, ctx)?;
+        // lfanew value is the offset of the PE signature, which `DosHeader::parse` checked is within the file
+        let nt_header_data = &pe_file[dos_header.lfanew as usize..];
+        let (i, nt_header) = nt::NTHeader::parse(nt_header_data, dos_header.lfanew as usize, ctx)?;
+        let num_of_sections = nt_header.file_header.num_of_sections;
+        if num_of_sections as usize > nt_header.max_num_of_sections() {
+            return Err(errors::PEError::from_string(
+                i,
+                format!(
+                    "number of sections {} does not fit in the headers (at most {})",
+                    num_of_sections,
+                    nt_header.max_num_of_sections()
+                ),
+            ));
+        }
+        // the section table follows the optional header of size_of_optional_header bytes,
+        // which may be padded after the data directories
+        let section_table_offset = nt_header.section_table_offset();
+        let section_table_data = pe_file.get(section_table_offset..).ok_or_else(|| {
+            errors::PEError::offset_out_of_bounds(
+                i,
+                section_table_offset,
+                pe_file.len(),
+                "section table",
+            )
+        })?;
+        let (i, sections) = sections::Sections::parse(
+            section_table_data,
+            num_of_sections,
+            section_table_offset,
+            ctx,
+        )?;
         Ok((
             i,
             Self {
@@ -29,10 +60,164 @@ impl<'a> PEHeader<'a> {
     }
 }
 
+impl PEHeader<'_> {
+    /// The size of the file implied by the headers, see `PE::declared_size`
+    pub fn declared_size(&self) -> u32 {
+        self.sections
+            .iter()
+            .filter(|section| section.size_of_raw_data != 0)
+            .map(|section| {
+                section
+                    .ptr_to_raw_data
+                    .saturating_add(section.size_of_raw_data)
+            })
+            .fold(self.nt_header.optional_header.size_of_headers(), u32::max)
+    }
+}
+
 impl<'a> fmt::Display for PEHeader<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            writeln!(f, "{:#}", self.dos_header)?;
+            writeln!(f, "{:#}", self.nt_header)?;
+            return writeln!(f, "{:#}", self.sections);
+        }
         writeln!(f, "{}", self.dos_header)?;
         writeln!(f, "{}", self.nt_header)?;
         writeln!(f, "{}", self.sections)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::PEHeader;
+    use crate::errors::ErrorKind;
+    use crate::options::ParseContext;
+
+    #[test]
+    fn padded_optional_header() {
+        let mut pe_file = vec![0u8; 0x40];
+        pe_file[..2].copy_from_slice(b"MZ");
+        pe_file[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        pe_file.extend_from_slice(b"PE\0\0");
+        // FileHeader with an optional header padded by 16 bytes after the data directories
+        pe_file.extend_from_slice(&0x14cu16.to_le_bytes());
+        pe_file.extend_from_slice(&1u16.to_le_bytes());
+        pe_file.extend_from_slice(&[0; 12]);
+        pe_file.extend_from_slice(&(0xe0u16 + 16).to_le_bytes());
+        pe_file.extend_from_slice(&0x102u16.to_le_bytes());
+        let mut optional_header = vec![0u8; 0xe0];
+        optional_header[..2].copy_from_slice(&0x10bu16.to_le_bytes());
+        optional_header[36..40].copy_from_slice(&0x200u32.to_le_bytes());
+        optional_header[60..64].copy_from_slice(&0x400u32.to_le_bytes());
+        optional_header[92..96].copy_from_slice(&16u32.to_le_bytes());
+        pe_file.extend_from_slice(&optional_header);
+        pe_file.extend_from_slice(&[0xff; 16]);
+        let mut section = vec![0u8; 40];
+        section[..5].copy_from_slice(b".text");
+        pe_file.extend_from_slice(&section);
+        pe_file.resize(0x400, 0);
+
+        let (_, header) = PEHeader::parse(&pe_file, &ParseContext::default()).unwrap();
+        assert_eq!(header.sections.table_offset(), 0x40 + 4 + 20 + 0xe0 + 16);
+        let text = header.sections.iter().next().unwrap();
+        assert_eq!(text.name, ".text");
+        assert_eq!(text.raw_header(&pe_file), &section[..]);
+        let optional_header = &header.nt_header.optional_header;
+        assert_eq!(
+            optional_header.data_directories_offset(),
+            0x40 + 4 + 20 + 96
+        );
+        assert_eq!(
+            optional_header.data_directories_raw(&pe_file),
+            &pe_file[0x40 + 4 + 20 + 96..][..16 * 8]
+        );
+    }
+
+    #[test]
+    fn truncated_data_directories() {
+        let mut pe_file = vec![0u8; 0x40];
+        pe_file[..2].copy_from_slice(b"MZ");
+        pe_file[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        pe_file.extend_from_slice(b"PE\0\0");
+        // 16 directories are declared but the optional header only has room for 10 of them
+        pe_file.extend_from_slice(&0x14cu16.to_le_bytes());
+        pe_file.extend_from_slice(&1u16.to_le_bytes());
+        pe_file.extend_from_slice(&[0; 12]);
+        pe_file.extend_from_slice(&(96u16 + 10 * 8).to_le_bytes());
+        pe_file.extend_from_slice(&0x102u16.to_le_bytes());
+        let mut optional_header = vec![0u8; 96 + 10 * 8];
+        optional_header[..2].copy_from_slice(&0x10bu16.to_le_bytes());
+        optional_header[36..40].copy_from_slice(&0x200u32.to_le_bytes());
+        optional_header[60..64].copy_from_slice(&0x400u32.to_le_bytes());
+        optional_header[92..96].copy_from_slice(&16u32.to_le_bytes());
+        pe_file.extend_from_slice(&optional_header);
+        let mut section = vec![0u8; 40];
+        section[..5].copy_from_slice(b".text");
+        pe_file.extend_from_slice(&section);
+        pe_file.resize(0x400, 0);
+
+        let (_, header) = PEHeader::parse(&pe_file, &ParseContext::default()).unwrap();
+        let optional_header = &header.nt_header.optional_header;
+        assert_eq!(optional_header.expected_directory_count(), 10);
+        assert_eq!(optional_header.data_directories().len(), 10);
+        assert_eq!(header.sections.iter().next().unwrap().name, ".text");
+    }
+
+    #[test]
+    fn nt_header_out_of_bounds() {
+        let mut pe_file = vec![0u8; 0x40];
+        pe_file[..2].copy_from_slice(b"MZ");
+        pe_file[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        // the file ends in the middle of the file header
+        pe_file.extend_from_slice(b"PE\0\0");
+        pe_file.extend_from_slice(&0x14cu16.to_le_bytes());
+
+        let err = match PEHeader::parse(&pe_file, &ParseContext::default()) {
+            Err(nom::Err::Failure(err)) => err,
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        };
+        assert!(matches!(
+            err.errors[0].1,
+            ErrorKind::OffsetOutOfBounds {
+                offset: 0x5a,
+                size: 0x46
+            }
+        ));
+        assert!(matches!(err.errors[1].1, ErrorKind::Context("NT header")));
+    }
+
+    #[test]
+    fn section_table_out_of_bounds() {
+        let mut pe_file = vec![0u8; 0x40];
+        pe_file[..2].copy_from_slice(b"MZ");
+        pe_file[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        pe_file.extend_from_slice(b"PE\0\0");
+        // the optional header claims to be 0x1000 bytes, which pushes the section table beyond the file
+        pe_file.extend_from_slice(&0x14cu16.to_le_bytes());
+        pe_file.extend_from_slice(&0u16.to_le_bytes());
+        pe_file.extend_from_slice(&[0; 12]);
+        pe_file.extend_from_slice(&0x1000u16.to_le_bytes());
+        pe_file.extend_from_slice(&0x102u16.to_le_bytes());
+        let mut optional_header = vec![0u8; 0xe0];
+        optional_header[..2].copy_from_slice(&0x10bu16.to_le_bytes());
+        optional_header[92..96].copy_from_slice(&16u32.to_le_bytes());
+        pe_file.extend_from_slice(&optional_header);
+
+        let err = match PEHeader::parse(&pe_file, &ParseContext::default()) {
+            Err(nom::Err::Failure(err)) => err,
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        };
+        assert!(matches!(
+            err.errors[0].1,
+            ErrorKind::OffsetOutOfBounds {
+                offset: 0x1058,
+                size: 0x138
+            }
+        ));
+        assert!(matches!(
+            err.errors[1].1,
+            ErrorKind::Context("section table")
+        ));
+    }
+}
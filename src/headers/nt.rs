@@ -1,10 +1,12 @@
+use crate::headers::sections::Section;
+use crate::options::ParseContext;
 use crate::{errors, parse};
 use chrono::{DateTime, Utc};
 use derive_try_from_primitive::TryFromPrimitive;
 use nom::{
     bytes::complete::tag,
     combinator::map_res,
-    error::{context, ErrorKind},
+    error::ErrorKind,
     number::complete::{be_u8, le_u16, le_u32},
     sequence::tuple,
 };
@@ -19,35 +21,86 @@ pub struct NTHeader<'a> {
     pub signature: &'a [u8],
     pub file_header: FileHeader,
     pub optional_header: OptionalHeader,
+    pub offset: usize, // file offset of the NT header, i.e. lfanew
 }
 
 impl<'a> NTHeader<'a> {
     const SIGNAUTRE: &'static [u8] = &[0x50, 0x45, 0x00, 0x00];
+    pub const SIZE_OF_SIGNATURE: usize = 4;
 
-    pub fn parse(i: parse::Input<'a>) -> parse::Result<Self> {
-        let (i, (signature,)) = tuple((context("Signature", tag(Self::SIGNAUTRE)),))(i)?;
+    /// offset is the file offset of the NT header, i.e. lfanew
+    pub fn parse(
+        i: parse::Input<'a>,
+        offset: usize,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Self> {
+        // the signature, the file header and the magic are read before knowing the size of the rest
+        let fixed_size = Self::SIZE_OF_SIGNATURE + FileHeader::SIZE + 2;
+        if i.len() < fixed_size {
+            return Err(errors::PEError::offset_out_of_bounds(
+                i,
+                offset + fixed_size,
+                offset + i.len(),
+                "NT header",
+            ));
+        }
+        let (i, signature) = ctx.field("Signature", tag(Self::SIGNAUTRE))(i)?;
 
-        let (i, file_header) = FileHeader::parse(i)?;
+        let (i, file_header) = FileHeader::parse(i, ctx)?;
 
-        let (i, magic) = OptionalHeaderMagic::parse(i)?;
+        let (i, magic) = ctx.field("Magic", OptionalHeaderMagic::parse)(i)?;
+        let size_of_fields = match magic {
+            OptionalHeaderMagic::Pe32Plus => OptionalHeader64::SIZE_OF_FIELDS,
+            _ => OptionalHeader32::SIZE_OF_FIELDS,
+        };
+        // the magic has been read, the rest of the fixed fields must follow it
+        if i.len() + 2 < size_of_fields {
+            return Err(errors::PEError::offset_out_of_bounds(
+                i,
+                offset + Self::SIZE_OF_SIGNATURE + FileHeader::SIZE + size_of_fields,
+                offset + fixed_size + i.len(),
+                "optional header",
+            ));
+        }
 
-        let (i, optional_header) = OptionalHeader::parse(i, magic)?;
+        let optional_header_offset = offset + Self::SIZE_OF_SIGNATURE + FileHeader::SIZE;
+        let (i, optional_header) = OptionalHeader::parse(
+            i,
+            magic,
+            optional_header_offset,
+            file_header.size_of_optional_header as usize,
+            ctx,
+        )?;
 
         let nt_header = NTHeader {
             signature,
             file_header,
             optional_header,
+            offset,
         };
 
         Ok((i, nt_header))
     }
+
+    /// the file offset of the section table which follows the optional header
+    pub fn section_table_offset(&self) -> usize {
+        self.optional_header.offset() + self.file_header.size_of_optional_header as usize
+    }
+
+    /// the maximum number of section headers fitting between the section table and size_of_headers
+    pub fn max_num_of_sections(&self) -> usize {
+        (self.optional_header.size_of_headers() as usize)
+            .saturating_sub(self.section_table_offset())
+            / Section::SIZE
+    }
 }
 
 #[derive(Debug)]
 pub struct FileHeader {
     pub machine: Machine,
     pub num_of_sections: u16,
-    pub datetime: DateTime<Utc>,
+    pub time_date_stamp: u32,
+    pub datetime: DateTime<Utc>, // the Unix epoch when timestamps are not decoded
     pub ptr_to_sym_tbl: u32,
     pub num_of_syms: u32,
     pub size_of_optional_header: u16,
@@ -56,37 +109,66 @@ pub struct FileHeader {
 }
 
 impl FileHeader {
-    pub fn parse(i: parse::Input) -> parse::Result<Self> {
-        let (i, machine) = Machine::parse(i)?;
+    pub const SIZE: usize = 20;
+
+    /// Whether the flag is set in the characteristics
+    pub fn has_characteristic(&self, flag: FileCharacteristic) -> bool {
+        self.characteristics & flag as u16 != 0
+    }
+
+    /// Whether the obsolete `BytesReversedLo` or `BytesReversedHi` flag is set,
+    /// which marked images of historical big-endian platforms.
+    /// Byte-reversed images are not supported: the fields are still read as little endian.
+    pub fn bytes_reversed(&self) -> bool {
+        self.has_characteristic(FileCharacteristic::BytesReversedLo)
+            || self.has_characteristic(FileCharacteristic::BytesReversedHi)
+    }
+
+    /// The characteristics as read, including the reserved bits `characteristic_flags` leaves out
+    pub fn raw_characteristics(&self) -> u16 {
+        self.characteristics
+    }
+
+    /// The flags set in the characteristics, e.g. to match on `FileCharacteristic::Dll`
+    pub fn characteristic_flags(&self) -> Vec<FileCharacteristic> {
+        FileCharacteristic::flags(self.characteristics)
+    }
+
+    pub fn parse<'a>(i: parse::Input<'a>, ctx: &ParseContext) -> parse::Result<'a, Self> {
+        let (i, machine) = ctx.field("Machine", Machine::parse)(i)?;
 
         let (
             i,
             (
                 num_of_sections,
-                timestamp,
+                time_date_stamp,
                 ptr_to_sym_tbl,
                 num_of_syms,
                 size_of_optional_header,
                 characteristics,
             ),
         ) = tuple((
-            context("NumOfSections", le_u16),
-            context("Timestamp", le_u32),
-            context("PtrToSymbolTable", le_u32),
-            context("NumOfSymbols", le_u32),
-            context("SizeOfHeader", le_u16),
-            context("Characteristics", le_u16),
+            ctx.field("NumOfSections", le_u16),
+            ctx.field("Timestamp", le_u32),
+            ctx.field("PtrToSymbolTable", le_u32),
+            ctx.field("NumOfSymbols", le_u32),
+            ctx.field("SizeOfHeader", le_u16),
+            ctx.field("Characteristics", le_u16),
         ))(i)?;
 
-        let timestamp = chrono::NaiveDateTime::from_timestamp_opt(timestamp as i64, 0)
-            .ok_or(errors::PEError::from_string(i, "wrong timestamp format"))?;
-        let datetime: DateTime<Utc> = DateTime::from_utc(timestamp, Utc);
+        let datetime = if ctx.decode_timestamps() {
+            DateTime::<Utc>::from_timestamp(time_date_stamp as i64, 0)
+                .ok_or(errors::PEError::from_string(i, "wrong timestamp format"))?
+        } else {
+            DateTime::<Utc>::default()
+        };
 
         Ok((
             i,
             FileHeader {
                 machine,
                 num_of_sections,
+                time_date_stamp,
                 datetime,
                 ptr_to_sym_tbl,
                 num_of_syms,
@@ -139,6 +221,28 @@ impl Machine {
             Err(_) => Err(ErrorKind::Alt),
         })(i)
     }
+
+    /// The architecture component of the Rust/LLVM target triple, e.g. "x86_64" for AMD64,
+    /// or "unknown" for machines without a clear counterpart
+    pub fn target_arch(&self) -> &'static str {
+        match self {
+            Machine::Amd64 => "x86_64",
+            Machine::I386 => "i686",
+            Machine::Arm64 => "aarch64",
+            Machine::Arm | Machine::Armnt | Machine::Thumb => "arm",
+            Machine::RiscV32 => "riscv32",
+            Machine::RiscV64 => "riscv64",
+            Machine::LoongArch32 => "loongarch32",
+            Machine::LoongArch64 => "loongarch64",
+            Machine::PowerPc | Machine::PowerPcfp => "powerpc",
+            Machine::Mips16
+            | Machine::MipsFpu
+            | Machine::MipsFpu16
+            | Machine::R4000
+            | Machine::WceMipsV2 => "mips",
+            _ => "unknown",
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -148,14 +252,21 @@ pub enum OptionalHeader {
 }
 
 impl OptionalHeader {
-    pub fn parse(i: parse::Input, magic: OptionalHeaderMagic) -> parse::Result<Self> {
+    /// offset is the file offset of the optional header and size is size_of_optional_header
+    pub fn parse<'a>(
+        i: parse::Input<'a>,
+        magic: OptionalHeaderMagic,
+        offset: usize,
+        size: usize,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Self> {
         match magic {
             OptionalHeaderMagic::Pe32 => {
-                let (i, optional_header) = OptionalHeader32::parse(i)?;
+                let (i, optional_header) = OptionalHeader32::parse(i, offset, size, ctx)?;
                 Ok((i, OptionalHeader::Op32(optional_header)))
             }
             OptionalHeaderMagic::Pe32Plus => {
-                let (i, optional_header) = OptionalHeader64::parse(i)?;
+                let (i, optional_header) = OptionalHeader64::parse(i, offset, size, ctx)?;
                 Ok((i, OptionalHeader::Op64(optional_header)))
             }
             OptionalHeaderMagic::Rom => Err(errors::PEError::from_string(
@@ -165,12 +276,224 @@ impl OptionalHeader {
         }
     }
 
+    /// Whether the image is PE32+, whose thunks and some fields are 8 bytes
+    pub fn is_64bit(&self) -> bool {
+        matches!(self, Self::Op64(_))
+    }
+
+    pub fn magic(&self) -> OptionalHeaderMagic {
+        match self {
+            Self::Op32(ref op_header) => op_header.magic,
+            Self::Op64(ref op_header) => op_header.magic,
+        }
+    }
+
     pub fn find_directory_by_entry(&self, entry: DirectoryEntry) -> Option<DataDirectory> {
         match self {
             Self::Op32(ref op_header) => op_header.data_directories.find_by_entry(entry),
             Self::Op64(ref op_header) => op_header.data_directories.find_by_entry(entry),
         }
     }
+
+    pub fn data_directories(&self) -> &DataDirectories {
+        match self {
+            Self::Op32(ref op_header) => &op_header.data_directories,
+            Self::Op64(ref op_header) => &op_header.data_directories,
+        }
+    }
+
+    pub fn file_alignment(&self) -> u32 {
+        match self {
+            Self::Op32(ref op_header) => op_header.file_alignment,
+            Self::Op64(ref op_header) => op_header.file_alignment,
+        }
+    }
+
+    /// the major and the minor version of the linker which produced the image
+    pub fn linker_version(&self) -> (u8, u8) {
+        match self {
+            Self::Op32(ref op_header) => (
+                op_header.major_linker_version,
+                op_header.minor_linker_version,
+            ),
+            Self::Op64(ref op_header) => (
+                op_header.major_linker_version,
+                op_header.minor_linker_version,
+            ),
+        }
+    }
+
+    /// the subsystem required to run the image, or None for values unknown to the parser
+    pub fn subsystem(&self) -> Option<Subsystem> {
+        let sub_system = match self {
+            Self::Op32(ref op_header) => op_header.sub_system,
+            Self::Op64(ref op_header) => op_header.sub_system,
+        };
+        Subsystem::try_from(sub_system).ok()
+    }
+
+    pub fn dll_characteristics(&self) -> u16 {
+        match self {
+            Self::Op32(ref op_header) => op_header.dll_characteristics,
+            Self::Op64(ref op_header) => op_header.dll_characteristics,
+        }
+    }
+
+    /// The flags set in the DLL characteristics, e.g. `DllCharacteristic::DynamicBase` for ASLR
+    pub fn dll_characteristic_flags(&self) -> Vec<DllCharacteristic> {
+        DllCharacteristic::flags(self.dll_characteristics())
+    }
+
+    /// the obsolete loader flags, reserved and zero in valid images
+    pub fn loader_flags(&self) -> u32 {
+        match self {
+            Self::Op32(ref op_header) => op_header.loader_flags,
+            Self::Op64(ref op_header) => op_header.loader_flags,
+        }
+    }
+
+    /// Whether the reserved loader flags are set, which the loader ignores but linkers never do
+    pub fn loader_flags_nonzero(&self) -> bool {
+        self.loader_flags() != 0
+    }
+
+    /// the sizes of the stack of the main thread and of the default process heap, widened to u64 for PE32
+    pub fn memory_config(&self) -> MemoryConfig {
+        match self {
+            Self::Op32(ref op_header) => MemoryConfig {
+                stack_reserve: op_header.size_of_stack_reserve as u64,
+                stack_commit: op_header.size_of_stack_commit as u64,
+                heap_reserve: op_header.size_of_heap_reserve as u64,
+                heap_commit: op_header.size_of_heap_commit as u64,
+            },
+            Self::Op64(ref op_header) => MemoryConfig {
+                stack_reserve: op_header.size_of_stack_reserve,
+                stack_commit: op_header.size_of_stack_commit,
+                heap_reserve: op_header.size_of_heap_reserve,
+                heap_commit: op_header.size_of_heap_commit,
+            },
+        }
+    }
+
+    /// the preferred load address, widened to u64 for PE32
+    pub fn image_base(&self) -> u64 {
+        match self {
+            Self::Op32(ref op_header) => op_header.image_base as u64,
+            Self::Op64(ref op_header) => op_header.image_base,
+        }
+    }
+
+    pub fn address_of_entry_point(&self) -> u32 {
+        match self {
+            Self::Op32(ref op_header) => op_header.address_of_entry_point,
+            Self::Op64(ref op_header) => op_header.address_of_entry_point,
+        }
+    }
+
+    pub fn section_alignment(&self) -> u32 {
+        match self {
+            Self::Op32(ref op_header) => op_header.section_of_alignment,
+            Self::Op64(ref op_header) => op_header.section_of_alignment,
+        }
+    }
+
+    /// The checksum of the image, zero when the linker didn't compute it
+    pub fn check_sum(&self) -> u32 {
+        match self {
+            Self::Op32(ref op_header) => op_header.check_sum,
+            Self::Op64(ref op_header) => op_header.check_sum,
+        }
+    }
+
+    pub fn size_of_image(&self) -> u32 {
+        match self {
+            Self::Op32(ref op_header) => op_header.size_of_image,
+            Self::Op64(ref op_header) => op_header.size_of_image,
+        }
+    }
+
+    pub fn size_of_headers(&self) -> u32 {
+        match self {
+            Self::Op32(ref op_header) => op_header.size_of_headers,
+            Self::Op64(ref op_header) => op_header.size_of_headers,
+        }
+    }
+
+    /// the file offset of the optional header
+    pub fn offset(&self) -> usize {
+        match self {
+            Self::Op32(ref op_header) => op_header.offset,
+            Self::Op64(ref op_header) => op_header.offset,
+        }
+    }
+
+    /// the size of the optional header declared by size_of_optional_header
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Op32(ref op_header) => op_header.size,
+            Self::Op64(ref op_header) => op_header.size,
+        }
+    }
+
+    /// Returns the optional header bytes exactly as they appear in the file,
+    /// so that patching tools can modify specific fields while preserving the rest byte-for-byte.
+    /// The slice is truncated when the file is shorter than the declared size.
+    pub fn raw_bytes<'a>(&self, pe_file: &'a [u8]) -> &'a [u8] {
+        let start = self.offset().min(pe_file.len());
+        let end = self.offset().saturating_add(self.size()).min(pe_file.len());
+        &pe_file[start..end]
+    }
+
+    /// the file offset of the data directories, which follow the fixed fields of the optional header
+    pub fn data_directories_offset(&self) -> usize {
+        match self {
+            Self::Op32(ref op_header) => op_header.offset + OptionalHeader32::SIZE_OF_FIELDS,
+            Self::Op64(ref op_header) => op_header.offset + OptionalHeader64::SIZE_OF_FIELDS,
+        }
+    }
+
+    /// The number of data directories parsed: number_of_rva_and_sizes, or fewer when they don't all fit
+    /// in size_of_optional_header after the fixed fields, so that the directories aren't read from the section table
+    pub fn expected_directory_count(&self) -> usize {
+        match self {
+            Self::Op32(ref op_header) => directory_count(
+                op_header.number_of_rva_and_sizes,
+                op_header.size,
+                OptionalHeader32::SIZE_OF_FIELDS,
+            ),
+            Self::Op64(ref op_header) => directory_count(
+                op_header.number_of_rva_and_sizes,
+                op_header.size,
+                OptionalHeader64::SIZE_OF_FIELDS,
+            ),
+        }
+    }
+
+    /// Returns the data directories bytes exactly as they appear in the file,
+    /// e.g. to set the RVA and the size of a directory in place.
+    /// The slice covers `expected_directory_count` entries and is truncated at the end of the file.
+    pub fn data_directories_raw<'a>(&self, pe_file: &'a [u8]) -> &'a [u8] {
+        let size = self.data_directories().len() * DataDirectory::SIZE;
+        let start = self.data_directories_offset().min(pe_file.len());
+        let end = start.saturating_add(size).min(pe_file.len());
+        &pe_file[start..end]
+    }
+}
+
+/// The reserved and committed sizes set by the linker, e.g. with `/STACK:reserve,commit`.
+/// The stack sizes apply to the main thread and to the threads created without an explicit size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryConfig {
+    pub stack_reserve: u64,
+    pub stack_commit: u64,
+    pub heap_reserve: u64,
+    pub heap_commit: u64,
+}
+
+/// number_of_rva_and_sizes capped by the number of directories fitting in the optional header
+fn directory_count(number_of_rva_and_sizes: u32, size: usize, size_of_fields: usize) -> usize {
+    (number_of_rva_and_sizes as usize)
+        .min(size.saturating_sub(size_of_fields) / DataDirectory::SIZE)
 }
 
 #[derive(Debug)]
@@ -206,10 +529,20 @@ pub struct OptionalHeader32 {
     pub loader_flags: u32,
     pub number_of_rva_and_sizes: u32,
     pub data_directories: DataDirectories,
+    pub offset: usize, // file offset of the optional header
+    pub size: usize,   // size_of_optional_header
 }
 
 impl OptionalHeader32 {
-    pub fn parse(i: parse::Input) -> parse::Result<Self> {
+    /// the size of the fields preceding the data directories
+    pub const SIZE_OF_FIELDS: usize = 96;
+
+    pub fn parse<'a>(
+        i: parse::Input<'a>,
+        offset: usize,
+        size: usize,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Self> {
         let (
             i,
             (
@@ -232,23 +565,23 @@ impl OptionalHeader32 {
                 minor_sub_system_version,
             ),
         ) = tuple((
-            context("MajorLinkerVersion", be_u8),
-            context("MinorLinkerVersion", be_u8),
-            context("SizeOfCode", le_u32),
-            context("SizeOfInitializedCode", le_u32),
-            context("SizeOfUninitializedCode", le_u32),
-            context("AddressOfEntryPoint", le_u32),
-            context("BaseOfCode", le_u32),
-            context("BaseOfData", le_u32),
-            context("ImageBase", le_u32),
-            context("SectionOfAlignment", le_u32),
-            context("FileAlignment", le_u32),
-            context("MajorOperatingSystemVersion", le_u16),
-            context("MinorOperatingSystemVersion", le_u16),
-            context("MajorImageVersion", le_u16),
-            context("MinorImageVersion", le_u16),
-            context("MajorSubSystemVersion", le_u16),
-            context("MinorSubSystemVersion", le_u16),
+            ctx.field("MajorLinkerVersion", be_u8),
+            ctx.field("MinorLinkerVersion", be_u8),
+            ctx.field("SizeOfCode", le_u32),
+            ctx.field("SizeOfInitializedCode", le_u32),
+            ctx.field("SizeOfUninitializedCode", le_u32),
+            ctx.field("AddressOfEntryPoint", le_u32),
+            ctx.field("BaseOfCode", le_u32),
+            ctx.field("BaseOfData", le_u32),
+            ctx.field("ImageBase", le_u32),
+            ctx.field("SectionOfAlignment", le_u32),
+            ctx.field("FileAlignment", le_u32),
+            ctx.field("MajorOperatingSystemVersion", le_u16),
+            ctx.field("MinorOperatingSystemVersion", le_u16),
+            ctx.field("MajorImageVersion", le_u16),
+            ctx.field("MinorImageVersion", le_u16),
+            ctx.field("MajorSubSystemVersion", le_u16),
+            ctx.field("MinorSubSystemVersion", le_u16),
         ))(i)?;
 
         let (
@@ -268,21 +601,25 @@ impl OptionalHeader32 {
                 number_of_rva_and_sizes,
             ),
         ) = tuple((
-            context("Win32VersionValue", le_u32),
-            context("SizeOfImage", le_u32),
-            context("SizeOfHeaders", le_u32),
-            context("CheckSum", le_u32),
-            context("Subsystem", le_u16),
-            context("DllCharacteristics", le_u16),
-            context("SizeOfStackReserve", le_u32),
-            context("SizeOfStackCommit", le_u32),
-            context("SizeOfHeapReserve", le_u32),
-            context("SizeOfHeapCommit", le_u32),
-            context("LoaderFlags", le_u32),
-            context("NumberOfRvaAndSizes", le_u32),
+            ctx.field("Win32VersionValue", le_u32),
+            ctx.field("SizeOfImage", le_u32),
+            ctx.field("SizeOfHeaders", le_u32),
+            ctx.field("CheckSum", le_u32),
+            ctx.field("Subsystem", le_u16),
+            ctx.field("DllCharacteristics", le_u16),
+            ctx.field("SizeOfStackReserve", le_u32),
+            ctx.field("SizeOfStackCommit", le_u32),
+            ctx.field("SizeOfHeapReserve", le_u32),
+            ctx.field("SizeOfHeapCommit", le_u32),
+            ctx.field("LoaderFlags", le_u32),
+            ctx.field("NumberOfRvaAndSizes", le_u32),
         ))(i)?;
 
-        let (i, data_directories) = DataDirectories::parse(i, number_of_rva_and_sizes as usize)?;
+        let (i, data_directories) = DataDirectories::parse(
+            i,
+            directory_count(number_of_rva_and_sizes, size, Self::SIZE_OF_FIELDS),
+            ctx,
+        )?;
 
         Ok((
             i,
@@ -318,6 +655,8 @@ impl OptionalHeader32 {
                 loader_flags,
                 number_of_rva_and_sizes,
                 data_directories,
+                offset,
+                size,
             },
         ))
     }
@@ -355,10 +694,20 @@ pub struct OptionalHeader64 {
     pub loader_flags: u32,
     pub number_of_rva_and_sizes: u32,
     pub data_directories: DataDirectories,
+    pub offset: usize, // file offset of the optional header
+    pub size: usize,   // size_of_optional_header
 }
 
 impl OptionalHeader64 {
-    pub fn parse(i: parse::Input) -> parse::Result<Self> {
+    /// the size of the fields preceding the data directories
+    pub const SIZE_OF_FIELDS: usize = 112;
+
+    pub fn parse<'a>(
+        i: parse::Input<'a>,
+        offset: usize,
+        size: usize,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Self> {
         let (
             i,
             (
@@ -380,22 +729,22 @@ impl OptionalHeader64 {
                 minor_sub_system_version,
             ),
         ) = tuple((
-            context("MajorLinkerVersion", be_u8),
-            context("MinorLinkerVersion", be_u8),
-            context("SizeOfCode", le_u32),
-            context("SizeOfInitializedCode", le_u32),
-            context("SizeOfUninitializedCode", le_u32),
-            context("AddressOfEntryPoint", le_u32),
-            context("BaseOfCode", le_u32),
-            context("ImageBase", le_u64),
-            context("SectionOfAlignment", le_u32),
-            context("FileAlignment", le_u32),
-            context("MajorOperatingSystemVersion", le_u16),
-            context("MinorOperatingSystemVersion", le_u16),
-            context("MajorImageVersion", le_u16),
-            context("MinorImageVersion", le_u16),
-            context("MajorSubSystemVersion", le_u16),
-            context("MinorSubSystemVersion", le_u16),
+            ctx.field("MajorLinkerVersion", be_u8),
+            ctx.field("MinorLinkerVersion", be_u8),
+            ctx.field("SizeOfCode", le_u32),
+            ctx.field("SizeOfInitializedCode", le_u32),
+            ctx.field("SizeOfUninitializedCode", le_u32),
+            ctx.field("AddressOfEntryPoint", le_u32),
+            ctx.field("BaseOfCode", le_u32),
+            ctx.field("ImageBase", le_u64),
+            ctx.field("SectionOfAlignment", le_u32),
+            ctx.field("FileAlignment", le_u32),
+            ctx.field("MajorOperatingSystemVersion", le_u16),
+            ctx.field("MinorOperatingSystemVersion", le_u16),
+            ctx.field("MajorImageVersion", le_u16),
+            ctx.field("MinorImageVersion", le_u16),
+            ctx.field("MajorSubSystemVersion", le_u16),
+            ctx.field("MinorSubSystemVersion", le_u16),
         ))(i)?;
 
         let (
@@ -415,21 +764,25 @@ impl OptionalHeader64 {
                 number_of_rva_and_sizes,
             ),
         ) = tuple((
-            context("Win32VersionValue", le_u32),
-            context("SizeOfImage", le_u32),
-            context("SizeOfHeaders", le_u32),
-            context("CheckSum", le_u32),
-            context("Subsystem", le_u16),
-            context("DllCharacteristics", le_u16),
-            context("SizeOfStackReserve", le_u64),
-            context("SizeOfStackCommit", le_u64),
-            context("SizeOfHeapReserve", le_u64),
-            context("SizeOfHeapCommit", le_u64),
-            context("LoaderFlags", le_u32),
-            context("NumberOfRvaAndSizes", le_u32),
+            ctx.field("Win32VersionValue", le_u32),
+            ctx.field("SizeOfImage", le_u32),
+            ctx.field("SizeOfHeaders", le_u32),
+            ctx.field("CheckSum", le_u32),
+            ctx.field("Subsystem", le_u16),
+            ctx.field("DllCharacteristics", le_u16),
+            ctx.field("SizeOfStackReserve", le_u64),
+            ctx.field("SizeOfStackCommit", le_u64),
+            ctx.field("SizeOfHeapReserve", le_u64),
+            ctx.field("SizeOfHeapCommit", le_u64),
+            ctx.field("LoaderFlags", le_u32),
+            ctx.field("NumberOfRvaAndSizes", le_u32),
         ))(i)?;
 
-        let (i, data_directories) = DataDirectories::parse(i, number_of_rva_and_sizes as usize)?;
+        let (i, data_directories) = DataDirectories::parse(
+            i,
+            directory_count(number_of_rva_and_sizes, size, Self::SIZE_OF_FIELDS),
+            ctx,
+        )?;
 
         Ok((
             i,
@@ -464,11 +817,127 @@ impl OptionalHeader64 {
                 loader_flags,
                 number_of_rva_and_sizes,
                 data_directories,
+                offset,
+                size,
             },
         ))
     }
 }
 
+/// The flags of the characteristics of the file header.
+///
+/// Reference: https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#characteristics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[repr(u16)]
+pub enum FileCharacteristic {
+    RelocsStripped = 0x0001,
+    ExecutableImage = 0x0002,
+    LineNumsStripped = 0x0004,
+    LocalSymsStripped = 0x0008,
+    AggressiveWsTrim = 0x0010,
+    LargeAddressAware = 0x0020,
+    BytesReversedLo = 0x0080,
+    Machine32Bit = 0x0100,
+    DebugStripped = 0x0200,
+    RemovableRunFromSwap = 0x0400,
+    NetRunFromSwap = 0x0800,
+    System = 0x1000,
+    Dll = 0x2000,
+    UpSystemOnly = 0x4000,
+    BytesReversedHi = 0x8000,
+}
+
+impl FileCharacteristic {
+    const ALL: [Self; 15] = [
+        Self::RelocsStripped,
+        Self::ExecutableImage,
+        Self::LineNumsStripped,
+        Self::LocalSymsStripped,
+        Self::AggressiveWsTrim,
+        Self::LargeAddressAware,
+        Self::BytesReversedLo,
+        Self::Machine32Bit,
+        Self::DebugStripped,
+        Self::RemovableRunFromSwap,
+        Self::NetRunFromSwap,
+        Self::System,
+        Self::Dll,
+        Self::UpSystemOnly,
+        Self::BytesReversedHi,
+    ];
+
+    /// The flags set in `characteristics`, in the order of their bits. Unknown bits are ignored.
+    pub fn flags(characteristics: u16) -> Vec<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|flag| characteristics & *flag as u16 != 0)
+            .collect()
+    }
+}
+
+/// The flags of the DLL characteristics of the optional header.
+///
+/// Reference: https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#dll-characteristics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[repr(u16)]
+pub enum DllCharacteristic {
+    HighEntropyVa = 0x0020,
+    DynamicBase = 0x0040,
+    ForceIntegrity = 0x0080,
+    NxCompat = 0x0100,
+    NoIsolation = 0x0200,
+    NoSeh = 0x0400,
+    NoBind = 0x0800,
+    AppContainer = 0x1000,
+    WdmDriver = 0x2000,
+    GuardCf = 0x4000,
+    TerminalServerAware = 0x8000,
+}
+
+impl DllCharacteristic {
+    const ALL: [Self; 11] = [
+        Self::HighEntropyVa,
+        Self::DynamicBase,
+        Self::ForceIntegrity,
+        Self::NxCompat,
+        Self::NoIsolation,
+        Self::NoSeh,
+        Self::NoBind,
+        Self::AppContainer,
+        Self::WdmDriver,
+        Self::GuardCf,
+        Self::TerminalServerAware,
+    ];
+
+    /// The flags set in `dll_characteristics`, in the order of their bits. Reserved bits are ignored.
+    pub fn flags(dll_characteristics: u16) -> Vec<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|flag| dll_characteristics & *flag as u16 != 0)
+            .collect()
+    }
+}
+
+/// Reference: https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#windows-subsystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, Display)]
+#[repr(u16)]
+pub enum Subsystem {
+    Unknown = 0,
+    Native = 1, // device drivers and native Windows processes
+    WindowsGui = 2,
+    WindowsCui = 3,
+    Os2Cui = 5,
+    PosixCui = 7,
+    NativeWindows = 8, // native Win9x drivers
+    WindowsCeGui = 9,
+    EfiApplication = 10,
+    EfiBootServiceDriver = 11,
+    EfiRuntimeDriver = 12,
+    EfiRom = 13,
+    Xbox = 14,
+    WindowsBootApplication = 16,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, Display)]
 #[repr(u16)]
 pub enum OptionalHeaderMagic {
@@ -478,6 +947,14 @@ pub enum OptionalHeaderMagic {
 }
 
 impl OptionalHeaderMagic {
+    pub fn bitness(&self) -> Bitness {
+        match self {
+            Self::Pe32 => Bitness::Bits32,
+            Self::Pe32Plus => Bitness::Bits64,
+            Self::Rom => Bitness::Rom,
+        }
+    }
+
     pub fn parse(i: parse::Input) -> parse::Result<Self> {
         map_res(le_u16, |x| match Self::try_from(x) {
             Ok(x) => Ok(x),
@@ -486,18 +963,38 @@ impl OptionalHeaderMagic {
     }
 }
 
+/// The kind of image told by the magic of the optional header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum Bitness {
+    Bits32, // PE32
+    Bits64, // PE32+
+    Rom,    // a ROM image, which has no Windows-specific fields
+}
+
+/// The declared checksum of the image compared to the computed one, see `PE::checksum_status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum ChecksumStatus {
+    Absent,  // zero, as the linker leaves it for most user-mode images
+    Valid,   // as the loader requires for drivers and the DLLs loaded at boot
+    Invalid, // the image was modified after linking, e.g. patched or tampered with
+}
+
 #[derive(Debug)]
 pub struct DataDirectories(Vec<DataDirectory>);
 
 impl DataDirectories {
-    fn parse(input: parse::Input, count: usize) -> parse::Result<Self> {
+    fn parse<'a>(
+        input: parse::Input<'a>,
+        count: usize,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Self> {
         let mut directories = Vec::new();
         let mut input = input;
         for i in 0..count {
             let entry = DirectoryEntry::try_from(i).map_err(|e| {
                 errors::PEError::from_string(input, format!("unknown image directory. {}", e))
             })?;
-            let (new_input, directory) = DataDirectory::parse(entry, input)?;
+            let (new_input, directory) = DataDirectory::parse(entry, input, ctx)?;
             directories.push(directory);
             input = new_input;
         }
@@ -511,6 +1008,70 @@ impl DataDirectories {
             Some(self.0[entry.value()])
         }
     }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DataDirectory> {
+        self.0.iter()
+    }
+
+    /// Whether each directory is present without parsing it
+    pub fn presence(&self) -> DirectoryPresence {
+        let is_present = |entry| {
+            self.find_by_entry(entry)
+                .is_some_and(|directory| directory.is_present())
+        };
+        DirectoryPresence {
+            export: is_present(DirectoryEntry::Export),
+            import: is_present(DirectoryEntry::Import),
+            resource: is_present(DirectoryEntry::Resource),
+            exception: is_present(DirectoryEntry::Exception),
+            certificate: is_present(DirectoryEntry::Certificate),
+            base_relocation: is_present(DirectoryEntry::BaseRelocation),
+            debug: is_present(DirectoryEntry::Debug),
+            architecture: is_present(DirectoryEntry::Architecture),
+            global_ptr: is_present(DirectoryEntry::Globalptr),
+            tls: is_present(DirectoryEntry::Tls),
+            load_config: is_present(DirectoryEntry::LoadConfig),
+            bound_import: is_present(DirectoryEntry::BoundImport),
+            import_address_table: is_present(DirectoryEntry::ImportAddressTable),
+            delay_import: is_present(DirectoryEntry::DelayImport),
+            clr_runtime: is_present(DirectoryEntry::ClrRuntime),
+        }
+    }
+}
+
+/// Whether each data directory has a nonzero RVA and size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirectoryPresence {
+    pub export: bool,
+    pub import: bool,
+    pub resource: bool,
+    pub exception: bool,
+    pub certificate: bool,
+    pub base_relocation: bool,
+    pub debug: bool,
+    pub architecture: bool,
+    pub global_ptr: bool,
+    pub tls: bool,
+    pub load_config: bool,
+    pub bound_import: bool,
+    pub import_address_table: bool,
+    pub delay_import: bool,
+    pub clr_runtime: bool,
+}
+
+/// What refers to a region of the virtual address space from the headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RvaReference {
+    Directory(DirectoryEntry),
+    EntryPoint,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -521,8 +1082,19 @@ pub struct DataDirectory {
 }
 
 impl DataDirectory {
-    pub fn parse(entry: DirectoryEntry, input: parse::Input) -> parse::Result<Self> {
-        let (input, (virtual_address, size)) = tuple((le_u32, le_u32))(input)?;
+    pub const SIZE: usize = 8;
+
+    pub fn is_present(&self) -> bool {
+        self.virtual_address != 0 && self.size != 0
+    }
+
+    pub fn parse<'a>(
+        entry: DirectoryEntry,
+        input: parse::Input<'a>,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Self> {
+        let (input, (virtual_address, size)) =
+            ctx.field(entry.field_name(), tuple((le_u32, le_u32)))(input)?;
         Ok((
             input,
             Self {
@@ -559,10 +1131,37 @@ impl DirectoryEntry {
     fn value(&self) -> usize {
         *self as usize
     }
+
+    /// The name of the data directory entry in the optional header, e.g. "ExportTable"
+    pub fn field_name(&self) -> &'static str {
+        match self {
+            Self::Export => "ExportTable",
+            Self::Import => "ImportTable",
+            Self::Resource => "ResourceTable",
+            Self::Exception => "ExceptionTable",
+            Self::Certificate => "CertificateTable",
+            Self::BaseRelocation => "BaseRelocationTable",
+            Self::Debug => "Debug",
+            Self::Architecture => "Architecture",
+            Self::Globalptr => "GlobalPtr",
+            Self::Tls => "TLSTable",
+            Self::LoadConfig => "LoadConfigTable",
+            Self::BoundImport => "BoundImport",
+            Self::ImportAddressTable => "IAT",
+            Self::DelayImport => "DelayImportDescriptor",
+            Self::ClrRuntime => "CLRRuntimeHeader",
+            Self::Reserved => "Reserved",
+        }
+    }
 }
 
 impl<'a> fmt::Display for NTHeader<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            writeln!(f, "NTHeader:")?;
+            write!(f, "{:#}", self.file_header)?;
+            return write!(f, "{:#}", self.optional_header);
+        }
         writeln!(f, "NTHeader:")?;
         writeln!(f, "  Signature: {:?}", self.signature)?;
         writeln!(f, "  {}", self.file_header)?;
@@ -572,6 +1171,13 @@ impl<'a> fmt::Display for NTHeader<'a> {
 
 impl fmt::Display for FileHeader {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            return writeln!(
+                f,
+                "  FileHeader: Machine: {}, Sections: {}, Datetime: {}, Characteristics: {:#06x}",
+                self.machine, self.num_of_sections, self.datetime, self.characteristics
+            );
+        }
         writeln!(f, "FileHeader:")?;
         writeln!(f, "    Machine: {}", self.machine)?;
         writeln!(f, "    Number of sections: {}", self.num_of_sections)?;
@@ -583,12 +1189,37 @@ impl fmt::Display for FileHeader {
             "    Size of optional header: {}",
             self.size_of_optional_header
         )?;
-        writeln!(f, "    characteristics: {}", self.characteristics)
+        writeln!(f, "    characteristics: {}", self.characteristics)?;
+        if self.bytes_reversed() {
+            writeln!(
+                f,
+                "    Warning: the bytes reversed flags are set, byte-reversed images are not supported"
+            )?;
+        }
+        Ok(())
     }
 }
 
 impl fmt::Display for OptionalHeader {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            let magic = match self {
+                OptionalHeader::Op32(op) => op.magic,
+                OptionalHeader::Op64(op) => op.magic,
+            };
+            writeln!(
+                f,
+                "  OptionalHeader: Magic: {}, Entry point: {:#x}, Image base: {:#x}, Size of image: {:#x}, Subsystem: {}",
+                magic,
+                self.address_of_entry_point(),
+                self.image_base(),
+                self.size_of_image(),
+                self.subsystem()
+                    .map(|subsystem| subsystem.to_string())
+                    .unwrap_or_else(|| "-".to_string())
+            )?;
+            return write!(f, "{:#}", self.data_directories());
+        }
         match self {
             OptionalHeader::Op32(op) => {
                 writeln!(f, "{}", op)
@@ -748,8 +1379,15 @@ impl fmt::Display for OptionalHeader64 {
 
 impl fmt::Display for DataDirectories {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            // only the present directories
+            for dir in self.iter().filter(|dir| dir.is_present()) {
+                writeln!(f, "{}", dir)?;
+            }
+            return Ok(());
+        }
         writeln!(f, "Entry Address Size")?;
-        for (_, dir) in self.0.iter().enumerate() {
+        for dir in self.0.iter() {
             writeln!(f, "  {}", dir)?;
         }
         Ok(())
@@ -760,7 +1398,7 @@ impl fmt::Display for DataDirectory {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "    {} Entry {} {}",
+            "    {}: RVA={:#010x} Size={:#010x}",
             self.entry, self.virtual_address, self.size
         )
     }
@@ -768,7 +1406,10 @@ impl fmt::Display for DataDirectory {
 
 #[cfg(test)]
 mod tests {
-    use super::Machine;
+    use super::{
+        Bitness, DllCharacteristic, FileCharacteristic, FileHeader, Machine, OptionalHeaderMagic,
+    };
+    use crate::options::{ParseContext, ParseOptions};
     use std::convert::TryFrom;
 
     #[test]
@@ -777,4 +1418,77 @@ mod tests {
         assert_eq!(Machine::try_from(0x9041), Ok(Machine::M32R));
         assert_eq!(Machine::try_from(0x1234), Err(0x1234));
     }
+
+    #[test]
+    fn target_arch() {
+        assert_eq!(Machine::Amd64.target_arch(), "x86_64");
+        assert_eq!(Machine::I386.target_arch(), "i686");
+        assert_eq!(Machine::Armnt.target_arch(), "arm");
+        assert_eq!(Machine::Ebc.target_arch(), "unknown");
+    }
+
+    #[test]
+    fn bitness() {
+        assert_eq!(OptionalHeaderMagic::Pe32.bitness(), Bitness::Bits32);
+        assert_eq!(OptionalHeaderMagic::Pe32Plus.bitness(), Bitness::Bits64);
+        assert_eq!(OptionalHeaderMagic::Rom.bitness(), Bitness::Rom);
+    }
+
+    #[test]
+    fn bytes_reversed() {
+        let file_header = |characteristics: u16| {
+            let mut data = vec![];
+            data.extend_from_slice(&0x14cu16.to_le_bytes());
+            data.extend_from_slice(&[0; 14]);
+            data.extend_from_slice(&0xe0u16.to_le_bytes());
+            data.extend_from_slice(&characteristics.to_le_bytes());
+            FileHeader::parse(&data, &ParseContext::default())
+                .unwrap()
+                .1
+        };
+        assert!(!file_header(0x0102).bytes_reversed());
+        assert!(file_header(0x0182).bytes_reversed());
+        assert!(file_header(0x8102).to_string().contains("Warning"));
+        assert_eq!(file_header(0x0142).raw_characteristics(), 0x0142);
+        assert_eq!(
+            file_header(0x2122).characteristic_flags(),
+            vec![
+                FileCharacteristic::ExecutableImage,
+                FileCharacteristic::LargeAddressAware,
+                FileCharacteristic::Machine32Bit,
+                FileCharacteristic::Dll
+            ]
+        );
+        assert_eq!(
+            DllCharacteristic::flags(0x8161),
+            vec![
+                DllCharacteristic::HighEntropyVa,
+                DllCharacteristic::DynamicBase,
+                DllCharacteristic::NxCompat,
+                DllCharacteristic::TerminalServerAware
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_timestamp() {
+        let mut data = vec![];
+        data.extend_from_slice(&0x14cu16.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&0x5f5e1000u32.to_le_bytes());
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&0xe0u16.to_le_bytes());
+        data.extend_from_slice(&0x102u16.to_le_bytes());
+        let options = ParseOptions {
+            decode_timestamps: false,
+            ..ParseOptions::default()
+        };
+
+        let (_, decoded) = FileHeader::parse(&data, &ParseContext::default()).unwrap();
+        assert_eq!(decoded.time_date_stamp, 0x5f5e1000);
+        assert_eq!(decoded.datetime.timestamp(), 0x5f5e1000);
+        let (_, raw) = FileHeader::parse(&data, &ParseContext::from_options(&options)).unwrap();
+        assert_eq!(raw.time_date_stamp, 0x5f5e1000);
+        assert_eq!(raw.datetime.timestamp(), 0);
+    }
 }
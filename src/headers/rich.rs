@@ -0,0 +1,160 @@
+use crate::utils;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// The Rich header, an undocumented record of the tools which built the objects of the image,
+/// written by the Microsoft linker in the DOS stub and masked with a key which is also a checksum.
+///
+/// Reference: https://www.ntcore.com/files/richsign.htm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RichHeader {
+    pub offset: usize, // the file offset of the "DanS" marker
+    pub key: u32,
+    pub entries: Vec<RichEntry>,
+}
+
+impl RichHeader {
+    const DANS: u32 = 0x536e6144; // "DanS"
+    const RICH: &'static [u8] = b"Rich";
+
+    /// Finds the Rich header in the DOS stub, whose first byte is at `stub_offset` in the file.
+    /// Returns None when there is no "Rich" marker or no "DanS" marker masked with its key.
+    pub fn parse(stub: &[u8], stub_offset: usize) -> Option<Self> {
+        let rich = stub
+            .windows(4)
+            .rposition(|window| window == Self::RICH)
+            .filter(|rich| rich % 4 == 0)?;
+        let key = utils::read_u32_at(stub, rich + 4)?;
+        // the marker is followed by 3 zeroed padding dwords, so it is at least 16 bytes before "Rich"
+        let dans = (0..rich.checked_sub(16)? + 1)
+            .rev()
+            .step_by(4)
+            .find(|offset| utils::read_u32_at(stub, *offset) == Some(Self::DANS ^ key))?;
+        let entries = stub[dans + 16..rich]
+            .chunks_exact(8)
+            .map(|entry| {
+                let comp_id = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]) ^ key;
+                let count = u32::from_le_bytes([entry[4], entry[5], entry[6], entry[7]]) ^ key;
+                RichEntry {
+                    product_id: (comp_id >> 16) as u16,
+                    build: comp_id as u16,
+                    count,
+                }
+            })
+            .collect();
+        Some(Self {
+            offset: stub_offset + dans,
+            key,
+            entries,
+        })
+    }
+
+    /// Recomputes the checksum the linker uses as the key, from the bytes of the file preceding the Rich header,
+    /// i.e. the DOS header and the beginning of the stub with `e_lfanew` excluded, and from the entries.
+    pub fn compute_checksum(&self, pe_file: &[u8]) -> Option<u32> {
+        let dos_bytes = pe_file.get(..self.offset)?;
+        let mut checksum = self.offset as u32;
+        for (index, byte) in dos_bytes.iter().enumerate() {
+            // e_lfanew is written after the checksum is computed
+            if (0x3c..0x40).contains(&index) {
+                continue;
+            }
+            checksum = checksum.wrapping_add((*byte as u32).rotate_left(index as u32));
+        }
+        for entry in &self.entries {
+            checksum = checksum.wrapping_add(entry.comp_id().rotate_left(entry.count));
+        }
+        Some(checksum)
+    }
+
+    /// Whether the key matches the recomputed checksum.
+    /// A mismatch means the Rich header or the DOS header was modified after linking,
+    /// e.g. to forge the toolchain of the image.
+    pub fn verify_checksum(&self, pe_file: &[u8]) -> bool {
+        self.compute_checksum(pe_file) == Some(self.key)
+    }
+}
+
+/// The number of objects built by a tool of the given product and build number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RichEntry {
+    pub product_id: u16,
+    pub build: u16,
+    pub count: u32,
+}
+
+impl RichEntry {
+    pub fn comp_id(&self) -> u32 {
+        (self.product_id as u32) << 16 | self.build as u32
+    }
+}
+
+impl fmt::Display for RichHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "RichHeader: offset: {:#x}, key: {:#010x}",
+            self.offset, self.key
+        )?;
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "  product: {}, build: {}, count: {}",
+                entry.product_id, entry.build, entry.count
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RichEntry, RichHeader};
+
+    #[test]
+    fn checksum() {
+        let mut pe_file = vec![0u8; 0x80];
+        pe_file[..2].copy_from_slice(b"MZ");
+        pe_file[0x3c..0x40].copy_from_slice(&0xc0u32.to_le_bytes());
+        let entries = [
+            RichEntry {
+                product_id: 0x0104,
+                build: 30795,
+                count: 12,
+            },
+            RichEntry {
+                product_id: 0x0102,
+                build: 30795,
+                count: 1,
+            },
+        ];
+        let key = RichHeader {
+            offset: 0x80,
+            key: 0,
+            entries: entries.to_vec(),
+        }
+        .compute_checksum(&pe_file)
+        .unwrap();
+        pe_file.extend_from_slice(&(RichHeader::DANS ^ key).to_le_bytes());
+        for _ in 0..3 {
+            pe_file.extend_from_slice(&key.to_le_bytes());
+        }
+        for entry in &entries {
+            pe_file.extend_from_slice(&(entry.comp_id() ^ key).to_le_bytes());
+            pe_file.extend_from_slice(&(entry.count ^ key).to_le_bytes());
+        }
+        pe_file.extend_from_slice(b"Rich");
+        pe_file.extend_from_slice(&key.to_le_bytes());
+
+        let rich = RichHeader::parse(&pe_file[0x40..], 0x40).unwrap();
+        assert_eq!(rich.offset, 0x80);
+        assert_eq!(rich.entries, entries);
+        assert!(rich.verify_checksum(&pe_file));
+
+        // tampering with the DOS header breaks the checksum, but not e_lfanew
+        pe_file[0x3c] = 0xd0;
+        assert!(rich.verify_checksum(&pe_file));
+        pe_file[0x40] = 0x0e;
+        assert!(!rich.verify_checksum(&pe_file));
+    }
+}
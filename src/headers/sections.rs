@@ -1,58 +1,284 @@
-use crate::parse;
+use crate::headers::coff::{CoffLineNumber, CoffRelocation};
+use crate::headers::nt::DataDirectory;
+use crate::options::ParseContext;
+use crate::{errors, parse};
 use nom::number::complete::{le_u16, le_u32};
-use nom::{bytes::complete::take, error::context, sequence::tuple};
+use nom::{bytes::complete::take, sequence::tuple};
 use std::fmt;
 use std::fmt::Formatter;
+use std::ops::Range;
+
+use derive_more::Display;
 
 #[derive(Debug, Clone)]
-pub struct Sections(pub Vec<Section>);
+pub struct Sections {
+    pub sections: Vec<Section>,
+    table_offset: usize, // the file offset of the section table
+}
 
 impl Sections {
-    pub fn parse(i: parse::Input, num_of_sections: u16) -> parse::Result<Self> {
-        let mut sections = Vec::new();
+    pub fn parse<'a>(
+        i: parse::Input<'a>,
+        num_of_sections: u16,
+        offset: usize,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Self> {
+        ctx.budget()
+            .table("section table")
+            .reserve(i, num_of_sections as usize)?;
+        let mut sections = Vec::with_capacity(num_of_sections as usize);
         let mut input = i;
-        for _ in 0..num_of_sections {
-            let (new_input, section) = Section::parse(input)?;
+        for index in 0..num_of_sections as usize {
+            let (new_input, mut section) = Section::parse(input, ctx)?;
+            section.header_offset = offset + index * Section::SIZE;
+            ctx.visit(|visitor| visitor.on_section(&section));
             sections.push(section);
             input = new_input;
         }
 
-        Ok((i, Sections(sections)))
+        Ok((input, Sections::new(sections, offset)))
+    }
+
+    pub fn new(sections: Vec<Section>, table_offset: usize) -> Self {
+        Sections {
+            sections,
+            table_offset,
+        }
     }
 
-    pub fn find_by_name(self, name: &str) -> Option<Section> {
-        self.0.into_iter().find(|section| section.name == name)
+    /// the file offset of the section table
+    pub fn table_offset(&self) -> usize {
+        self.table_offset
     }
 
-    /// find the section including the addr
-    pub fn find_by_address(self, addr: u32) -> Option<Section> {
-        self.0
-            .into_iter()
-            .find(|section| addr >= section.vir_addr && addr < section.vir_addr + section.vir_size)
+    /// the number of bytes of the section table, which is followed by the padding up to the first section data
+    pub fn table_size(&self) -> usize {
+        self.sections.len() * Section::SIZE
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Section> {
+        self.sections.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sections.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    pub fn find_by_section_name(&self, name: SectionName) -> Option<&Section> {
+        self.find_by_name(name.as_str())
+    }
+
+    pub fn text(&self) -> Option<&Section> {
+        self.find_by_section_name(SectionName::Text)
+    }
+
+    pub fn data(&self) -> Option<&Section> {
+        self.find_by_section_name(SectionName::Data)
+    }
+
+    pub fn rdata(&self) -> Option<&Section> {
+        self.find_by_section_name(SectionName::Rdata)
+    }
+
+    pub fn bss(&self) -> Option<&Section> {
+        self.find_by_section_name(SectionName::Bss)
+    }
+
+    pub fn idata(&self) -> Option<&Section> {
+        self.find_by_section_name(SectionName::Idata)
+    }
+
+    pub fn edata(&self) -> Option<&Section> {
+        self.find_by_section_name(SectionName::Edata)
+    }
+
+    pub fn rsrc(&self) -> Option<&Section> {
+        self.find_by_section_name(SectionName::Rsrc)
+    }
+
+    pub fn reloc(&self) -> Option<&Section> {
+        self.find_by_section_name(SectionName::Reloc)
+    }
+
+    pub fn tls(&self) -> Option<&Section> {
+        self.find_by_section_name(SectionName::Tls)
+    }
+
+    pub fn didat(&self) -> Option<&Section> {
+        self.find_by_section_name(SectionName::Didat)
+    }
+
+    /// find the section including the addr.
+    /// When sections overlap in a crafted file, the first one in the section table wins.
+    pub fn find_by_address(&self, addr: u32) -> Option<&Section> {
+        self.sections.iter().find(|section| {
+            addr >= section.vir_addr
+                && addr < section.vir_addr.saturating_add(section.mapped_size())
+        })
+    }
+
+    /// find the section including the whole range `[rva, rva + len)`, e.g. to check that a fixed-size structure
+    /// is mapped before reading it. None when the range straddles a section boundary or is not mapped at all.
+    pub fn range_in_single_section(&self, rva: u32, len: u32) -> Option<&Section> {
+        let end = rva.checked_add(len)?;
+        self.find_by_address(rva)
+            .filter(|section| end <= section.vir_addr.saturating_add(section.mapped_size()))
+    }
+
+    /// find the section whose raw data includes the file offset, the counterpart of `find_by_address`
+    /// for offsets e.g. from a hex editor
+    pub fn section_for_offset(&self, offset: u32) -> Option<&Section> {
+        self.sections.iter().find(|section| {
+            offset >= section.ptr_to_raw_data
+                && offset
+                    < section
+                        .ptr_to_raw_data
+                        .saturating_add(section.size_of_raw_data)
+        })
+    }
+
+    /// the pairs of indices of the sections whose virtual ranges overlap, which well-formed images never have.
+    /// The ranges span the `mapped_size` of the sections, like `find_by_address`.
+    pub fn overlapping(&self) -> Vec<(usize, usize)> {
+        let mut pairs = vec![];
+        for (i, a) in self.sections.iter().enumerate() {
+            for (j, b) in self.sections.iter().enumerate().skip(i + 1) {
+                if a.vir_addr < b.vir_addr.saturating_add(b.mapped_size())
+                    && b.vir_addr < a.vir_addr.saturating_add(a.mapped_size())
+                {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs
+    }
+
+    pub fn has_overlapping_sections(&self) -> bool {
+        !self.overlapping().is_empty()
+    }
+
+    /// the pairs of indices of the sections whose raw data ranges in the file overlap,
+    /// which packers use to share the same bytes between sections. Sections without raw data are ignored.
+    pub fn raw_overlapping(&self) -> Vec<(usize, usize)> {
+        let raw_range = |section: &Section| {
+            let start = section.ptr_to_raw_data as u64;
+            start..start + section.size_of_raw_data as u64
+        };
+        let mut pairs = vec![];
+        for (i, a) in self.sections.iter().enumerate() {
+            for (j, b) in self.sections.iter().enumerate().skip(i + 1) {
+                let (a, b) = (raw_range(a), raw_range(b));
+                if !a.is_empty() && !b.is_empty() && a.start < b.end && b.start < a.end {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// convert the rva to the file offset by using the section including the rva
+    pub fn rva_to_offset(&self, rva: u32) -> Option<u32> {
+        self.find_by_address(rva)
+            .and_then(|section| section.rva_to_offset(rva))
+    }
+
+    /// Fails with `ErrorKind::RvaOutOfBounds` when the directory is present but no section maps its RVA,
+    /// e.g. a directory pointing into the headers or beyond the image.
+    /// The size of the error is the end of the sections in memory.
+    pub fn check_directory_mapped<'a>(
+        &self,
+        input: parse::Input<'a>,
+        directory: DataDirectory,
+        ctx: &'static str,
+    ) -> Result<(), nom::Err<errors::PEError<parse::Input<'a>>>> {
+        if !directory.is_present() || self.find_by_address(directory.virtual_address).is_some() {
+            return Ok(());
+        }
+        let mapped_end = self
+            .sections
+            .iter()
+            .map(|section| section.vir_addr.saturating_add(section.mapped_size()))
+            .max()
+            .unwrap_or(0);
+        Err(errors::PEError::rva_out_of_bounds(
+            input,
+            directory.virtual_address,
+            mapped_end,
+            ctx,
+        ))
     }
 }
 
 /// Enum representing common section names in a Portable Executable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SectionName {
     Text,
     Data,
     Rdata,
     Bss,
     Idata,
+    Edata,
+    Rsrc,
+    Reloc,
     Tls,
+    Didat,
 }
 
 impl SectionName {
-    pub fn as_str(&self) -> &str {
+    pub fn as_str(&self) -> &'static str {
         match *self {
             SectionName::Text => ".text",
             SectionName::Data => ".data",
             SectionName::Rdata => ".rdata",
             SectionName::Bss => ".bss",
             SectionName::Idata => ".idata",
+            SectionName::Edata => ".edata",
+            SectionName::Rsrc => ".rsrc",
+            SectionName::Reloc => ".reloc",
             SectionName::Tls => ".tls",
+            SectionName::Didat => ".didat",
         }
     }
+
+    pub fn description(&self) -> &'static str {
+        describe_section(self.as_str()).unwrap_or_default()
+    }
+}
+
+/// Describes the usual content of a section by its well-known name, e.g. "executable code" for `.text`.
+/// Section names are only a convention, so the description is a hint and not a guarantee.
+pub fn describe_section(name: &str) -> Option<&'static str> {
+    let description = match name {
+        ".text" => "executable code",
+        ".data" => "initialized data",
+        ".rdata" => "read-only initialized data",
+        ".bss" => "uninitialized data",
+        ".idata" => "imports",
+        ".edata" => "exports",
+        ".rsrc" => "resources",
+        ".reloc" => "base relocations",
+        ".tls" => "thread-local storage",
+        ".pdata" => "exception handlers",
+        ".xdata" => "exception unwind information",
+        ".debug" => "debug information",
+        ".didat" => "delay-load imports",
+        ".CRT" => "C runtime initializers",
+        ".gfids" => "control flow guard function ids",
+        ".cormeta" => "CLR metadata",
+        ".sxdata" => "registered exception handlers",
+        ".drectve" => "linker directives",
+        ".textbss" => "incremental linking padding",
+        _ => return None,
+    };
+    Some(description)
 }
 
 #[derive(Debug, Clone)]
@@ -67,12 +293,46 @@ pub struct Section {
     pub num_of_relocs: u16,
     pub num_of_line_nums: u16,
     pub characteristics: u32,
+    pub header_offset: usize, // the file offset of the section header in the section table
+    pub name_bytes: [u8; 8],  // the name as it appears in the section header, see `raw_name`
 }
 
 impl Section {
-    pub fn parse(i: parse::Input) -> parse::Result<Self> {
+    pub const SIZE: usize = 40;
+
+    /// A section mapping `vir_size` bytes at `vir_addr` from `size_of_raw_data` bytes at `ptr_to_raw_data`,
+    /// for the tests of the parsers reading through `Sections`
+    #[cfg(test)]
+    pub(crate) fn for_test(
+        name: &str,
+        vir_addr: u32,
+        vir_size: u32,
+        ptr_to_raw_data: u32,
+        size_of_raw_data: u32,
+        characteristics: u32,
+    ) -> Self {
+        let mut name_bytes = [0; 8];
+        let len = name.len().min(8);
+        name_bytes[..len].copy_from_slice(&name.as_bytes()[..len]);
+        Self {
+            name: name.to_string(),
+            vir_size,
+            vir_addr,
+            size_of_raw_data,
+            ptr_to_raw_data,
+            ptr_to_relocs: 0,
+            ptr_to_line_nums: 0,
+            num_of_relocs: 0,
+            num_of_line_nums: 0,
+            characteristics,
+            header_offset: 0,
+            name_bytes,
+        }
+    }
+
+    pub fn parse<'a>(i: parse::Input<'a>, ctx: &ParseContext) -> parse::Result<'a, Self> {
         let (
-            i,
+            rest,
             (
                 name,
                 vir_size,
@@ -86,23 +346,23 @@ impl Section {
                 characteristics,
             ),
         ) = tuple((
-            context("Name", take(8usize)),
-            context("VirtualSize", le_u32),
-            context("VirtualAddress", le_u32),
-            context("SizeOfRawData", le_u32),
-            context("PointerToRawData", le_u32),
-            context("PointerToRelocations", le_u32),
-            context("PointerToLinenumbers", le_u32),
-            context("NumberOfRelocations", le_u16),
-            context("NumberOfLineNumbers", le_u16),
-            context("Characteristics", le_u32),
+            ctx.field("Name", take(8usize)),
+            ctx.field("VirtualSize", le_u32),
+            ctx.field("VirtualAddress", le_u32),
+            ctx.field("SizeOfRawData", le_u32),
+            ctx.field("PointerToRawData", le_u32),
+            ctx.field("PointerToRelocations", le_u32),
+            ctx.field("PointerToLinenumbers", le_u32),
+            ctx.field("NumberOfRelocations", le_u16),
+            ctx.field("NumberOfLineNumbers", le_u16),
+            ctx.field("Characteristics", le_u32),
         ))(i)?;
 
+        let mut name_bytes = [0; 8];
+        name_bytes.copy_from_slice(name);
         let section = Self {
-            name: String::from_utf8_lossy(name)
-                .to_string()
-                .trim_end_matches('\0')
-                .to_string(),
+            // the name ends at the first null, anything after it is not part of the name
+            name: ctx.read_name(i, name, "section name")?,
             vir_size,
             vir_addr,
             size_of_raw_data,
@@ -112,24 +372,326 @@ impl Section {
             num_of_relocs,
             num_of_line_nums,
             characteristics,
+            header_offset: 0,
+            name_bytes,
         };
 
-        Ok((i, section))
+        Ok((rest, section))
+    }
+
+    /// The 8 bytes of the name field, including the bytes after an embedded null which `name` drops.
+    /// Names with garbage after a null or non-UTF-8 bytes are used to confuse analysis tools.
+    pub fn raw_name(&self) -> [u8; 8] {
+        self.name_bytes
     }
 
+    /// The size of the section in memory: `vir_size`, or `size_of_raw_data` when the linker left `vir_size` zero,
+    /// which low-alignment images often do
+    pub fn mapped_size(&self) -> u32 {
+        if self.vir_size == 0 {
+            self.size_of_raw_data
+        } else {
+            self.vir_size
+        }
+    }
+
+    /// None when the rva is below the section or the offset overflows in a crafted header
     pub fn rva_to_offset(&self, rva: u32) -> Option<u32> {
-        if rva >= self.vir_addr {
-            Some(rva - self.vir_addr + self.ptr_to_raw_data)
+        rva.checked_sub(self.vir_addr)?
+            .checked_add(self.ptr_to_raw_data)
+    }
+
+    /// the COFF line numbers pointed by `ptr_to_line_nums`, which are deprecated and only found in
+    /// object files and old debug builds
+    pub fn line_numbers(&self, pe_file: &[u8]) -> Vec<CoffLineNumber> {
+        if self.ptr_to_line_nums == 0 {
+            return vec![];
+        }
+        CoffLineNumber::parse_array(
+            pe_file,
+            self.ptr_to_line_nums as usize,
+            self.num_of_line_nums as usize,
+        )
+    }
+
+    /// the COFF relocations pointed by `ptr_to_relocs`, which are only found in object files
+    pub fn relocations(&self, pe_file: &[u8]) -> Vec<CoffRelocation> {
+        if self.ptr_to_relocs == 0 {
+            return vec![];
+        }
+        let offset = self.ptr_to_relocs as usize;
+        if self.characteristics & IMAGE_SCN_LNK_NRELOC_OVFL != 0 && self.num_of_relocs == 0xffff {
+            // the actual count is in the address of the first record, which counts itself
+            return match CoffRelocation::parse_array(pe_file, offset, 1).first() {
+                Some(first) => CoffRelocation::parse_array(
+                    pe_file,
+                    offset + CoffRelocation::SIZE,
+                    (first.virtual_address as usize).saturating_sub(1),
+                ),
+                None => vec![],
+            };
+        }
+        CoffRelocation::parse_array(pe_file, offset, self.num_of_relocs as usize)
+    }
+
+    /// the 40-byte section header as it appears in the file, e.g. to patch the characteristics in place
+    pub fn raw_header<'a>(&self, pe_file: &'a [u8]) -> &'a [u8] {
+        let start = self.header_offset.min(pe_file.len());
+        let end = start.saturating_add(Self::SIZE).min(pe_file.len());
+        &pe_file[start..end]
+    }
+
+    /// the raw data of the section in the file, truncated when the file is shorter than declared
+    pub fn raw_data<'a>(&self, pe_file: &'a [u8]) -> &'a [u8] {
+        &pe_file[self.raw_range(pe_file.len())]
+    }
+
+    /// the file range of the raw data, clamped to the length of the file
+    pub fn raw_range(&self, file_len: usize) -> Range<usize> {
+        let start = (self.ptr_to_raw_data as usize).min(file_len);
+        let end = start
+            .saturating_add(self.size_of_raw_data as usize)
+            .min(file_len);
+        start..end
+    }
+
+    /// Shannon entropy of the raw data in bits per byte, from 0.0 to 8.0
+    pub fn entropy(&self, pe_file: &[u8]) -> f64 {
+        let data = self.raw_data(pe_file);
+        if data.is_empty() {
+            return 0.0;
+        }
+        let mut counts = [0usize; 256];
+        for byte in data {
+            counts[*byte as usize] += 1;
+        }
+        let len = data.len() as f64;
+        counts
+            .iter()
+            .filter(|count| **count != 0)
+            .map(|count| {
+                let p = *count as f64 / len;
+                -p * p.log2()
+            })
+            .sum()
+    }
+
+    /// Extracts the runs of at least `min_len` printable ASCII characters and the runs of as many
+    /// UTF-16LE code units in the printable ASCII range from the raw data, like the `strings` utility.
+    /// The strings are sorted by their file offset.
+    pub fn extract_strings(&self, pe_file: &[u8], min_len: usize) -> Vec<SectionString> {
+        let data = self.raw_data(pe_file);
+        let base = self.ptr_to_raw_data as usize;
+        let min_len = min_len.max(1);
+        let mut strings = vec![];
+
+        let mut start = 0;
+        for (index, byte) in data.iter().chain(std::iter::once(&0)).enumerate() {
+            if is_printable(*byte) {
+                continue;
+            }
+            if index - start >= min_len {
+                strings.push(SectionString {
+                    offset: base + start,
+                    encoding: StringEncoding::Ascii,
+                    value: String::from_utf8_lossy(&data[start..index]).to_string(),
+                });
+            }
+            start = index + 1;
+        }
+
+        let mut index = 0;
+        while index < data.len() {
+            let mut end = index;
+            while end + 1 < data.len() && is_printable(data[end]) && data[end + 1] == 0 {
+                end += 2;
+            }
+            if (end - index) / 2 >= min_len {
+                strings.push(SectionString {
+                    offset: base + index,
+                    encoding: StringEncoding::Utf16Le,
+                    value: data[index..end]
+                        .iter()
+                        .step_by(2)
+                        .map(|b| *b as char)
+                        .collect(),
+                });
+                index = end;
+            } else {
+                index += 1;
+            }
+        }
+
+        strings.sort_by_key(|string| string.offset);
+        strings
+    }
+
+    /// The characteristics as read, including the alignment and the reserved bits `characteristic_flags` leaves out
+    pub fn raw_characteristics(&self) -> u32 {
+        self.characteristics
+    }
+
+    /// The flags set in the characteristics, without the alignment which is a number and not a flag
+    pub fn characteristic_flags(&self) -> Vec<SectionCharacteristic> {
+        SectionCharacteristic::flags(self.characteristics)
+    }
+
+    pub fn contains_code(&self) -> bool {
+        self.characteristics & IMAGE_SCN_CNT_CODE != 0
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.characteristics & IMAGE_SCN_MEM_EXECUTE != 0
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.characteristics & IMAGE_SCN_MEM_READ != 0
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.characteristics & IMAGE_SCN_MEM_WRITE != 0
+    }
+
+    /// The memory protection of the section like `r-x`, e.g. to render a memory map
+    pub fn permissions(&self) -> &'static str {
+        match (self.is_readable(), self.is_writable(), self.is_executable()) {
+            (false, false, false) => "---",
+            (false, false, true) => "--x",
+            (false, true, false) => "-w-",
+            (false, true, true) => "-wx",
+            (true, false, false) => "r--",
+            (true, false, true) => "r-x",
+            (true, true, false) => "rw-",
+            (true, true, true) => "rwx",
+        }
+    }
+
+    /// Classifies the section with a heuristic over the name, the characteristics and the entropy.
+    /// `.rsrc` is always a resource, otherwise a high entropy section is considered packed
+    /// since compressed or encrypted data is close to random.
+    pub fn classify(&self, pe_file: &[u8]) -> SectionClass {
+        if self.name == SectionName::Rsrc.as_str() {
+            SectionClass::Resource
+        } else if self.entropy(pe_file) > SectionClass::PACKED_ENTROPY_THRESHOLD {
+            SectionClass::Packed
+        } else if self.contains_code() || self.is_executable() {
+            SectionClass::Code
         } else {
-            None
+            SectionClass::Data
         }
     }
 }
 
+fn is_printable(byte: u8) -> bool {
+    byte == b'\t' || (0x20..0x7f).contains(&byte)
+}
+
+/// A string found in the raw data of a section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionString {
+    pub offset: usize, // the file offset of the first byte
+    pub encoding: StringEncoding,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum StringEncoding {
+    Ascii,
+    Utf16Le,
+}
+
+pub const IMAGE_SCN_CNT_CODE: u32 = 0x00000020;
+pub const IMAGE_SCN_CNT_INITIALIZED_DATA: u32 = 0x00000040;
+pub const IMAGE_SCN_CNT_UNINITIALIZED_DATA: u32 = 0x00000080;
+pub const IMAGE_SCN_LNK_NRELOC_OVFL: u32 = 0x01000000;
+pub const IMAGE_SCN_MEM_EXECUTE: u32 = 0x20000000;
+pub const IMAGE_SCN_MEM_READ: u32 = 0x40000000;
+pub const IMAGE_SCN_MEM_WRITE: u32 = 0x80000000;
+
+/// The flags of the characteristics of a section header.
+///
+/// Reference: https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#section-flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[repr(u32)]
+pub enum SectionCharacteristic {
+    TypeNoPad = 0x00000008,
+    CntCode = IMAGE_SCN_CNT_CODE,
+    CntInitializedData = IMAGE_SCN_CNT_INITIALIZED_DATA,
+    CntUninitializedData = IMAGE_SCN_CNT_UNINITIALIZED_DATA,
+    LnkOther = 0x00000100,
+    LnkInfo = 0x00000200,
+    LnkRemove = 0x00000800,
+    LnkComdat = 0x00001000,
+    Gprel = 0x00008000,
+    MemPurgeable = 0x00020000,
+    MemLocked = 0x00040000,
+    MemPreload = 0x00080000,
+    LnkNrelocOvfl = IMAGE_SCN_LNK_NRELOC_OVFL,
+    MemDiscardable = 0x02000000,
+    MemNotCached = 0x04000000,
+    MemNotPaged = 0x08000000,
+    MemShared = 0x10000000,
+    MemExecute = IMAGE_SCN_MEM_EXECUTE,
+    MemRead = IMAGE_SCN_MEM_READ,
+    MemWrite = IMAGE_SCN_MEM_WRITE,
+}
+
+impl SectionCharacteristic {
+    const ALL: [Self; 20] = [
+        Self::TypeNoPad,
+        Self::CntCode,
+        Self::CntInitializedData,
+        Self::CntUninitializedData,
+        Self::LnkOther,
+        Self::LnkInfo,
+        Self::LnkRemove,
+        Self::LnkComdat,
+        Self::Gprel,
+        Self::MemPurgeable,
+        Self::MemLocked,
+        Self::MemPreload,
+        Self::LnkNrelocOvfl,
+        Self::MemDiscardable,
+        Self::MemNotCached,
+        Self::MemNotPaged,
+        Self::MemShared,
+        Self::MemExecute,
+        Self::MemRead,
+        Self::MemWrite,
+    ];
+
+    /// The flags set in `characteristics`, in the order of their bits.
+    /// The IMAGE_SCN_ALIGN_* values in bits 20 to 23 and the reserved bits are ignored.
+    pub fn flags(characteristics: u32) -> Vec<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|flag| characteristics & *flag as u32 != 0)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum SectionClass {
+    Code,
+    Data,
+    Resource,
+    Packed,
+}
+
+impl SectionClass {
+    const PACKED_ENTROPY_THRESHOLD: f64 = 7.2;
+}
+
 impl fmt::Display for Sections {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         writeln!(f, "Sections")?;
-        for section in self.0.iter() {
+        if f.alternate() {
+            for section in self.sections.iter() {
+                writeln!(f, "{:#}", section)?;
+            }
+            return Ok(());
+        }
+        for section in self.sections.iter() {
             writeln!(f, "{}", section)?;
         }
         Ok(())
@@ -138,7 +700,26 @@ impl fmt::Display for Sections {
 
 impl fmt::Display for Section {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        writeln!(f, "  name: {}", self.name)?;
+        if f.alternate() {
+            write!(
+                f,
+                "  {}: Virtual address: {:#x}, Virtual size: {:#x}, Raw data: {:#x}+{:#x}, Characteristics: {:#010x}",
+                self.name,
+                self.vir_addr,
+                self.vir_size,
+                self.ptr_to_raw_data,
+                self.size_of_raw_data,
+                self.characteristics
+            )?;
+            return match describe_section(&self.name) {
+                Some(description) => write!(f, " ({})", description),
+                None => Ok(()),
+            };
+        }
+        match describe_section(&self.name) {
+            Some(description) => writeln!(f, "  name: {} ({})", self.name, description)?,
+            None => writeln!(f, "  name: {}", self.name)?,
+        }
         writeln!(f, "  Virtual size: {}", self.vir_size)?;
         writeln!(f, "  Virtual Address: {}", self.vir_addr)?;
         writeln!(f, "  Size of raw data: {}", self.size_of_raw_data)?;
@@ -150,3 +731,227 @@ impl fmt::Display for Section {
         writeln!(f, "  Characteristics: {}", self.characteristics)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(name: &str, vir_addr: u32, vir_size: u32, ptr_to_raw_data: u32) -> Section {
+        Section::for_test(name, vir_addr, vir_size, ptr_to_raw_data, vir_size, 0)
+    }
+
+    #[test]
+    fn flat_mapped_lookup() {
+        // file_alignment == section_alignment == 0x200, the sections are adjacent and the first has no vir_size
+        let mut text = section(".text", 0x200, 0x200, 0x200);
+        text.vir_size = 0;
+        let sections = Sections::new(vec![text, section(".data", 0x400, 0x200, 0x400)], 0);
+        assert_eq!(sections.find_by_address(0x3ff).unwrap().name, ".text");
+        assert_eq!(sections.find_by_address(0x400).unwrap().name, ".data");
+        assert_eq!(sections.rva_to_offset(0x234), Some(0x234));
+        assert_eq!(sections.rva_to_offset(0x456), Some(0x456));
+        assert!(sections.find_by_address(0x600).is_none());
+    }
+
+    #[test]
+    fn embedded_null_name() {
+        let mut header = b".te\0xt\0\0".to_vec();
+        header.resize(Section::SIZE, 0);
+        let (_, section) = Section::parse(&header, &ParseContext::default()).unwrap();
+        assert_eq!(section.name, ".te");
+        assert_eq!(&section.raw_name(), b".te\0xt\0\0");
+    }
+
+    #[test]
+    fn characteristic_flags() {
+        let mut text = section(".text", 0x1000, 0x200, 0x400);
+        // IMAGE_SCN_ALIGN_16BYTES is not a flag
+        text.characteristics = 0x60500020;
+        assert_eq!(
+            text.characteristic_flags(),
+            vec![
+                SectionCharacteristic::CntCode,
+                SectionCharacteristic::MemExecute,
+                SectionCharacteristic::MemRead
+            ]
+        );
+        assert_eq!(text.permissions(), "r-x");
+        assert_eq!(text.raw_characteristics(), 0x60500020);
+    }
+
+    #[test]
+    fn entropy_and_class() {
+        // zeros then every byte value once
+        let mut pe_file = vec![0u8; 0x100];
+        pe_file.extend(0..=255u8);
+        let zeros = |name, characteristics| {
+            let mut section = section(name, 0x1000, 0x100, 0);
+            section.characteristics = characteristics;
+            section
+        };
+        let text = zeros(".text", 0x60000020);
+        assert!(text.contains_code() && text.is_executable() && text.is_readable());
+        assert!(!text.is_writable());
+        assert_eq!(text.entropy(&pe_file), 0.0);
+        assert_eq!(text.classify(&pe_file), SectionClass::Code);
+        let data = zeros(".data", 0xc0000040);
+        assert!(data.is_writable() && !data.contains_code());
+        assert_eq!(data.classify(&pe_file), SectionClass::Data);
+        assert_eq!(
+            zeros(".rsrc", 0x40000040).classify(&pe_file),
+            SectionClass::Resource
+        );
+
+        let packed = section("UPX1", 0x2000, 0x100, 0x100);
+        assert_eq!(packed.raw_data(&pe_file).len(), 0x100);
+        assert_eq!(packed.entropy(&pe_file), 8.0);
+        assert_eq!(packed.classify(&pe_file), SectionClass::Packed);
+        // the raw data is clamped to the file
+        let truncated = section(".data", 0x3000, 0x200, 0x180);
+        assert_eq!(truncated.raw_data(&pe_file).len(), 0x80);
+        assert_eq!(section(".bss", 0x4000, 0x100, 0x400).entropy(&pe_file), 0.0);
+    }
+
+    #[test]
+    fn section_descriptions() {
+        assert_eq!(describe_section(".pdata"), Some("exception handlers"));
+        assert_eq!(describe_section(".CRT"), Some("C runtime initializers"));
+        assert_eq!(describe_section(".crt"), None);
+        assert_eq!(
+            section(".rsrc", 0, 0, 0).to_string().lines().next(),
+            Some("  name: .rsrc (resources)")
+        );
+        assert_eq!(SectionName::Tls.description(), "thread-local storage");
+    }
+
+    #[test]
+    fn overlapping_sections() {
+        let sections = Sections::new(
+            vec![
+                section(".text", 0x2000, 0x1000, 0x400),
+                section(".data", 0x1000, 0x1800, 0x1400),
+                section(".rsrc", 0x3000, 0x1000, 0x2400),
+            ],
+            0,
+        );
+        assert_eq!(sections.overlapping(), vec![(0, 1)]);
+        assert!(sections.has_overlapping_sections());
+        // the first section in the table wins regardless of the addresses
+        assert_eq!(sections.find_by_address(0x2400).unwrap().name, ".text");
+        assert_eq!(sections.rva_to_offset(0x2400), Some(0x800));
+        assert_eq!(sections.find_by_address(0x1400).unwrap().name, ".data");
+        assert_eq!(sections.find_by_address(0x3000).unwrap().name, ".rsrc");
+
+        // a zero virtual size maps the raw data size
+        let mut text = section(".text", 0x1000, 0x1000, 0x400);
+        text.vir_size = 0;
+        let sections = Sections::new(vec![text, section(".data", 0x1800, 0x800, 0x1400)], 0);
+        assert_eq!(sections.overlapping(), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn raw_overlapping_sections() {
+        let sections = Sections::new(
+            vec![
+                section(".text", 0x1000, 0x1000, 0x400),
+                section(".bss", 0x2000, 0, 0x400),
+                section(".data", 0x3000, 0x200, 0x1200),
+                section(".rsrc", 0x4000, 0x200, 0x1400),
+            ],
+            0,
+        );
+        // .data shares its bytes with the end of .text, and .bss has no raw data
+        assert_eq!(sections.raw_overlapping(), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn extended_relocation_count() {
+        let mut pe_file = vec![0u8; 0x10];
+        // the first record holds the count including itself
+        pe_file.extend_from_slice(&[3, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        pe_file.extend_from_slice(&[0x10, 0, 0, 0, 1, 0, 0, 0, 6, 0]);
+        pe_file.extend_from_slice(&[0x20, 0, 0, 0, 2, 0, 0, 0, 6, 0]);
+        let section = Section {
+            ptr_to_relocs: 0x10,
+            num_of_relocs: 0xffff,
+            characteristics: IMAGE_SCN_LNK_NRELOC_OVFL,
+            ..section(".text", 0, 0, 0)
+        };
+        let relocations = section.relocations(&pe_file);
+        assert_eq!(relocations.len(), 2);
+        assert_eq!(relocations[1].virtual_address, 0x20);
+        assert_eq!(relocations[1].symbol_index, 2);
+    }
+
+    #[test]
+    fn rva_to_offset_overflow() {
+        let section = section(".text", 0x1000, 0x2000, u32::MAX - 0x10);
+        assert_eq!(section.rva_to_offset(0x1010), Some(u32::MAX));
+        assert_eq!(section.rva_to_offset(0x1011), None);
+        assert_eq!(section.rva_to_offset(0xfff), None);
+    }
+
+    #[test]
+    fn extract_strings() {
+        let mut pe_file = vec![0u8; 0x10];
+        pe_file.extend_from_slice(b"\x01kernel32.dll\0ab\x01");
+        pe_file.extend_from_slice(b"H\0e\0l\0l\0o\0\0\0");
+        pe_file.extend_from_slice(b"tail");
+        let section = section(".rdata", 0, (pe_file.len() - 0x10) as u32, 0x10);
+        let strings = section.extract_strings(&pe_file, 4);
+        let found: Vec<(usize, StringEncoding, &str)> = strings
+            .iter()
+            .map(|string| (string.offset, string.encoding, string.value.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (0x11, StringEncoding::Ascii, "kernel32.dll"),
+                (0x21, StringEncoding::Utf16Le, "Hello"),
+                (0x2d, StringEncoding::Ascii, "tail"),
+            ]
+        );
+    }
+
+    #[test]
+    fn range_in_single_section() {
+        let sections = Sections::new(
+            vec![
+                section(".text", 0x1000, 0x1000, 0x400),
+                section(".rdata", 0x2000, 0x800, 0x1400),
+            ],
+            0,
+        );
+        let found = |rva, len| {
+            sections
+                .range_in_single_section(rva, len)
+                .map(|section| section.name.as_str())
+        };
+        assert_eq!(found(0x1f00, 0x100), Some(".text"));
+        assert_eq!(found(0x2000, 0x28), Some(".rdata"));
+        // a structure straddling .text and .rdata
+        assert_eq!(found(0x1ff0, 0x28), None);
+        assert_eq!(found(0x27f0, 0x28), None);
+        assert_eq!(found(0xffff_fff0, 0x28), None);
+    }
+
+    #[test]
+    fn section_for_offset() {
+        let sections = Sections::new(
+            vec![
+                section(".text", 0x1000, 0x1000, 0x400),
+                section(".bss", 0x2000, 0, 0),
+                section(".data", 0x3000, 0x200, 0x1400),
+            ],
+            0,
+        );
+        assert_eq!(
+            sections.section_for_offset(0x3a0).map(|s| s.name.as_str()),
+            None
+        );
+        assert_eq!(sections.section_for_offset(0x400).unwrap().name, ".text");
+        assert_eq!(sections.section_for_offset(0x13ff).unwrap().name, ".text");
+        assert_eq!(sections.section_for_offset(0x1400).unwrap().name, ".data");
+        assert!(sections.section_for_offset(0x1600).is_none());
+    }
+}
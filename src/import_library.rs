@@ -0,0 +1,231 @@
+use crate::headers::nt::Machine;
+use crate::{errors, parse, utils};
+use derive_more::Display;
+use derive_try_from_primitive::TryFromPrimitive;
+use nom::bytes::complete::tag;
+use nom::error::context;
+use nom::number::complete::{le_u16, le_u32};
+use nom::sequence::tuple;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// A short import member of a COFF import library (`.lib`), describing a single symbol imported from a DLL.
+/// The linker synthesizes the import descriptor and the thunk of the symbol from it.
+///
+/// Reference: https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#import-library-format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortImport {
+    pub version: u16,
+    pub machine: Machine,
+    pub time_date_stamp: u32,
+    pub size_of_data: u32, // the size of the strings following the header
+    pub ordinal_or_hint: u16,
+    pub import_type: ImportType,
+    pub name_type: ImportNameType,
+    pub symbol_name: String,
+    pub dll_name: String,
+    pub export_name: Option<String>, // the name to import by for ImportNameType::ExportAs
+}
+
+impl ShortImport {
+    pub const HEADER_SIZE: usize = 20;
+    // IMAGE_FILE_MACHINE_UNKNOWN followed by 0xffff, where an object file has its machine and section count
+    const SIGNATURE: &'static [u8] = &[0x00, 0x00, 0xff, 0xff];
+
+    /// Whether the member data starts with the signature of a short import
+    pub fn is_short_import(member: &[u8]) -> bool {
+        member.starts_with(Self::SIGNATURE)
+    }
+
+    pub fn parse(i: parse::Input) -> parse::Result<Self> {
+        let (i, _) = context("Signature", tag(Self::SIGNATURE))(i)?;
+        let (i, (version, machine, time_date_stamp, size_of_data, ordinal_or_hint, types)) =
+            tuple((
+                context("Version", le_u16),
+                context("Machine", Machine::parse),
+                context("TimeDateStamp", le_u32),
+                context("SizeOfData", le_u32),
+                context("OrdinalOrHint", le_u16),
+                context("Type", le_u16),
+            ))(i)?;
+        let import_type = ImportType::try_from(types & 0x3).map_err(|typ| {
+            errors::PEError::from_string(i, format!("unknown import type {}", typ))
+        })?;
+        let name_type = ImportNameType::try_from((types >> 2) & 0x7).map_err(|typ| {
+            errors::PEError::from_string(i, format!("unknown import name type {}", typ))
+        })?;
+        let data = &i[..i.len().min(size_of_data as usize)];
+        let mut strings = data
+            .split(|b| *b == 0)
+            .map(utils::read_null_terminated_string);
+        let symbol_name = strings.next().unwrap_or_default();
+        let dll_name = strings.next().unwrap_or_default();
+        let export_name = match name_type {
+            ImportNameType::ExportAs => strings.next(),
+            _ => None,
+        };
+        Ok((
+            &i[data.len()..],
+            Self {
+                version,
+                machine,
+                time_date_stamp,
+                size_of_data,
+                ordinal_or_hint,
+                import_type,
+                name_type,
+                symbol_name,
+                dll_name,
+                export_name,
+            },
+        ))
+    }
+
+    /// The ordinal the symbol is imported by, None when it is imported by name and the value is a hint
+    pub fn ordinal(&self) -> Option<u16> {
+        match self.name_type {
+            ImportNameType::Ordinal => Some(self.ordinal_or_hint),
+            _ => None,
+        }
+    }
+
+    /// The name looked up in the exports of the DLL, derived from the symbol name by the name type.
+    /// None for imports by ordinal.
+    pub fn import_name(&self) -> Option<String> {
+        let name = self.symbol_name.as_str();
+        match self.name_type {
+            ImportNameType::Ordinal => None,
+            ImportNameType::Name => Some(name.to_string()),
+            ImportNameType::NameNoPrefix => {
+                Some(name.trim_start_matches(['?', '@', '_']).to_string())
+            }
+            // e.g. "_Sleep@4" is imported as "Sleep"
+            ImportNameType::NameUndecorate => {
+                let name = name.trim_start_matches(['?', '@', '_']);
+                Some(name.split('@').next().unwrap_or(name).to_string())
+            }
+            ImportNameType::ExportAs => self.export_name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, Display)]
+#[repr(u16)]
+pub enum ImportType {
+    Code = 0,
+    Data = 1,
+    Const = 2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, Display)]
+#[repr(u16)]
+pub enum ImportNameType {
+    Ordinal = 0,
+    Name = 1,
+    NameNoPrefix = 2,   // the name without the leading ?, @ or _
+    NameUndecorate = 3, // also without the suffix starting at the first @
+    ExportAs = 4,       // the name following the DLL name
+}
+
+/// The short imports of an import library, which is an archive of members like a static library.
+/// Other members, e.g. the linker members or the object files holding the import descriptors, are skipped,
+/// as are the short imports failing to parse.
+pub fn short_imports(archive: &[u8]) -> Vec<ShortImport> {
+    const MAGIC: &[u8] = b"!<arch>\n";
+    const MEMBER_HEADER_SIZE: usize = 60;
+    let mut imports = vec![];
+    if !archive.starts_with(MAGIC) {
+        return imports;
+    }
+    let mut offset = MAGIC.len();
+    while let Some(header) = archive.get(offset..offset + MEMBER_HEADER_SIZE) {
+        // the size is in decimal ASCII padded with spaces
+        let size = match std::str::from_utf8(&header[48..58])
+            .ok()
+            .and_then(|size| size.trim_end().parse::<usize>().ok())
+        {
+            Some(size) => size,
+            None => break,
+        };
+        let start = offset + MEMBER_HEADER_SIZE;
+        let end = start.saturating_add(size).min(archive.len());
+        let member = &archive[start..end];
+        if ShortImport::is_short_import(member) {
+            if let Ok((_, import)) = ShortImport::parse(member) {
+                imports.push(import);
+            }
+        }
+        // members are aligned on 2 bytes
+        offset = end + (end & 1);
+    }
+    imports
+}
+
+impl fmt::Display for ShortImport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}!{}: Machine: {}, Type: {}, NameType: {}",
+            self.dll_name, self.symbol_name, self.machine, self.import_type, self.name_type
+        )?;
+        match self.ordinal() {
+            Some(ordinal) => write!(f, ", Ordinal: {}", ordinal),
+            None => write!(f, ", Hint: {}", self.ordinal_or_hint),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{short_imports, ImportNameType, ImportType, ShortImport};
+    use crate::headers::nt::Machine;
+
+    fn member(symbol_name: &str, dll_name: &str, types: u16) -> Vec<u8> {
+        let strings = format!("{}\0{}\0", symbol_name, dll_name);
+        let mut member = vec![0x00, 0x00, 0xff, 0xff];
+        member.extend_from_slice(&0u16.to_le_bytes());
+        member.extend_from_slice(&0x14cu16.to_le_bytes());
+        member.extend_from_slice(&0u32.to_le_bytes());
+        member.extend_from_slice(&(strings.len() as u32).to_le_bytes());
+        member.extend_from_slice(&7u16.to_le_bytes());
+        member.extend_from_slice(&types.to_le_bytes());
+        member.extend_from_slice(strings.as_bytes());
+        member
+    }
+
+    #[test]
+    fn import_library() {
+        let mut archive = b"!<arch>\n".to_vec();
+        for data in [
+            member("_Sleep@4", "KERNEL32.dll", 3 << 2),
+            member("_timezone", "msvcrt.dll", 1 | 2 << 2),
+        ] {
+            let mut header = format!(
+                "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+                "/",
+                0,
+                0,
+                0,
+                0,
+                data.len()
+            )
+            .into_bytes();
+            header.extend_from_slice(&data);
+            if data.len() % 2 == 1 {
+                header.push(b'\n');
+            }
+            archive.extend_from_slice(&header);
+        }
+
+        let imports = short_imports(&archive);
+        assert_eq!(imports.len(), 2);
+        assert_eq!(imports[0].machine, Machine::I386);
+        assert_eq!(imports[0].dll_name, "KERNEL32.dll");
+        assert_eq!(imports[0].import_type, ImportType::Code);
+        assert_eq!(imports[0].name_type, ImportNameType::NameUndecorate);
+        assert_eq!(imports[0].import_name().as_deref(), Some("Sleep"));
+        assert_eq!(imports[1].import_type, ImportType::Data);
+        assert_eq!(imports[1].import_name().as_deref(), Some("timezone"));
+        assert!(!ShortImport::is_short_import(b"\x4c\x01\x03\x00"));
+    }
+}
@@ -0,0 +1,235 @@
+use crate::options::ParseContext;
+use crate::{errors, parse};
+use nom::error::context;
+use nom::number::complete::{le_u16, le_u32};
+use nom::sequence::tuple;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// The bound import descriptors pointed by the bound import data directory, recording the timestamps
+/// of the DLLs whose addresses were written in the IAT by `bind.exe`.
+/// The loader uses the bound addresses as is when the timestamps match the loaded DLLs.
+#[derive(Debug)]
+pub struct BoundImportTable(Vec<BoundImportDescriptor>);
+
+impl BoundImportTable {
+    /// Parses the descriptors from the bytes of the directory, whose module names are at offsets from its start.
+    /// The directory is usually located in the headers, after the section table.
+    /// Fails only on a module name that is not valid UTF-8 in `StringMode::Strict`.
+    pub fn parse<'a>(
+        directory: parse::Input<'a>,
+        ctx: &ParseContext,
+    ) -> Result<Self, nom::Err<errors::PEError<parse::Input<'a>>>> {
+        let mut input = directory;
+        let mut descriptors = vec![];
+        // the table ends with a zeroed descriptor, or at the end of the directory when it is missing
+        loop {
+            let (i, descriptor) = match BoundImportDescriptor::parse(directory, input, ctx) {
+                Ok(result) => result,
+                Err(nom::Err::Failure(e)) => return Err(nom::Err::Failure(e)),
+                Err(_) => break,
+            };
+            if descriptor.time_date_stamp == 0 && descriptor.offset_module_name == 0 {
+                break;
+            }
+            descriptors.push(descriptor);
+            input = i;
+        }
+        Ok(Self(descriptors))
+    }
+
+    pub fn descriptors(&self) -> &[BoundImportDescriptor] {
+        &self.0
+    }
+
+    /// The names of the bound DLLs and of the DLLs they forward to whose binding is stale, given the timestamps
+    /// of the DLLs found at load time. A DLL without timestamp, e.g. not found, is stale as well.
+    /// The loader ignores the bound addresses of a descriptor when it or one of its forwarder refs is stale.
+    pub fn stale_modules<F>(&self, timestamp_of: F) -> Vec<&str>
+    where
+        F: Fn(&str) -> Option<u32>,
+    {
+        let is_stale = |module_name: &str, time_date_stamp: u32| {
+            timestamp_of(module_name).is_none_or(|timestamp| timestamp != time_date_stamp)
+        };
+        let mut modules = vec![];
+        for descriptor in &self.0 {
+            if is_stale(&descriptor.module_name, descriptor.time_date_stamp) {
+                modules.push(descriptor.module_name.as_str());
+            }
+            for forwarder_ref in &descriptor.forwarder_refs {
+                if is_stale(&forwarder_ref.module_name, forwarder_ref.time_date_stamp) {
+                    modules.push(forwarder_ref.module_name.as_str());
+                }
+            }
+        }
+        modules
+    }
+}
+
+/// An IMAGE_BOUND_IMPORT_DESCRIPTOR followed by its IMAGE_BOUND_FORWARDER_REFs,
+/// which record the DLLs the functions of the module are forwarded to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundImportDescriptor {
+    pub time_date_stamp: u32,
+    pub offset_module_name: u16, // offset from the start of the directory
+    pub module_name: String,
+    pub forwarder_refs: Vec<BoundForwarderRef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundForwarderRef {
+    pub time_date_stamp: u32,
+    pub offset_module_name: u16,
+    pub module_name: String,
+}
+
+impl BoundForwarderRef {
+    /// Whether the DLL forwarded to changed since it was bound, see `BoundImportDescriptor::is_stale`
+    pub fn is_stale(&self, dll_timestamp: u32) -> bool {
+        self.time_date_stamp != dll_timestamp
+    }
+}
+
+impl BoundImportDescriptor {
+    pub const SIZE: usize = 8;
+
+    /// Whether the DLL changed since it was bound, given its timestamp found at load time,
+    /// e.g. the file header timestamp or `ExportDirectoryTable::time_date_stamp`, which linkers write equal
+    pub fn is_stale(&self, dll_timestamp: u32) -> bool {
+        self.time_date_stamp != dll_timestamp
+    }
+
+    fn parse<'a>(
+        directory: parse::Input<'a>,
+        i: parse::Input<'a>,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Self> {
+        let (mut i, (time_date_stamp, offset_module_name, num_of_forwarder_refs)) = tuple((
+            context("TimeDateStamp", le_u32),
+            context("OffsetModuleName", le_u16),
+            context("NumberOfModuleForwarderRefs", le_u16),
+        ))(i)?;
+        let mut forwarder_refs = vec![];
+        for _ in 0..num_of_forwarder_refs {
+            let (rest, (time_date_stamp, offset_module_name, _reserved)) = tuple((
+                context("TimeDateStamp", le_u32),
+                context("OffsetModuleName", le_u16),
+                context("Reserved", le_u16),
+            ))(i)?;
+            forwarder_refs.push(BoundForwarderRef {
+                time_date_stamp,
+                offset_module_name,
+                module_name: Self::read_name(directory, offset_module_name, ctx)?,
+            });
+            i = rest;
+        }
+        Ok((
+            i,
+            Self {
+                time_date_stamp,
+                offset_module_name,
+                module_name: Self::read_name(directory, offset_module_name, ctx)?,
+                forwarder_refs,
+            },
+        ))
+    }
+
+    /// Reads the module name at the offset in the string mode of the parse, see `ParseContext::read_name`
+    fn read_name<'a>(
+        directory: parse::Input<'a>,
+        offset: u16,
+        ctx: &ParseContext,
+    ) -> Result<String, nom::Err<errors::PEError<parse::Input<'a>>>> {
+        match directory.get(offset as usize..) {
+            Some(slice) => ctx.read_name(directory, slice, "bound import module name"),
+            None => Ok(String::new()),
+        }
+    }
+}
+
+impl fmt::Display for BoundImportTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  BoundImportTable:")?;
+        for descriptor in &self.0 {
+            writeln!(
+                f,
+                "    {}: TimeDateStamp: {:#x}",
+                descriptor.module_name, descriptor.time_date_stamp
+            )?;
+            for forwarder_ref in &descriptor.forwarder_refs {
+                writeln!(
+                    f,
+                    "      forwarded to {}: TimeDateStamp: {:#x}",
+                    forwarder_ref.module_name, forwarder_ref.time_date_stamp
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BoundImportTable;
+    use crate::options::{ParseContext, ParseOptions, StringMode};
+
+    #[test]
+    fn forwarder_refs() {
+        let mut directory = vec![];
+        directory.extend_from_slice(&0x4a5bc60fu32.to_le_bytes());
+        directory.extend_from_slice(&0x20u16.to_le_bytes());
+        directory.extend_from_slice(&1u16.to_le_bytes());
+        directory.extend_from_slice(&0x4a5bdb3cu32.to_le_bytes());
+        directory.extend_from_slice(&0x2du16.to_le_bytes());
+        directory.extend_from_slice(&0u16.to_le_bytes());
+        directory.extend_from_slice(&0x4a5bc000u32.to_le_bytes());
+        directory.extend_from_slice(&0x37u16.to_le_bytes());
+        directory.extend_from_slice(&0u16.to_le_bytes());
+        directory.resize(0x20, 0);
+        directory.extend_from_slice(b"KERNEL32.dll\0NTDLL.DLL\0USER32.dll\0");
+
+        let table = BoundImportTable::parse(&directory, &ParseContext::default()).unwrap();
+        let descriptors = table.descriptors();
+        assert_eq!(descriptors.len(), 2);
+        assert_eq!(descriptors[0].module_name, "KERNEL32.dll");
+        assert_eq!(descriptors[0].forwarder_refs[0].module_name, "NTDLL.DLL");
+        assert_eq!(descriptors[0].forwarder_refs[0].time_date_stamp, 0x4a5bdb3c);
+        assert_eq!(descriptors[1].module_name, "USER32.dll");
+        assert!(descriptors[1].forwarder_refs.is_empty());
+
+        assert!(!descriptors[0].is_stale(0x4a5bc60f));
+        assert!(descriptors[0].forwarder_refs[0].is_stale(0x5b000000));
+        // NTDLL.DLL was updated and USER32.dll is not found
+        let timestamp_of = |module_name: &str| match module_name {
+            "KERNEL32.dll" => Some(0x4a5bc60f),
+            "NTDLL.DLL" => Some(0x5b000000),
+            _ => None,
+        };
+        assert_eq!(
+            table.stale_modules(timestamp_of),
+            vec!["NTDLL.DLL", "USER32.dll"]
+        );
+    }
+
+    #[test]
+    fn invalid_module_name() {
+        let mut directory = vec![];
+        directory.extend_from_slice(&0x4a5bc60fu32.to_le_bytes());
+        directory.extend_from_slice(&0x10u16.to_le_bytes());
+        directory.extend_from_slice(&0u16.to_le_bytes());
+        directory.resize(0x10, 0);
+        directory.extend_from_slice(b"KERNEL\xff.dll\0");
+
+        let table = BoundImportTable::parse(&directory, &ParseContext::default()).unwrap();
+        assert_eq!(table.descriptors()[0].module_name, "KERNEL\u{fffd}.dll");
+
+        let options = ParseOptions {
+            string_mode: StringMode::Strict,
+            ..ParseOptions::default()
+        };
+        assert!(
+            BoundImportTable::parse(&directory, &ParseContext::from_options(&options)).is_err()
+        );
+    }
+}
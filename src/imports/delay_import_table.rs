@@ -0,0 +1,436 @@
+use crate::headers::nt::{DataDirectory, DirectoryEntry};
+use crate::headers::sections::{Section, Sections};
+use crate::imports::import_directory_table::{ImportDescriptor, ImportEntries, ImportEntry};
+use crate::options::ParseContext;
+use crate::{parse, utils};
+use nom::error::context;
+use nom::number::complete::le_u32;
+use nom::sequence::tuple;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// The delay-load import descriptors pointed by the delay import data directory,
+/// whose DLLs are loaded on the first call of one of their functions instead of at startup
+#[derive(Debug)]
+pub struct DelayImportTable(Vec<DelayImportDescriptor>, DelayImportLocation);
+
+/// Where the descriptors were found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelayImportLocation {
+    // the delay import data directory, wherever the linker put the descriptors
+    Directory,
+    // the start of the .didat section, for linkers which put the descriptors there without setting the directory
+    DidatSection,
+}
+
+impl DelayImportTable {
+    pub fn parse<'a>(
+        pe_file: parse::Input<'a>,
+        delay_import_directory: DataDirectory,
+        sections: &Sections,
+        is_64bit: bool,
+        image_base: u64,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Option<Self>> {
+        sections.check_directory_mapped(
+            pe_file,
+            delay_import_directory,
+            "delay import directory",
+        )?;
+        let mut input = match sections
+            .rva_to_offset(delay_import_directory.virtual_address)
+            .and_then(|offset| pe_file.get(offset as usize..))
+        {
+            Some(data) => data,
+            None => return Ok((pe_file, None)),
+        };
+        let mut descriptors_budget = ctx.budget().table("delay import directory table");
+        let mut descriptors = vec![];
+        // the table ends with a zeroed descriptor, or at the end of the file when it is missing
+        while input.len() >= DelayImportDescriptor::SIZE {
+            descriptors_budget.reserve(input, 1)?;
+            let (i, descriptor) =
+                DelayImportDescriptor::parse(pe_file, input, sections, is_64bit, image_base, ctx)?;
+            if descriptor.name_rva == 0 && descriptor.iat_rva == 0 {
+                break;
+            }
+            descriptors.push(descriptor);
+            input = i;
+        }
+        Ok((
+            input,
+            Some(Self(descriptors, DelayImportLocation::Directory)),
+        ))
+    }
+
+    /// Parses the descriptors at the start of the .didat section, for images whose delay import directory is absent.
+    /// Recent linkers only put the delay IATs and module handles in .didat, so the section is only
+    /// taken for the descriptors when it starts with a descriptor using RVAs whose name is mapped.
+    pub fn parse_didat<'a>(
+        pe_file: parse::Input<'a>,
+        sections: &Sections,
+        is_64bit: bool,
+        image_base: u64,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Option<Self>> {
+        let didat = match sections.didat() {
+            Some(didat) => didat,
+            None => return Ok((pe_file, None)),
+        };
+        let first = sections
+            .rva_to_offset(didat.vir_addr)
+            .and_then(|offset| pe_file.get(offset as usize..))
+            .unwrap_or_default();
+        let looks_like_descriptor =
+            match (utils::read_u32_at(first, 0), utils::read_u32_at(first, 4)) {
+                (Some(attributes), Some(name_rva)) => {
+                    attributes & DelayImportDescriptor::RVA_BASED != 0
+                        && sections.rva_to_offset(name_rva).is_some()
+                }
+                _ => false,
+            };
+        if !looks_like_descriptor {
+            return Ok((pe_file, None));
+        }
+        let directory = DataDirectory {
+            entry: DirectoryEntry::DelayImport,
+            virtual_address: didat.vir_addr,
+            size: didat.mapped_size(),
+        };
+        let (i, table) = Self::parse(pe_file, directory, sections, is_64bit, image_base, ctx)?;
+        Ok((
+            i,
+            table
+                .filter(|table| !table.0.is_empty())
+                .map(|table| Self(table.0, DelayImportLocation::DidatSection)),
+        ))
+    }
+
+    pub fn descriptors(&self) -> &[DelayImportDescriptor] {
+        &self.0
+    }
+
+    pub fn location(&self) -> DelayImportLocation {
+        self.1
+    }
+}
+
+/// An ImgDelayDescr, whose fields are RVAs when bit 0 of the attributes is set, as linkers have done since VC++ 7.
+/// Older linkers wrote virtual addresses instead, which are converted to RVAs with the image base while parsing.
+#[derive(Debug)]
+pub struct DelayImportDescriptor {
+    pub attributes: u32,
+    pub name_rva: u32,
+    pub module_handle_rva: u32, // the HMODULE written by the delay-load helper
+    pub iat_rva: u32,
+    pub int_rva: u32, // the import name table, the counterpart of the import lookup table
+    pub bound_iat_rva: u32,
+    pub unload_iat_rva: u32, // a copy of the IAT restored when the DLL is unloaded
+    pub time_date_stamp: u32, // the timestamp of the DLL the IAT is bound to
+    name: Option<String>,
+    entries: ImportEntries,
+    // the addresses of the bound delay IAT, when the delay imports are bound
+    bound_addresses: Option<Vec<u64>>,
+}
+
+impl DelayImportDescriptor {
+    pub const SIZE: usize = 32;
+    const RVA_BASED: u32 = 0x1; // dlattrRva
+
+    fn parse<'a>(
+        pe_file: parse::Input<'a>,
+        i: parse::Input<'a>,
+        sections: &Sections,
+        is_64bit: bool,
+        image_base: u64,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Self> {
+        let (
+            i,
+            (
+                attributes,
+                name_rva,
+                module_handle_rva,
+                iat_rva,
+                int_rva,
+                bound_iat_rva,
+                unload_iat_rva,
+                time_date_stamp,
+            ),
+        ) = tuple((
+            context("Attributes", le_u32),
+            context("DllNameRVA", le_u32),
+            context("ModuleHandleRVA", le_u32),
+            context("ImportAddressTableRVA", le_u32),
+            context("ImportNameTableRVA", le_u32),
+            context("BoundImportAddressTableRVA", le_u32),
+            context("UnloadInformationTableRVA", le_u32),
+            context("TimeDateStamp", le_u32),
+        ))(i)?;
+        // the virtual addresses of old descriptors, and of their import name tables, are converted to RVAs
+        let address_base = if attributes & Self::RVA_BASED != 0 {
+            0
+        } else {
+            image_base
+        };
+        let to_rva = |address: u32| match address {
+            0 => 0,
+            address => (address as u64).wrapping_sub(address_base) as u32,
+        };
+        let name_rva = to_rva(name_rva);
+        let module_handle_rva = to_rva(module_handle_rva);
+        let iat_rva = to_rva(iat_rva);
+        let int_rva = to_rva(int_rva);
+        let bound_iat_rva = to_rva(bound_iat_rva);
+        let unload_iat_rva = to_rva(unload_iat_rva);
+
+        let name = ImportDescriptor::get_dll_name(pe_file, name_rva, sections, ctx)?;
+        let entries = ImportEntries::parse_based(
+            pe_file,
+            name.as_deref().unwrap_or_default(),
+            int_rva,
+            sections,
+            is_64bit,
+            address_base,
+            ctx,
+        )?;
+        let bound_addresses = match sections.rva_to_offset(bound_iat_rva) {
+            Some(offset) if time_date_stamp != 0 && bound_iat_rva != 0 => {
+                let thunks = ImportDescriptor::read_thunks(pe_file, offset as usize, is_64bit);
+                ctx.budget()
+                    .table("bound delay import address table")
+                    .reserve(pe_file, thunks.len())?;
+                Some(thunks)
+            }
+            _ => None,
+        };
+        Ok((
+            i,
+            Self {
+                attributes,
+                name_rva,
+                module_handle_rva,
+                iat_rva,
+                int_rva,
+                bound_iat_rva,
+                unload_iat_rva,
+                time_date_stamp,
+                name,
+                entries,
+                bound_addresses,
+            },
+        ))
+    }
+
+    /// Whether the descriptor was written with RVAs rather than virtual addresses, see `attributes`
+    pub fn is_rva_based(&self) -> bool {
+        self.attributes & Self::RVA_BASED != 0
+    }
+
+    /// The name of the delay-loaded DLL, or None when the descriptor has no name
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The delay-loaded functions, by name or by ordinal
+    pub fn functions(&self) -> &[ImportEntry] {
+        self.entries.as_slice()
+    }
+
+    /// Whether the linker bound the delay IAT to the addresses of the DLL with the given timestamp.
+    /// The helper uses the bound addresses when the timestamp still matches the DLL at load time.
+    pub fn is_bound(&self) -> bool {
+        self.time_date_stamp != 0 && self.bound_iat_rva != 0
+    }
+
+    /// The addresses of the bound delay IAT, in the order of the import name table.
+    /// None when the delay imports are not bound.
+    pub fn bound_addresses(&self) -> Option<&[u64]> {
+        self.bound_addresses.as_deref()
+    }
+
+    /// The section holding the delay IAT, which is `.didat` for recent linkers and `.data` for older ones
+    pub fn iat_section<'s>(&self, sections: &'s Sections) -> Option<&'s Section> {
+        sections.find_by_address(self.iat_rva)
+    }
+
+    /// Whether the descriptor has an unload IAT, which lets `__FUnloadDelayLoadedDLL2` unload the DLL
+    pub fn has_unload_iat(&self) -> bool {
+        self.unload_iat_rva != 0
+    }
+}
+
+impl fmt::Display for DelayImportTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "DelayImports")?;
+        for descriptor in &self.0 {
+            writeln!(f, "{}", descriptor)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DelayImportDescriptor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "    Name: {}, Attributes: {:#x}, IAT: {:#x}, INT: {:#x}, BoundIAT: {:#x}, UnloadIAT: {:#x}, TimeDateStamp: {}",
+            self.name.as_deref().unwrap_or("-"),
+            self.attributes,
+            self.iat_rva,
+            self.int_rva,
+            self.bound_iat_rva,
+            self.unload_iat_rva,
+            self.time_date_stamp
+        )?;
+        if let Some(bound_addresses) = &self.bound_addresses {
+            let bound_addresses: Vec<String> = bound_addresses
+                .iter()
+                .map(|address| format!("{:#x}", address))
+                .collect();
+            writeln!(f, "      BoundAddresses: {}", bound_addresses.join(", "))?;
+        }
+        write!(f, "{}", self.entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DelayImportLocation, DelayImportTable};
+    use crate::headers::nt::{DataDirectory, DirectoryEntry};
+    use crate::headers::sections::{Section, Sections};
+    use crate::options::ParseContext;
+
+    fn didat() -> Sections {
+        Sections::new(
+            vec![Section::for_test(".didat", 0x1000, 0x1000, 0, 0x1000, 0)],
+            0,
+        )
+    }
+
+    /// A bound descriptor followed by the zeroed terminator at the start of .didat
+    fn delay_imports() -> Vec<u8> {
+        let mut pe_file = vec![];
+        for field in [1u32, 0x1080, 0x1090, 0x10a0, 0x10b0, 0x10c0, 0, 0x5f5e0ff] {
+            pe_file.extend_from_slice(&field.to_le_bytes());
+        }
+        pe_file.resize(0x80, 0);
+        pe_file.extend_from_slice(b"USER32.dll\0");
+        pe_file.resize(0xb0, 0);
+        pe_file.extend_from_slice(&0x80000073u32.to_le_bytes()); // INT, by ordinal
+        pe_file.resize(0xc0, 0);
+        pe_file.extend_from_slice(&0x7e411234u32.to_le_bytes()); // bound IAT
+        pe_file.resize(0x100, 0);
+        pe_file
+    }
+
+    #[test]
+    fn bound_delay_imports() {
+        let sections = didat();
+        let pe_file = delay_imports();
+        let directory = DataDirectory {
+            entry: DirectoryEntry::DelayImport,
+            virtual_address: 0x1000,
+            size: 0x40,
+        };
+        let (_, table) = DelayImportTable::parse(
+            &pe_file,
+            directory,
+            &sections,
+            false,
+            0x400000,
+            &ParseContext::default(),
+        )
+        .unwrap();
+        let table = table.unwrap();
+        assert_eq!(table.location(), DelayImportLocation::Directory);
+        assert_eq!(table.descriptors().len(), 1);
+        let descriptor = &table.descriptors()[0];
+        assert!(descriptor.is_rva_based());
+        assert_eq!(descriptor.name(), Some("USER32.dll"));
+        assert_eq!(descriptor.functions().len(), 1);
+        assert!(descriptor.is_bound());
+        assert!(!descriptor.has_unload_iat());
+        assert_eq!(descriptor.bound_addresses(), Some(&[0x7e411234][..]));
+        assert_eq!(descriptor.iat_section(&sections).unwrap().name, ".didat");
+    }
+
+    #[test]
+    fn virtual_address_descriptor() {
+        // the fields and the import name table of a VC++ 6 descriptor are virtual addresses
+        let mut pe_file = vec![];
+        for field in [0u32, 0x401080, 0x401090, 0x4010a0, 0x4010b0, 0, 0, 0] {
+            pe_file.extend_from_slice(&field.to_le_bytes());
+        }
+        pe_file.resize(0x80, 0);
+        pe_file.extend_from_slice(b"USER32.dll\0");
+        pe_file.resize(0xb0, 0);
+        pe_file.extend_from_slice(&0x4010c0u32.to_le_bytes());
+        pe_file.extend_from_slice(&0x80000073u32.to_le_bytes());
+        pe_file.resize(0xc2, 0);
+        pe_file.extend_from_slice(b"MessageBoxA\0");
+        pe_file.resize(0x100, 0);
+        let directory = DataDirectory {
+            entry: DirectoryEntry::DelayImport,
+            virtual_address: 0x1000,
+            size: 0x40,
+        };
+        let (_, table) = DelayImportTable::parse(
+            &pe_file,
+            directory,
+            &didat(),
+            false,
+            0x400000,
+            &ParseContext::default(),
+        )
+        .unwrap();
+        let table = table.unwrap();
+        let descriptor = &table.descriptors()[0];
+        assert!(!descriptor.is_rva_based());
+        assert_eq!(descriptor.name(), Some("USER32.dll"));
+        assert_eq!(
+            (
+                descriptor.module_handle_rva,
+                descriptor.iat_rva,
+                descriptor.int_rva
+            ),
+            (0x1090, 0x10a0, 0x10b0)
+        );
+        assert_eq!(descriptor.bound_iat_rva, 0);
+        let functions = descriptor
+            .functions()
+            .iter()
+            .map(|function| function.identifier())
+            .collect::<Vec<_>>();
+        assert_eq!(functions, vec!["MessageBoxA", "#115"]);
+    }
+
+    #[test]
+    fn didat_without_directory() {
+        let sections = didat();
+        let mut pe_file = delay_imports();
+        let (_, table) = DelayImportTable::parse_didat(
+            &pe_file,
+            &sections,
+            false,
+            0x400000,
+            &ParseContext::default(),
+        )
+        .unwrap();
+        let table = table.unwrap();
+        assert_eq!(table.location(), DelayImportLocation::DidatSection);
+        assert_eq!(table.descriptors()[0].name(), Some("USER32.dll"));
+
+        // .didat holding only IAT entries, as recent linkers emit it
+        pe_file[..8].copy_from_slice(&[0x34, 0x12, 0x40, 0x00, 0x78, 0x56, 0x40, 0x00]);
+        let (_, table) = DelayImportTable::parse_didat(
+            &pe_file,
+            &sections,
+            false,
+            0x400000,
+            &ParseContext::default(),
+        )
+        .unwrap();
+        assert!(table.is_none());
+    }
+}
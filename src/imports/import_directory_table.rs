@@ -1,34 +1,60 @@
-use crate::headers::sections::{Section, Sections};
-use crate::{parse, utils};
+use crate::headers::sections::Sections;
+use crate::imports::ordinals;
+use crate::options::ParseContext;
+use crate::{errors, parse, utils};
 
+use crate::exports::export_directory_table::ExportDirectoryTable;
 use crate::headers::nt::DataDirectory;
-use byteorder::{ByteOrder, LittleEndian};
 use nom::error::context;
 use nom::number::complete::le_u32;
 use nom::sequence::tuple;
 use std::fmt;
 use std::fmt::Formatter;
 
+/// The flag bit of a PE32 thunk importing a function by ordinal
+pub const IMAGE_ORDINAL_FLAG32: u32 = 0x80000000;
+/// The flag bit of a PE32+ thunk importing a function by ordinal
+pub const IMAGE_ORDINAL_FLAG64: u64 = 0x8000000000000000;
+
+/// Whether the thunk imports a function by ordinal rather than by name
+pub fn is_ordinal(thunk: u64, is_64bit: bool) -> bool {
+    if is_64bit {
+        thunk & IMAGE_ORDINAL_FLAG64 != 0
+    } else {
+        thunk & IMAGE_ORDINAL_FLAG32 as u64 != 0
+    }
+}
+
+/// The ordinal of a thunk importing by ordinal, held in its low 16 bits
+pub fn ordinal_of(thunk: u64) -> u16 {
+    (thunk & 0xffff) as u16
+}
+
 #[derive(Debug)]
 pub struct ImportDirectoryTable(Vec<ImportDescriptor>);
 
 impl ImportDirectoryTable {
-    pub fn parse(
-        pe_file: parse::Input,
+    pub fn parse<'a>(
+        pe_file: parse::Input<'a>,
         import_directory: DataDirectory,
-        sections: Sections,
-    ) -> parse::Result<Self> {
-        match sections.find_by_address(import_directory.virtual_address) {
-            Some(section) => {
-                let offset = section
-                    .rva_to_offset(import_directory.virtual_address)
-                    .unwrap(); // @todo remove it
-                let section_data = &pe_file[offset as usize..];
+        sections: &Sections,
+        is_64bit: bool,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Self> {
+        sections.check_directory_mapped(pe_file, import_directory, "import directory")?;
+        let section_data = sections
+            .rva_to_offset(import_directory.virtual_address)
+            .and_then(|offset| pe_file.get(offset as usize..));
+        match section_data {
+            Some(section_data) => {
                 let mut res = Vec::new();
                 let mut cur_input = section_data;
+                let mut descriptors_budget = ctx.budget().table("import directory table");
 
                 loop {
-                    let (i, descriptor) = ImportDescriptor::parse(pe_file, cur_input, &section)?;
+                    descriptors_budget.reserve(cur_input, 1)?;
+                    let (i, descriptor) =
+                        ImportDescriptor::parse(pe_file, cur_input, sections, is_64bit, ctx)?;
 
                     if descriptor.original_first_thunk == 0
                         && descriptor.time_date_stamp == 0
@@ -39,6 +65,13 @@ impl ImportDirectoryTable {
                         break;
                     }
 
+                    ctx.visit(|visitor| {
+                        let dll_name = descriptor.name().unwrap_or_default();
+                        visitor.on_import_dll(dll_name);
+                        for entry in descriptor.functions() {
+                            visitor.on_import(dll_name, entry);
+                        }
+                    });
                     res.push(descriptor);
                     cur_input = i;
                 }
@@ -53,9 +86,89 @@ impl ImportDirectoryTable {
     }
 }
 
+impl ImportDirectoryTable {
+    pub fn descriptors(&self) -> &[ImportDescriptor] {
+        &self.0
+    }
+
+    /// Serializes the descriptors into a fresh import directory to be mapped at `rva`, laid out as
+    /// the descriptors with their null terminator, the import lookup tables, the hint/name entries and the DLL names.
+    /// `FirstThunk` keeps the RVA of the original import address table,
+    /// which the code of the image refers to and the loader overwrites.
+    pub fn to_bytes(&self, rva: u32, is_64bit: bool) -> Vec<u8> {
+        let thunk_size = if is_64bit { 8 } else { 4 };
+        let descriptors_size = (self.0.len() + 1) * ImportDescriptor::SIZE;
+        let lookup_tables_size: usize = self
+            .0
+            .iter()
+            .map(|descriptor| (descriptor.function_count() + 1) * thunk_size)
+            .sum();
+        let rva_at = |offset: usize| rva.wrapping_add(offset as u32);
+
+        let mut descriptors = Vec::with_capacity(descriptors_size);
+        let mut lookup_tables = Vec::with_capacity(lookup_tables_size);
+        let mut strings = vec![];
+        let strings_offset = descriptors_size + lookup_tables_size;
+        for descriptor in &self.0 {
+            let lookup_table_rva = rva_at(descriptors_size + lookup_tables.len());
+            for entry in descriptor.functions() {
+                let thunk = match entry {
+                    ImportEntry::ByOrdinal { ordinal, .. } if is_64bit => {
+                        IMAGE_ORDINAL_FLAG64 | *ordinal as u64
+                    }
+                    ImportEntry::ByOrdinal { ordinal, .. } => {
+                        (IMAGE_ORDINAL_FLAG32 | *ordinal as u32) as u64
+                    }
+                    // the entry stays in the original image, which is mapped as well
+                    ImportEntry::ByNameUnresolved { hint_name_rva } => *hint_name_rva as u64,
+                    ImportEntry::ByName(import_by_name) => {
+                        let hint_name_rva = rva_at(strings_offset + strings.len());
+                        strings.extend_from_slice(&import_by_name.hint.unwrap_or(0).to_le_bytes());
+                        strings.extend_from_slice(import_by_name.name.as_bytes());
+                        strings.push(0);
+                        // the hint/name entries are 2-byte aligned
+                        if strings.len() % 2 != 0 {
+                            strings.push(0);
+                        }
+                        hint_name_rva as u64
+                    }
+                };
+                lookup_tables.extend_from_slice(&thunk.to_le_bytes()[..thunk_size]);
+            }
+            lookup_tables.extend_from_slice(&[0; 8][..thunk_size]);
+
+            let name_rva = rva_at(strings_offset + strings.len());
+            strings.extend_from_slice(descriptor.name().unwrap_or_default().as_bytes());
+            strings.push(0);
+
+            for field in [lookup_table_rva, 0, 0, name_rva, descriptor.first_thunk] {
+                descriptors.extend_from_slice(&field.to_le_bytes());
+            }
+        }
+        descriptors.resize(descriptors_size, 0);
+
+        let mut bytes = descriptors;
+        bytes.extend(lookup_tables);
+        bytes.extend(strings);
+        bytes
+    }
+}
+
 impl fmt::Display for ImportDirectoryTable {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "  ImportDirectoryTable:")?;
+        if f.alternate() {
+            // a line per DLL with the number of imported functions
+            for d in &self.0 {
+                writeln!(
+                    f,
+                    "    {} ({})",
+                    d.name.as_deref().unwrap_or("-"),
+                    d.function_count()
+                )?;
+            }
+            return Ok(());
+        }
         for d in &self.0 {
             writeln!(f, "{}", d)?;
         }
@@ -70,17 +183,24 @@ pub struct ImportDescriptor {
     time_date_stamp: u32,
     forwarder_chain: u32,
     name_rva: u32,
-    name: String,
+    name: Option<String>, // None when the name RVA is zero or unmapped
     first_thunk: u32,
-    import_by_names: ImportByNames,
+    entries: ImportEntries,
+    // the addresses bound by the linker in the import address table, which are only separate from the names
+    // of the import lookup table for bound imports
+    bound_addresses: Option<Vec<u64>>,
 }
 
 impl ImportDescriptor {
+    pub const SIZE: usize = 20;
+
     /// pe_file is needed to retrieve the name from the offset
     fn parse<'a>(
         pe_file: parse::Input<'a>,
         i: parse::Input<'a>,
-        section: &Section,
+        sections: &Sections,
+        is_64bit: bool,
+        ctx: &ParseContext,
     ) -> parse::Result<'a, Self> {
         let (i, (original_first_thunk, time_date_stamp, forwarder_chain, name_rva, first_thunk)) =
             tuple((
@@ -91,117 +211,394 @@ impl ImportDescriptor {
                 context("FirstThunk", le_u32),
             ))(i)?;
 
-        let name = Self::get_dll_name(pe_file, name_rva, section).unwrap_or("".to_string());
+        let name = Self::get_dll_name(pe_file, name_rva, sections, ctx)?;
 
-        let import_by_names = ImportByNames::parse(pe_file, original_first_thunk, section);
+        // obfuscated binaries may zero OriginalFirstThunk, in which case FirstThunk still describes the imports
+        let lookup_table_rva = if original_first_thunk != 0 {
+            original_first_thunk
+        } else {
+            first_thunk
+        };
+        let entries = ImportEntries::parse(
+            pe_file,
+            name.as_deref().unwrap_or_default(),
+            lookup_table_rva,
+            sections,
+            is_64bit,
+            ctx,
+        )?;
+        // without the import lookup table, the import address table is the only source of the names
+        let is_bound = time_date_stamp != 0;
+        let bound_addresses = match sections.rva_to_offset(first_thunk) {
+            Some(offset) if is_bound && original_first_thunk != 0 => {
+                let thunks = Self::read_thunks(pe_file, offset as usize, is_64bit);
+                ctx.budget()
+                    .table("import address table")
+                    .reserve(pe_file, thunks.len())?;
+                Some(thunks)
+            }
+            _ => None,
+        };
         let descriptor = Self {
             original_first_thunk,
-            is_bound: time_date_stamp != 0,
+            is_bound,
             time_date_stamp,
             forwarder_chain,
             name_rva,
             name,
             first_thunk,
-            import_by_names,
+            entries,
+            bound_addresses,
         };
 
         Ok((i, descriptor))
     }
 
-    /// This function is used to convert a null-terminated C string to a Rust String.
-    /// It scans the input byte slice for the null terminator (0), then splits the byte slice at that position.
-    /// The first part (up to the null terminator) is interpreted as a UTF-8 string using `from_utf8_lossy`,
-    /// which replaces any invalid UTF-8 sequences with the Unicode replacement character.
-    /// The second part (after the null terminator) is returned along with the constructed string.
+    pub fn entries(&self) -> &ImportEntries {
+        &self.entries
+    }
+
+    /// The imported functions, by name or by ordinal
+    pub fn functions(&self) -> &[ImportEntry] {
+        &self.entries.0
+    }
+
+    /// The imported functions with the RVA of their slot in the import address table, where the loader writes
+    /// the address of the function, in the order of the import lookup table.
+    /// This names the indirect calls through the IAT, e.g. `call [0x401234]` once converted to an RVA.
+    pub fn iat_slots(&self, is_64bit: bool) -> Vec<(u32, ImportEntry)> {
+        let thunk_size = if is_64bit { 8 } else { 4 };
+        self.entries
+            .0
+            .iter()
+            .zip(self.entries.1.iter())
+            .filter_map(|(entry, index)| {
+                let offset = u32::try_from(*index).ok()?.checked_mul(thunk_size)?;
+                Some((self.first_thunk.checked_add(offset)?, entry.clone()))
+            })
+            .collect()
+    }
+
+    /// The number of imported functions, e.g. for summaries like `kernel32.dll (42)`
+    pub fn function_count(&self) -> usize {
+        self.entries.0.len()
+    }
+
+    /// Whether the import address table was bound to the addresses of the DLL by the linker
+    pub fn is_bound(&self) -> bool {
+        self.is_bound
+    }
+
+    /// The bound addresses of the import address table, in the order of the import lookup table.
+    /// None when the imports are not bound or the import lookup table is missing.
+    pub fn bound_addresses(&self) -> Option<&[u64]> {
+        self.bound_addresses.as_deref()
+    }
+
+    /// The name of the imported DLL, or None when the descriptor has no name
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The ordinals imported from the DLL outside the ordinal range of its exports,
+    /// which the loader fails to resolve, e.g. after the DLL was replaced by another version
+    pub fn out_of_range_ordinals(&self, exports: &ExportDirectoryTable) -> Vec<u16> {
+        self.entries
+            .iter()
+            .filter_map(|entry| match entry {
+                ImportEntry::ByOrdinal { ordinal, .. } => Some(*ordinal),
+                _ => None,
+            })
+            .filter(|ordinal| !exports.contains_ordinal(*ordinal))
+            .collect()
+    }
+
+    /// Walks the import lookup table and returns the raw thunk values with the ordinal flag bit intact.
+    /// The import address table (`first_thunk`) is walked instead when `original_first_thunk` is zero.
     ///
     /// Args:
-    /// * `input`: A byte slice that represents the input data from which to extract the C string.
+    /// * `pe_file`: The whole PE file.
+    /// * `sections`: The sections used to convert the RVA of the table to a file offset.
+    /// * `is_64bit`: Whether the thunks are 8 bytes (PE32+) or 4 bytes (PE32).
     ///
     /// Returns:
-    /// A nom `IResult` that contains the remainder of the input byte slice after the null terminator,
-    /// and the string that was constructed from the bytes up to the null terminator.
-    fn read_c_string(input: &[u8]) -> nom::IResult<&[u8], String> {
-        let pos = input.iter().position(|&c| c == 0).unwrap_or(input.len());
-        let (head, tail) = input.split_at(pos);
-        let string = String::from_utf8_lossy(head);
-        let (_, tail) = tail.split_at(1); // Skip the null terminator
-        Ok((tail, string.to_string()))
+    /// The thunk values up to the null terminator or the end of the file.
+    pub fn lookup_table_entries(
+        &self,
+        pe_file: parse::Input,
+        sections: &Sections,
+        is_64bit: bool,
+    ) -> Vec<u64> {
+        let rva = if self.original_first_thunk != 0 {
+            self.original_first_thunk
+        } else {
+            self.first_thunk
+        };
+        match sections.rva_to_offset(rva) {
+            Some(offset) => Self::read_thunks(pe_file, offset as usize, is_64bit),
+            None => vec![],
+        }
     }
 
+    /// Reads pointer-sized thunks up to the null terminator or the end of the file
+    pub(crate) fn read_thunks(
+        pe_file: parse::Input,
+        mut offset: usize,
+        is_64bit: bool,
+    ) -> Vec<u64> {
+        let mut entries = Vec::new();
+        loop {
+            let entry = if is_64bit {
+                utils::read_u64_at(pe_file, offset)
+            } else {
+                utils::read_u32_at(pe_file, offset).map(|entry| entry as u64)
+            };
+            match entry {
+                Some(entry) if entry != 0 => entries.push(entry),
+                _ => break,
+            }
+            offset += if is_64bit { 8 } else { 4 };
+        }
+        entries
+    }
 
     /// This function is used to get the name of a DLL from a byte slice, given the relative virtual address (RVA)
-    /// of the DLL's name and the section in which the DLL is defined.
-    /// It first converts the RVA to a file offset using the provided section,
+    /// of the DLL's name.
+    /// It first converts the RVA to a file offset using the section containing it,
+    /// which is not necessarily the section of the import directory,
     /// then reads a C string from that offset in the input byte slice.
     ///
     /// Args:
     /// * `input`: A byte slice that represents the input data from which to extract the DLL name.
     /// * `name_rva`: The relative virtual address at which the DLL's name is stored.
-    /// * `section`: The section of the PE file in which the DLL is defined.
+    /// * `sections`: The sections of the PE file.
+    /// * `ctx`: The tracker of the parse, whose string mode decodes the name.
     ///
     /// Returns:
-    /// The name of the DLL, or `None` if the DLL's name could not be read for any reason.
-    fn get_dll_name(input: &[u8], name_rva: u32, section: &Section) -> Option<String> {
-        section.rva_to_offset(name_rva).and_then(|offset| {
-            let name = Self::read_c_string(&input[offset as usize..]).ok();
-            name.map(|n| n.1)
-        })
+    /// The name of the DLL, or `None` if the name RVA is zero or the DLL's name could not be read for any reason.
+    /// Fails when the name is not valid UTF-8 in `StringMode::Strict`.
+    pub(crate) fn get_dll_name<'a>(
+        input: parse::Input<'a>,
+        name_rva: u32,
+        sections: &Sections,
+        ctx: &ParseContext,
+    ) -> Result<Option<String>, nom::Err<errors::PEError<parse::Input<'a>>>> {
+        if name_rva == 0 {
+            return Ok(None);
+        }
+        match sections
+            .rva_to_offset(name_rva)
+            .and_then(|offset| input.get(offset as usize..))
+        {
+            Some(slice) => ctx.read_name(input, slice, "DLL name").map(Some),
+            None => Ok(None),
+        }
     }
 }
 
+// the entries and their indices in the import lookup table, which differ when an entry can't be read
 #[derive(Debug)]
-pub struct ImportByNames(Vec<ImportByName>);
-
-impl ImportByNames {
-    pub fn parse(pe_file: parse::Input, original_first_thunk: u32, section: &Section) -> Self {
-        let ilt = Self::read_import_lookup_table(pe_file, original_first_thunk, section);
-        let mut import_by_names = vec![];
-        for entry in ilt {
-            if entry & 0x80000000 != 0 {
-                // original import case
-                // @todo figure out what I should do
-                ()
-            } else if let Some(import_by_name) = ImportByName::parse(pe_file, entry, section) {
-                import_by_names.push(import_by_name)
+pub struct ImportEntries(Vec<ImportEntry>, Vec<usize>);
+
+impl ImportEntries {
+    pub fn iter(&self) -> impl Iterator<Item = &ImportEntry> {
+        self.0.iter()
+    }
+
+    pub fn as_slice(&self) -> &[ImportEntry] {
+        &self.0
+    }
+
+    pub fn parse<'a>(
+        pe_file: parse::Input<'a>,
+        dll_name: &str,
+        lookup_table_rva: u32,
+        sections: &Sections,
+        is_64bit: bool,
+        ctx: &ParseContext,
+    ) -> Result<Self, nom::Err<errors::PEError<parse::Input<'a>>>> {
+        Self::parse_based(
+            pe_file,
+            dll_name,
+            lookup_table_rva,
+            sections,
+            is_64bit,
+            0,
+            ctx,
+        )
+    }
+
+    /// Same as `parse` for a lookup table whose hint/name entries are virtual addresses from `image_base`
+    /// rather than RVAs, as in the delay imports of linkers older than VC++ 7
+    pub(crate) fn parse_based<'a>(
+        pe_file: parse::Input<'a>,
+        dll_name: &str,
+        lookup_table_rva: u32,
+        sections: &Sections,
+        is_64bit: bool,
+        image_base: u64,
+        ctx: &ParseContext,
+    ) -> Result<Self, nom::Err<errors::PEError<parse::Input<'a>>>> {
+        let ilt =
+            Self::read_import_lookup_table(pe_file, lookup_table_rva, sections, is_64bit, ctx)?;
+        let mut entries = vec![];
+        let mut indices = vec![];
+        for (index, entry) in ilt.into_iter().enumerate() {
+            let hint_name_rva = (entry & 0x7fffffff).wrapping_sub(image_base) as u32;
+            if is_ordinal(entry, is_64bit) {
+                let ordinal = ordinal_of(entry);
+                entries.push(ImportEntry::ByOrdinal {
+                    ordinal,
+                    resolved_name: ordinals::lookup(dll_name, ordinal),
+                });
+                indices.push(index);
+            } else if !ctx.resolve_import_names() {
+                entries.push(ImportEntry::ByNameUnresolved { hint_name_rva });
+                indices.push(index);
+            } else if let Some(import_by_name) =
+                ImportByName::parse(pe_file, hint_name_rva, sections, ctx)?
+            {
+                entries.push(ImportEntry::ByName(import_by_name));
+                indices.push(index);
             }
         }
-        Self(import_by_names)
+        Ok(Self(entries, indices))
     }
 
-    fn read_import_lookup_table(pe_file: parse::Input, rva: u32, section: &Section) -> Vec<u32> {
-        let offset = match section.rva_to_offset(rva) {
+    fn read_import_lookup_table<'a>(
+        pe_file: parse::Input<'a>,
+        rva: u32,
+        sections: &Sections,
+        is_64bit: bool,
+        ctx: &ParseContext,
+    ) -> Result<Vec<u64>, nom::Err<errors::PEError<parse::Input<'a>>>> {
+        let offset = match sections.rva_to_offset(rva) {
             Some(offset) => offset as usize,
-            None => return vec![], // Return empty vector if the RVA couldn't be converted to an offset
+            None => return Ok(vec![]), // Return empty vector if the RVA couldn't be converted to an offset
         };
 
         // Read the ILT entries
         let mut entries = Vec::new();
         let mut current_offset = offset;
+        let mut entries_budget = ctx.budget().table("import lookup table");
+        // Stop reading when you reach a zero entry or the end of the file
         loop {
-            let entry = LittleEndian::read_u32(&pe_file[current_offset..]);
-            if entry == 0 {
-                break; // Stop reading when you reach a zero entry
+            let entry = if is_64bit {
+                utils::read_u64_at(pe_file, current_offset)
+            } else {
+                utils::read_u32_at(pe_file, current_offset).map(|entry| entry as u64)
+            };
+            match entry {
+                Some(entry) if entry != 0 => {
+                    entries_budget.reserve(pe_file, 1)?;
+                    entries.push(entry);
+                }
+                _ => break,
             }
-            entries.push(entry);
-            current_offset += 4; // Move to the next entry
+            current_offset += if is_64bit { 8 } else { 4 }; // Move to the next entry
         }
 
-        entries
+        Ok(entries)
     }
 }
-#[derive(Debug)]
+
+#[derive(Debug, Clone)]
+pub enum ImportEntry {
+    ByName(ImportByName),
+    // an import by name whose hint/name entry was not read, see `ParseOptions::resolve_import_names`
+    ByNameUnresolved {
+        hint_name_rva: u32,
+    },
+    ByOrdinal {
+        ordinal: u16,
+        // name from the built-in ordinal table of well-known DLLs
+        resolved_name: Option<&'static str>,
+    },
+}
+
+impl ImportEntry {
+    /// The name of the imported function.
+    /// Imports by ordinal are resolved by the built-in ordinal table of well-known DLLs (ws2_32 etc.)
+    /// and None is returned when the ordinal is unknown.
+    pub fn resolved_name(&self) -> Option<&str> {
+        match self {
+            Self::ByName(import_by_name) => Some(&import_by_name.name),
+            Self::ByNameUnresolved { .. } => None,
+            Self::ByOrdinal { resolved_name, .. } => *resolved_name,
+        }
+    }
+
+    /// The resolved name, `#<ordinal>` for imports by an unknown ordinal
+    /// or `@<rva>` of the hint/name entry for imports by name left unresolved
+    pub fn identifier(&self) -> String {
+        match self {
+            Self::ByName(import_by_name) => import_by_name.name.clone(),
+            Self::ByNameUnresolved { hint_name_rva } => format!("@{:#x}", hint_name_rva),
+            Self::ByOrdinal {
+                resolved_name: Some(name),
+                ..
+            } => name.to_string(),
+            Self::ByOrdinal { ordinal, .. } => format!("#{}", ordinal),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ImportByName {
-    hint: u16,
+    hint: Option<u16>, // None when the linker left the hint zero
     name: String,
 }
 
 impl ImportByName {
-    pub fn parse(pe_file: parse::Input, rva: u32, section: &Section) -> Option<ImportByName> {
-        section.rva_to_offset(rva).map(|offset| {
-            let hint = LittleEndian::read_u16(&pe_file[offset as usize..]);
-            let name = utils::read_null_terminated_string(&pe_file[(offset as usize + 2)..]);
-            Self { hint, name }
-        })
+    /// The name is resolved through all the sections since it often lives in another section than the thunks,
+    /// e.g. `.rdata` while the thunks are in `.idata`
+    /// Fails when the name is not valid UTF-8 in `StringMode::Strict`.
+    pub fn parse<'a>(
+        pe_file: parse::Input<'a>,
+        rva: u32,
+        sections: &Sections,
+        ctx: &ParseContext,
+    ) -> Result<Option<ImportByName>, nom::Err<errors::PEError<parse::Input<'a>>>> {
+        let offset = match sections.rva_to_offset(rva) {
+            Some(offset) => offset as usize,
+            None => return Ok(None),
+        };
+        // None when the hint is cut by the end of the file
+        let hint = match utils::read_u16_at(pe_file, offset) {
+            Some(hint) => hint,
+            None => return Ok(None),
+        };
+        let name = ctx.read_name(pe_file, &pe_file[offset + 2..], "imported function name")?;
+        Ok(Some(Self {
+            hint: Some(hint).filter(|hint| *hint != 0),
+            name,
+        }))
+    }
+
+    /// The index into the export name pointer table of the DLL where the loader looks for the name first
+    pub fn hint(&self) -> Option<u16> {
+        self.hint
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the export name pointer table of the imported DLL has this name at the hint index.
+    /// Returns None when there is no hint to validate.
+    pub fn is_valid_hint(
+        &self,
+        exports: &ExportDirectoryTable,
+        dll_file: parse::Input,
+        dll_sections: &Sections,
+    ) -> Option<bool> {
+        let hint = self.hint?;
+        Some(
+            exports
+                .name_at(dll_file, dll_sections, hint as u32)
+                .as_deref()
+                == Some(&self.name),
+        )
     }
 }
 
@@ -212,15 +609,22 @@ impl fmt::Display for ImportDescriptor {
                self.is_bound,
                self.time_date_stamp,
                self.forwarder_chain,
-               self.name,
+               self.name.as_deref().unwrap_or("-"),
                self.first_thunk)?;
-        write!(f, "{}", self.import_by_names)
+        if let Some(bound_addresses) = &self.bound_addresses {
+            let bound_addresses: Vec<String> = bound_addresses
+                .iter()
+                .map(|address| format!("{:#x}", address))
+                .collect();
+            writeln!(f, "      BoundAddresses: {}", bound_addresses.join(", "))?;
+        }
+        write!(f, "{}", self.entries)
     }
 }
 
-impl fmt::Display for ImportByNames {
+impl fmt::Display for ImportEntries {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        writeln!(f, "      ImportByNames:")?;
+        writeln!(f, "      ImportEntries:")?;
         for i in &self.0 {
             writeln!(f, "{}", i)?;
         }
@@ -228,8 +632,321 @@ impl fmt::Display for ImportByNames {
     }
 }
 
+impl fmt::Display for ImportEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ByName(import_by_name) => write!(f, "{}", import_by_name),
+            Self::ByNameUnresolved { hint_name_rva } => {
+                write!(f, "        hint/name: {:#x}", hint_name_rva)
+            }
+            Self::ByOrdinal {
+                ordinal,
+                resolved_name: Some(name),
+            } => write!(f, "        ordinal: {}, name: {}", ordinal, name),
+            Self::ByOrdinal { ordinal, .. } => write!(f, "        ordinal: {}", ordinal),
+        }
+    }
+}
+
 impl fmt::Display for ImportByName {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "        hint: {}, name: {}", self.hint, self.name)
+        match self.hint {
+            Some(hint) => write!(f, "        hint: {}, name: {}", hint, self.name),
+            None => write!(f, "        hint: -, name: {}", self.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        is_ordinal, ordinal_of, ImportByName, ImportDescriptor, ImportDirectoryTable,
+        ImportEntries, ImportEntry,
+    };
+    use crate::headers::nt::{DataDirectory, DirectoryEntry};
+    use crate::headers::sections::{Section, Sections};
+    use crate::options::{ParseContext, ParseOptions};
+
+    fn section(name: &str, vir_addr: u32, ptr_to_raw_data: u32) -> Section {
+        Section::for_test(name, vir_addr, 0x1000, ptr_to_raw_data, 0x1000, 0)
+    }
+
+    fn sections() -> Sections {
+        Sections::new(vec![section(".idata", 0x1000, 0)], 0)
+    }
+
+    #[test]
+    fn import_by_name_short_reads() {
+        let pe_file = b"\x05\x00Sleep\x00\x07";
+        let ctx = ParseContext::default();
+        let import_by_name = ImportByName::parse(pe_file, 0x1000, &sections(), &ctx)
+            .unwrap()
+            .unwrap();
+        assert_eq!(import_by_name.hint(), Some(5));
+        assert_eq!(import_by_name.name(), "Sleep");
+
+        // the hint starts at the last byte
+        assert!(ImportByName::parse(pe_file, 0x1008, &sections(), &ctx)
+            .unwrap()
+            .is_none());
+        // the hint is beyond the end of the file
+        assert!(ImportByName::parse(pe_file, 0x1010, &sections(), &ctx)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn import_lookup_table_without_terminator() {
+        let ctx = ParseContext::default();
+        // the ILT ends exactly at the end of the buffer
+        let pe_file = [0x00, 0x20, 0x00, 0x00, 0x10, 0x20, 0x00, 0x00];
+        let ilt =
+            ImportEntries::read_import_lookup_table(&pe_file, 0x1000, &sections(), false, &ctx)
+                .unwrap();
+        assert_eq!(ilt, vec![0x2000, 0x2010]);
+
+        // a truncated trailing entry is not read
+        let pe_file = [0x00, 0x20, 0x00, 0x00, 0x10, 0x20];
+        let ilt =
+            ImportEntries::read_import_lookup_table(&pe_file, 0x1000, &sections(), false, &ctx)
+                .unwrap();
+        assert_eq!(ilt, vec![0x2000]);
+
+        // the ILT starts beyond the end of the buffer
+        let ilt =
+            ImportEntries::read_import_lookup_table(&pe_file, 0x1100, &sections(), false, &ctx)
+                .unwrap();
+        assert!(ilt.is_empty());
+    }
+
+    #[test]
+    fn names_in_another_section() {
+        // the ILT is in .idata at 0x1000 and the hint/name entries are in .rdata at 0x2000
+        let sections = Sections::new(
+            vec![
+                section(".idata", 0x1000, 0),
+                section(".rdata", 0x2000, 0x10),
+            ],
+            0,
+        );
+        let mut pe_file = vec![0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        pe_file.resize(0x10, 0);
+        pe_file.extend_from_slice(b"\x07\x00Sleep\x00");
+        let entries = ImportEntries::parse(
+            &pe_file,
+            "kernel32.dll",
+            0x1000,
+            &sections,
+            false,
+            &ParseContext::default(),
+        )
+        .unwrap();
+        let entries: Vec<&ImportEntry> = entries.iter().collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].resolved_name(), Some("Sleep"));
+    }
+
+    #[test]
+    fn unresolved_import_names() {
+        let mut pe_file = vec![];
+        for entry in [0x1010u32, 0x80000010, 0] {
+            pe_file.extend_from_slice(&entry.to_le_bytes());
+        }
+        pe_file.resize(0x10, 0);
+        pe_file.extend_from_slice(b"\x07\x00Sleep\x00");
+        let options = ParseOptions {
+            resolve_import_names: false,
+            ..ParseOptions::default()
+        };
+        let entries = ImportEntries::parse(
+            &pe_file,
+            "kernel32.dll",
+            0x1000,
+            &sections(),
+            false,
+            &ParseContext::from_options(&options),
+        )
+        .unwrap();
+        let entries: Vec<String> = entries.iter().map(|entry| entry.identifier()).collect();
+        assert_eq!(entries, vec!["@0x1010", "#16"]);
+    }
+
+    #[test]
+    fn dll_name_in_another_section() {
+        // the descriptor is in .idata at 0x1000 and the DLL name is in .rdata at 0x2000
+        let sections = Sections::new(
+            vec![
+                section(".idata", 0x1000, 0),
+                section(".rdata", 0x2000, 0x40),
+            ],
+            0,
+        );
+        let mut pe_file = vec![];
+        for field in [0x1020u32, 0, 0, 0x2000, 0x1020] {
+            pe_file.extend_from_slice(&field.to_le_bytes());
+        }
+        pe_file.resize(0x40, 0);
+        pe_file.extend_from_slice(b"KERNEL32.dll\0");
+        let (_, descriptor) = ImportDescriptor::parse(
+            &pe_file,
+            &pe_file,
+            &sections,
+            false,
+            &ParseContext::default(),
+        )
+        .unwrap();
+        assert_eq!(descriptor.name(), Some("KERNEL32.dll"));
+    }
+
+    #[test]
+    fn ordinal_flags() {
+        assert!(is_ordinal(0x80000073, false));
+        assert!(!is_ordinal(0x2010, false));
+        // the 32-bit flag is a name RVA bit of a PE32+ thunk
+        assert!(!is_ordinal(0x80000073, true));
+        assert!(is_ordinal(0x8000000000000073, true));
+        assert_eq!(ordinal_of(0x8000000000000073), 0x73);
+        assert_eq!(ordinal_of(0x80000073), 0x73);
+    }
+
+    #[test]
+    fn pe32_plus_import_lookup_table() {
+        let ctx = ParseContext::default();
+        let mut pe_file = vec![];
+        for entry in [0x2000u64, 0x8000000000000073, 0] {
+            pe_file.extend_from_slice(&entry.to_le_bytes());
+        }
+        let ilt =
+            ImportEntries::read_import_lookup_table(&pe_file, 0x1000, &sections(), true, &ctx)
+                .unwrap();
+        assert_eq!(ilt, vec![0x2000, 0x8000000000000073]);
+    }
+
+    #[test]
+    fn rebuilt_import_directory() {
+        let descriptor = |name: &str, first_thunk, entries: Vec<ImportEntry>| ImportDescriptor {
+            original_first_thunk: 0,
+            is_bound: false,
+            time_date_stamp: 0,
+            forwarder_chain: 0,
+            name_rva: 0,
+            name: Some(name.to_string()),
+            first_thunk,
+            entries: ImportEntries(entries.clone(), (0..entries.len()).collect()),
+            bound_addresses: None,
+        };
+        let table = ImportDirectoryTable(vec![
+            descriptor(
+                "KERNEL32.dll",
+                0x3000,
+                vec![
+                    ImportEntry::ByName(ImportByName {
+                        hint: Some(5),
+                        name: "ExitProcess".to_string(),
+                    }),
+                    ImportEntry::ByName(ImportByName {
+                        hint: None,
+                        name: "Sleep".to_string(),
+                    }),
+                ],
+            ),
+            descriptor(
+                "CUSTOM.dll",
+                0x3010,
+                vec![ImportEntry::ByOrdinal {
+                    ordinal: 115,
+                    resolved_name: None,
+                }],
+            ),
+        ]);
+
+        for is_64bit in [false, true] {
+            let pe_file = table.to_bytes(0x1000, is_64bit);
+            let directory = DataDirectory {
+                entry: DirectoryEntry::Import,
+                virtual_address: 0x1000,
+                size: 0x3c,
+            };
+            let (_, rebuilt) = ImportDirectoryTable::parse(
+                &pe_file,
+                directory,
+                &sections(),
+                is_64bit,
+                &ParseContext::default(),
+            )
+            .unwrap();
+            let rebuilt: Vec<(Option<&str>, u32, Vec<String>)> = rebuilt
+                .descriptors()
+                .iter()
+                .map(|descriptor| {
+                    (
+                        descriptor.name(),
+                        descriptor.first_thunk,
+                        descriptor
+                            .functions()
+                            .iter()
+                            .map(|entry| entry.identifier())
+                            .collect(),
+                    )
+                })
+                .collect();
+            assert_eq!(
+                rebuilt,
+                vec![
+                    (
+                        Some("KERNEL32.dll"),
+                        0x3000,
+                        vec!["ExitProcess".to_string(), "Sleep".to_string()]
+                    ),
+                    (Some("CUSTOM.dll"), 0x3010, vec!["#115".to_string()]),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn iat_slots() {
+        let entry = |name: &str| {
+            ImportEntry::ByName(ImportByName {
+                hint: None,
+                name: name.to_string(),
+            })
+        };
+        // the second slot of the lookup table couldn't be read
+        let descriptor = ImportDescriptor {
+            original_first_thunk: 0x2000,
+            is_bound: false,
+            time_date_stamp: 0,
+            forwarder_chain: 0,
+            name_rva: 0,
+            name: Some("KERNEL32.dll".to_string()),
+            first_thunk: 0x3000,
+            entries: ImportEntries(vec![entry("Sleep"), entry("ExitProcess")], vec![0, 2]),
+            bound_addresses: None,
+        };
+        let slots: Vec<(u32, String)> = descriptor
+            .iat_slots(false)
+            .into_iter()
+            .map(|(rva, entry)| (rva, entry.identifier()))
+            .collect();
+        assert_eq!(
+            slots,
+            vec![
+                (0x3000, "Sleep".to_string()),
+                (0x3008, "ExitProcess".to_string())
+            ]
+        );
+        let slots: Vec<(u32, String)> = descriptor
+            .iat_slots(true)
+            .into_iter()
+            .map(|(rva, entry)| (rva, entry.identifier()))
+            .collect();
+        assert_eq!(
+            slots,
+            vec![
+                (0x3000, "Sleep".to_string()),
+                (0x3010, "ExitProcess".to_string())
+            ]
+        );
     }
 }
@@ -1,11 +1,17 @@
-use crate::headers::nt::DataDirectory;
+use crate::headers::nt::{DataDirectory, DirectoryEntry};
 use crate::headers::sections::Sections;
-use crate::imports::import_directory_table::ImportDirectoryTable;
+use crate::headers::PEHeader;
+use crate::imports::import_directory_table::{ImportDirectoryTable, ImportEntry};
+use crate::options::ParseContext;
 use crate::parse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 use std::fmt::Formatter;
 
+pub mod bound_import_table;
+pub mod delay_import_table;
 pub mod import_directory_table;
+pub mod ordinals;
 
 #[derive(Debug)]
 pub struct Imports {
@@ -13,21 +19,236 @@ pub struct Imports {
 }
 
 impl Imports {
-    pub fn parse(
-        input: parse::Input,
+    pub fn parse<'a>(
+        input: parse::Input<'a>,
         import_directory: DataDirectory,
-        sections: Sections,
-    ) -> parse::Result<Self> {
-        let (_, directory_table) = ImportDirectoryTable::parse(input, import_directory, sections)?;
+        sections: &Sections,
+        is_64bit: bool,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Self> {
+        let (_, directory_table) =
+            ImportDirectoryTable::parse(input, import_directory, sections, is_64bit, ctx)?;
 
         let imports = Self { directory_table };
 
         Ok((input, imports))
     }
+
+    /// Parses the imports with the import directory, the sections and the bitness of already parsed headers,
+    /// e.g. to parse the imports on demand after `PEHeader::parse`.
+    /// Returns None when the import directory is absent.
+    pub fn parse_with_header<'a>(
+        input: parse::Input<'a>,
+        header: &PEHeader,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Option<Self>> {
+        match header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(DirectoryEntry::Import)
+        {
+            Some(import_directory) => {
+                let (i, imports) = Self::parse(
+                    input,
+                    import_directory,
+                    &header.sections,
+                    header.nt_header.optional_header.is_64bit(),
+                    ctx,
+                )?;
+                Ok((i, Some(imports)))
+            }
+            None => Ok((input, None)),
+        }
+    }
+
+    /// The imported functions keyed by the lowercased DLL name, e.g. `kernel32.dll`.
+    /// The entries of the descriptors importing from the same DLL are merged in the order of the descriptors.
+    pub fn as_map(&self) -> HashMap<String, Vec<ImportEntry>> {
+        let mut map: HashMap<String, Vec<ImportEntry>> = HashMap::new();
+        for descriptor in self.directory_table.descriptors() {
+            let dll_name = descriptor.name().unwrap_or_default().to_ascii_lowercase();
+            map.entry(dll_name)
+                .or_default()
+                .extend(descriptor.functions().iter().cloned());
+        }
+        map
+    }
+
+    /// The lowercased names of the DLLs imported by more than one descriptor, in the order of the descriptors.
+    /// The loader accepts them, but linkers emit one descriptor per DLL, so duplicates hint at a tampered import table.
+    /// Their functions are kept apart by the descriptors and merged by `as_map`.
+    pub fn duplicate_dlls(&self) -> Vec<String> {
+        let mut seen = BTreeSet::new();
+        let mut duplicates = vec![];
+        for dll_name in self
+            .directory_table
+            .descriptors()
+            .iter()
+            .filter_map(|descriptor| descriptor.name())
+            .map(|dll_name| dll_name.to_ascii_lowercase())
+        {
+            if !seen.insert(dll_name.clone()) && !duplicates.contains(&dll_name) {
+                duplicates.push(dll_name);
+            }
+        }
+        duplicates
+    }
+
+    /// Serializes the imports into a fresh `.idata`-style import directory to be mapped at `rva`,
+    /// e.g. to rebuild the imports of an image dumped from memory after unpacking.
+    /// See `ImportDirectoryTable::to_bytes` for the layout.
+    pub fn to_idata_bytes(&self, rva: u32, is_64bit: bool) -> Vec<u8> {
+        self.directory_table.to_bytes(rva, is_64bit)
+    }
+
+    /// Compares the imports with the imports of another version of the image.
+    /// `self` is the old version and `other` is the new version.
+    /// Functions are compared by name, so that an import by a well-known ordinal matches the import
+    /// of the same function by name. Imports by name parsed without `ParseOptions::resolve_import_names`
+    /// are left out, since only the RVA of their hint/name entry is known and it changes between versions.
+    pub fn diff(&self, other: &Imports) -> ImportDiff {
+        ImportDiff::between(&self.functions_by_dll(), &other.functions_by_dll())
+    }
+
+    /// The imported functions grouped by the lowercased DLL name,
+    /// merging the descriptors importing from the same DLL
+    fn functions_by_dll(&self) -> BTreeMap<String, BTreeSet<String>> {
+        let mut functions: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for descriptor in self.directory_table.descriptors() {
+            let dll_name = descriptor.name().unwrap_or_default().to_ascii_lowercase();
+            functions
+                .entry(dll_name)
+                .or_default()
+                .extend(descriptor.entries().iter().filter_map(Self::diff_name));
+        }
+        functions
+    }
+
+    /// The name a function is compared by in `diff`, see `ImportEntry::identifier`
+    fn diff_name(entry: &ImportEntry) -> Option<String> {
+        match entry {
+            ImportEntry::ByNameUnresolved { .. } => None,
+            entry => Some(entry.identifier()),
+        }
+    }
+}
+
+/// The difference between the imports of two images.
+/// Functions are named by their resolved name or `#<ordinal>`, and DLL names are lowercased.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportDiff {
+    pub added_dlls: Vec<String>,
+    pub removed_dlls: Vec<String>,
+    // functions per DLL, including all functions of added and removed DLLs
+    pub added_functions: BTreeMap<String, Vec<String>>,
+    pub removed_functions: BTreeMap<String, Vec<String>>,
 }
+
+impl ImportDiff {
+    fn between(
+        old: &BTreeMap<String, BTreeSet<String>>,
+        new: &BTreeMap<String, BTreeSet<String>>,
+    ) -> Self {
+        let empty = BTreeSet::new();
+        let mut diff = Self::default();
+        for (dll_name, new_functions) in new {
+            let old_functions = old.get(dll_name).unwrap_or(&empty);
+            if !old.contains_key(dll_name) {
+                diff.added_dlls.push(dll_name.clone());
+            }
+            let added: Vec<String> = new_functions.difference(old_functions).cloned().collect();
+            if !added.is_empty() {
+                diff.added_functions.insert(dll_name.clone(), added);
+            }
+        }
+        for (dll_name, old_functions) in old {
+            let new_functions = new.get(dll_name).unwrap_or(&empty);
+            if !new.contains_key(dll_name) {
+                diff.removed_dlls.push(dll_name.clone());
+            }
+            let removed: Vec<String> = old_functions.difference(new_functions).cloned().collect();
+            if !removed.is_empty() {
+                diff.removed_functions.insert(dll_name.clone(), removed);
+            }
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_dlls.is_empty()
+            && self.removed_dlls.is_empty()
+            && self.added_functions.is_empty()
+            && self.removed_functions.is_empty()
+    }
+}
+
 impl fmt::Display for Imports {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         writeln!(f, "Imports")?;
+        if f.alternate() {
+            return writeln!(f, "{:#}", self.directory_table);
+        }
         writeln!(f, "{}", self.directory_table)
     }
 }
+
+impl fmt::Display for ImportDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "ImportDiff")?;
+        for dll_name in &self.added_dlls {
+            writeln!(f, "  + {}", dll_name)?;
+        }
+        for dll_name in &self.removed_dlls {
+            writeln!(f, "  - {}", dll_name)?;
+        }
+        for (dll_name, functions) in &self.added_functions {
+            for function in functions {
+                writeln!(f, "  + {}!{}", dll_name, function)?;
+            }
+        }
+        for (dll_name, functions) in &self.removed_functions {
+            for function in functions {
+                writeln!(f, "  - {}!{}", dll_name, function)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImportDiff;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn imports(dlls: &[(&str, &[&str])]) -> BTreeMap<String, BTreeSet<String>> {
+        dlls.iter()
+            .map(|(dll_name, functions)| {
+                (
+                    dll_name.to_string(),
+                    functions.iter().map(|f| f.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diff_imports() {
+        let old = imports(&[
+            ("kernel32.dll", &["CreateFileW", "ReadFile"]),
+            ("user32.dll", &["MessageBoxW"]),
+        ]);
+        let new = imports(&[
+            ("kernel32.dll", &["CreateFileW", "WriteFile"]),
+            ("ws2_32.dll", &["connect", "#115"]),
+        ]);
+        let diff = ImportDiff::between(&old, &new);
+
+        assert_eq!(diff.added_dlls, vec!["ws2_32.dll"]);
+        assert_eq!(diff.removed_dlls, vec!["user32.dll"]);
+        assert_eq!(diff.added_functions["kernel32.dll"], vec!["WriteFile"]);
+        assert_eq!(diff.added_functions["ws2_32.dll"], vec!["#115", "connect"]);
+        assert_eq!(diff.removed_functions["kernel32.dll"], vec!["ReadFile"]);
+        assert_eq!(diff.removed_functions["user32.dll"], vec!["MessageBoxW"]);
+        assert!(ImportDiff::between(&old, &old).is_empty());
+    }
+}
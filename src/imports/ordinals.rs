@@ -0,0 +1,153 @@
+/// Looks up the name of a function imported by ordinal from a well-known DLL.
+///
+/// # Arguments
+///
+/// * `dll_name` - The name of the imported DLL, e.g. `WS2_32.dll`. The comparison is case-insensitive.
+/// * `ordinal` - The imported ordinal.
+///
+/// # Returns
+///
+/// The function name, or `None` when the DLL or the ordinal is unknown
+/// or the `ordinal-names` feature is disabled.
+pub fn lookup(dll_name: &str, ordinal: u16) -> Option<&'static str> {
+    let dll_name = dll_name.to_ascii_lowercase();
+    let module = dll_name.strip_suffix(".dll").unwrap_or(&dll_name);
+    table(module).and_then(|entries| {
+        entries
+            .iter()
+            .find(|(o, _)| *o == ordinal)
+            .map(|(_, name)| *name)
+    })
+}
+
+#[cfg(not(feature = "ordinal-names"))]
+fn table(_module: &str) -> Option<&'static [(u16, &'static str)]> {
+    None
+}
+
+#[cfg(feature = "ordinal-names")]
+fn table(module: &str) -> Option<&'static [(u16, &'static str)]> {
+    match module {
+        "ws2_32" | "wsock32" => Some(WS2_32),
+        "oleaut32" => Some(OLEAUT32),
+        _ => None,
+    }
+}
+
+/// Winsock ordinals shared by ws2_32.dll and wsock32.dll
+#[cfg(feature = "ordinal-names")]
+const WS2_32: &[(u16, &str)] = &[
+    (1, "accept"),
+    (2, "bind"),
+    (3, "closesocket"),
+    (4, "connect"),
+    (5, "getpeername"),
+    (6, "getsockname"),
+    (7, "getsockopt"),
+    (8, "htonl"),
+    (9, "htons"),
+    (10, "ioctlsocket"),
+    (11, "inet_addr"),
+    (12, "inet_ntoa"),
+    (13, "listen"),
+    (14, "ntohl"),
+    (15, "ntohs"),
+    (16, "recv"),
+    (17, "recvfrom"),
+    (18, "select"),
+    (19, "send"),
+    (20, "sendto"),
+    (21, "setsockopt"),
+    (22, "shutdown"),
+    (23, "socket"),
+    (51, "gethostbyaddr"),
+    (52, "gethostbyname"),
+    (53, "getprotobyname"),
+    (54, "getprotobynumber"),
+    (55, "getservbyname"),
+    (56, "getservbyport"),
+    (57, "gethostname"),
+    (101, "WSAAsyncSelect"),
+    (102, "WSAAsyncGetHostByAddr"),
+    (103, "WSAAsyncGetHostByName"),
+    (104, "WSAAsyncGetProtoByNumber"),
+    (105, "WSAAsyncGetProtoByName"),
+    (106, "WSAAsyncGetServByPort"),
+    (107, "WSAAsyncGetServByName"),
+    (108, "WSACancelAsyncRequest"),
+    (109, "WSASetBlockingHook"),
+    (110, "WSAUnhookBlockingHook"),
+    (111, "WSAGetLastError"),
+    (112, "WSASetLastError"),
+    (113, "WSACancelBlockingCall"),
+    (114, "WSAIsBlocking"),
+    (115, "WSAStartup"),
+    (116, "WSACleanup"),
+    (151, "__WSAFDIsSet"),
+    (500, "WEP"),
+];
+
+#[cfg(feature = "ordinal-names")]
+const OLEAUT32: &[(u16, &str)] = &[
+    (2, "SysAllocString"),
+    (3, "SysReAllocString"),
+    (4, "SysAllocStringLen"),
+    (5, "SysReAllocStringLen"),
+    (6, "SysFreeString"),
+    (7, "SysStringLen"),
+    (8, "VariantInit"),
+    (9, "VariantClear"),
+    (10, "VariantCopy"),
+    (11, "VariantCopyInd"),
+    (12, "VariantChangeType"),
+    (13, "VariantTimeToDosDateTime"),
+    (14, "DosDateTimeToVariantTime"),
+    (15, "SafeArrayCreate"),
+    (16, "SafeArrayDestroy"),
+    (17, "SafeArrayGetDim"),
+    (18, "SafeArrayGetElemsize"),
+    (19, "SafeArrayGetUBound"),
+    (20, "SafeArrayGetLBound"),
+    (21, "SafeArrayLock"),
+    (22, "SafeArrayUnlock"),
+    (23, "SafeArrayAccessData"),
+    (24, "SafeArrayUnaccessData"),
+    (25, "SafeArrayGetElement"),
+    (26, "SafeArrayPutElement"),
+    (27, "SafeArrayCopy"),
+    (28, "DispGetParam"),
+    (29, "DispGetIDsOfNames"),
+    (30, "DispInvoke"),
+    (31, "CreateDispTypeInfo"),
+    (32, "CreateStdDispatch"),
+    (33, "RegisterActiveObject"),
+    (34, "RevokeActiveObject"),
+    (35, "GetActiveObject"),
+    (36, "SafeArrayAllocDescriptor"),
+    (37, "SafeArrayAllocData"),
+    (38, "SafeArrayDestroyDescriptor"),
+    (39, "SafeArrayDestroyData"),
+    (40, "SafeArrayRedim"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::lookup;
+
+    #[cfg(feature = "ordinal-names")]
+    #[test]
+    fn well_known_ordinals() {
+        assert_eq!(lookup("WS2_32.dll", 23), Some("socket"));
+        assert_eq!(lookup("wsock32.DLL", 111), Some("WSAGetLastError"));
+        assert_eq!(lookup("OLEAUT32", 6), Some("SysFreeString"));
+        assert_eq!(lookup("ws2_32.dll", 1000), None);
+        assert_eq!(lookup("kernel32.dll", 1), None);
+    }
+
+    #[cfg(not(feature = "ordinal-names"))]
+    #[test]
+    fn without_ordinal_names() {
+        assert_eq!(lookup("WS2_32.dll", 23), None);
+        assert_eq!(lookup("oleaut32.dll", 6), None);
+    }
+}
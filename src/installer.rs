@@ -0,0 +1,89 @@
+use crate::overlay::OverlayKind;
+use derive_more::Display;
+
+/// The family of the installer an image is the setup program of, see `PE::installer_kind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum InstallerKind {
+    Nsis,
+    InnoSetup,
+    InstallShield,
+    Wix, // a WiX bundle or an MSI package wrapped in an executable
+    Wise,
+}
+
+impl InstallerKind {
+    /// Classifies the installer from well-known signals:
+    /// * NSIS stubs have a `.ndata` section and append their data starting with "NullsoftInst"
+    /// * Inno Setup stubs append "Inno Setup Setup Data" and name themselves "JR.Inno.Setup" in the manifest
+    /// * InstallShield setups name themselves in the manifest or append a stream starting with "InstallShield"
+    /// * WiX Burn bundles have a `.wixburn` section, other wrappers append an MSI package
+    /// * Wise stubs have a `.WISE` section
+    ///
+    /// The overlay and the manifest are only searched for markers, so a packer could fake any of them.
+    pub fn classify(
+        section_names: &[&str],
+        overlay_kind: Option<OverlayKind>,
+        overlay: &[u8],
+        manifest: Option<&str>,
+    ) -> Option<Self> {
+        let has_section = |name: &str| section_names.contains(&name);
+        let manifest_contains = |marker: &str| manifest.is_some_and(|text| text.contains(marker));
+        if overlay_kind == Some(OverlayKind::Nsis)
+            || has_section(".ndata")
+            || manifest_contains("Nullsoft.NSIS")
+        {
+            Some(Self::Nsis)
+        } else if overlay_kind == Some(OverlayKind::InnoSetup) || manifest_contains("JR.Inno.Setup")
+        {
+            Some(Self::InnoSetup)
+        } else if overlay.starts_with(b"InstallShield") || manifest_contains("InstallShield") {
+            Some(Self::InstallShield)
+        } else if has_section(".wixburn") || overlay_kind == Some(OverlayKind::Msi) {
+            Some(Self::Wix)
+        } else if has_section(".WISE") {
+            Some(Self::Wise)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InstallerKind;
+    use crate::overlay::OverlayKind;
+
+    #[test]
+    fn classify() {
+        let sections = [".text", ".rdata", ".data"];
+        assert_eq!(
+            InstallerKind::classify(&[".text", ".ndata"], None, b"", None),
+            Some(InstallerKind::Nsis)
+        );
+        assert_eq!(
+            InstallerKind::classify(
+                &sections,
+                None,
+                b"",
+                Some(r#"<assemblyIdentity name="JR.Inno.Setup"/>"#)
+            ),
+            Some(InstallerKind::InnoSetup)
+        );
+        assert_eq!(
+            InstallerKind::classify(&sections, None, b"InstallShield\0", None),
+            Some(InstallerKind::InstallShield)
+        );
+        assert_eq!(
+            InstallerKind::classify(&sections, Some(OverlayKind::Msi), b"", None),
+            Some(InstallerKind::Wix)
+        );
+        assert_eq!(
+            InstallerKind::classify(&[".text", ".WISE"], None, b"", None),
+            Some(InstallerKind::Wise)
+        );
+        assert_eq!(
+            InstallerKind::classify(&sections, Some(OverlayKind::Zip), b"PK", None),
+            None
+        );
+    }
+}
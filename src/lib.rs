@@ -1,53 +1,195 @@
-mod errors;
-mod exports;
-mod headers;
-mod imports;
+pub mod annotations;
+pub mod anomalies;
+pub mod build_origin;
+pub mod debug;
+#[cfg(feature = "dotnet")]
+pub mod dotnet;
+pub mod errors;
+pub mod exports;
+pub mod headers;
+pub mod import_library;
+pub mod imports;
+pub mod installer;
+pub mod memory_map;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod options;
+pub mod overlay;
 mod parse;
+pub mod reader;
+pub mod relocations;
+pub mod resources;
+pub mod scanner;
+pub mod summary;
+pub mod timestamps;
+pub mod tls;
 mod utils;
+pub mod visitor;
+pub mod writer;
 
-use crate::exports::export_directory_table::ExportDirectoryTable;
-use crate::headers::nt::DirectoryEntry;
+use crate::annotations::FieldSpan;
+use crate::anomalies::Anomaly;
+use crate::build_origin::BuildOrigin;
+use crate::debug::debug_directory::{
+    DebugDirectory, IMAGE_DLLCHARACTERISTICS_EX_CET_COMPAT,
+    IMAGE_DLLCHARACTERISTICS_EX_CET_COMPAT_STRICT_MODE,
+};
+#[cfg(feature = "dotnet")]
+use crate::dotnet::assembly::AssemblyInfo;
+#[cfg(feature = "dotnet")]
+use crate::dotnet::ClrHeader;
+use crate::errors::Truncation;
+use crate::exports::export_directory_table::{
+    ExportDirectoryTable, ExportedFunction, TimestampConsistency,
+};
+use crate::headers::dos::DosHeader;
+use crate::headers::nt::{
+    Bitness, ChecksumStatus, DirectoryEntry, DirectoryPresence, DllCharacteristic,
+    FileCharacteristic, Machine, MemoryConfig, RvaReference, Subsystem,
+};
+use crate::headers::sections::{Section, Sections};
 use crate::headers::PEHeader;
+use crate::imports::bound_import_table::BoundImportTable;
+use crate::imports::delay_import_table::DelayImportTable;
+use crate::imports::import_directory_table::ImportDescriptor;
 use crate::imports::Imports;
+use crate::installer::InstallerKind;
+use crate::memory_map::MappedRegion;
+#[cfg(feature = "metrics")]
+use crate::metrics::ParseMetrics;
+use crate::options::{ParseContext, ParseOptions};
+use crate::overlay::OverlayKind;
+use crate::relocations::base_relocation_table::BaseRelocationTable;
+use crate::resources::resource_directory_table::{ResourceDirectoryTable, ResourceType};
+use crate::summary::Summary;
+use crate::timestamps::Timestamps;
+use crate::tls::tls_directory::TlsDirectory;
+use crate::visitor::{NoopVisitor, Visitor};
+use byteorder::{ByteOrder, LittleEndian};
+use chrono::DateTime;
 use std::fmt;
+use std::ops::Range;
+#[cfg(feature = "metrics")]
+use std::time::Instant;
 
 #[derive(Debug)]
 pub struct PE<'a> {
     pub file: &'a [u8],
     pub header: PEHeader<'a>,
     pub imports: Option<Imports>,
+    pub delay_imports: Option<DelayImportTable>,
     pub export: Option<ExportDirectoryTable>,
+    pub resources: Option<ResourceDirectoryTable>,
+    pub debug: Option<DebugDirectory>,
+    pub relocations: Option<BaseRelocationTable>,
+    pub tls: Option<TlsDirectory>,
+    #[cfg(feature = "dotnet")]
+    pub clr: Option<ClrHeader>,
+    #[cfg(feature = "metrics")]
+    pub metrics: ParseMetrics,
+    // the byte ranges of the fields of the headers, empty unless `ParseOptions::annotations` is set
+    pub annotations: Vec<FieldSpan>,
+}
+
+/// The result of parsing a data directory on its own
+pub type DirectoryResult<'a, T> = Result<T, nom::Err<errors::PEError<parse::Input<'a>>>>;
+
+/// The headers of a PE file with the result of parsing each data directory,
+/// returned by `PE::parse_headers_then_directories`
+#[derive(Debug)]
+pub struct PartialPE<'a> {
+    pub file: &'a [u8],
+    pub header: PEHeader<'a>,
+    pub imports: DirectoryResult<'a, Option<Imports>>,
+    pub delay_imports: DirectoryResult<'a, Option<DelayImportTable>>,
+    pub export: DirectoryResult<'a, Option<ExportDirectoryTable>>,
+    pub resources: DirectoryResult<'a, Option<ResourceDirectoryTable>>,
+    pub debug: DirectoryResult<'a, Option<DebugDirectory>>,
+    pub relocations: DirectoryResult<'a, Option<BaseRelocationTable>>,
+    pub tls: DirectoryResult<'a, Option<TlsDirectory>>,
+    #[cfg(feature = "dotnet")]
+    pub clr: DirectoryResult<'a, Option<ClrHeader>>,
+    pub annotations: Vec<FieldSpan>,
+}
+
+/// Evaluates the expression, recording the time it took in the given field of `ParseMetrics`
+/// when the `metrics` feature is enabled
+macro_rules! timed {
+    ($metrics:ident.$field:ident, $e:expr) => {{
+        #[cfg(feature = "metrics")]
+        let started = Instant::now();
+        let value = $e;
+        #[cfg(feature = "metrics")]
+        {
+            $metrics.$field = started.elapsed();
+        }
+        value
+    }};
 }
 
 impl<'a> PE<'a> {
-    pub fn parse(input: parse::Input<'a>) -> parse::Result<Self> {
-        let (i, header) = PEHeader::parse(input)?;
+    pub fn parse(input: parse::Input<'a>) -> parse::Result<'a, Self> {
+        Self::parse_with_options(input, &ParseOptions::default())
+    }
 
-        let import_directory_opt = header
-            .nt_header
-            .optional_header
-            .find_directory_by_entry(DirectoryEntry::Import);
-        let (i, imports) = match import_directory_opt {
-            Some(import_directory) => {
-                // @todo wants to avoid clone
-                let (i, imports) =
-                    Imports::parse(input, import_directory, header.sections.clone())?;
-                (i, Some(imports))
-            }
-            None => (i, None),
-        };
+    pub fn parse_with_options(
+        input: parse::Input<'a>,
+        options: &ParseOptions,
+    ) -> parse::Result<'a, Self> {
+        Self::parse_with_visitor(input, options, &mut NoopVisitor)
+    }
 
-        let export_directory_opt = header
-            .nt_header
-            .optional_header
-            .find_directory_by_entry(DirectoryEntry::Export);
-        let (i, export) = match export_directory_opt {
-            Some(export_directory) => {
-                // @todo wants to avoid clone
-                ExportDirectoryTable::parse(input, export_directory, header.sections.clone())?
-            }
-            None => (i, None),
-        };
+    /// Parses like `parse_with_options`, calling the hooks of the visitor from the parsers
+    /// as each section, import descriptor and exported function is produced.
+    /// A trait object is taken so that the parser isn't compiled again for every visitor type.
+    pub fn parse_with_visitor(
+        input: parse::Input<'a>,
+        options: &ParseOptions,
+        visitor: &mut dyn Visitor,
+    ) -> parse::Result<'a, Self> {
+        if input.len() < DosHeader::SIZE {
+            return Err(errors::PEError::from_string(
+                input,
+                format!(
+                    "file too small: {} bytes is less than the DOS header size of {} bytes",
+                    input.len(),
+                    DosHeader::SIZE
+                ),
+            ));
+        }
+
+        let ctx = ParseContext::from_options(options).with_visitor(visitor);
+        ctx.set_file(input);
+        #[cfg(feature = "metrics")]
+        let mut metrics = ParseMetrics::default();
+
+        let (i, header) = timed!(metrics.headers, PEHeader::parse(input, &ctx)?);
+
+        let imports = timed!(metrics.imports, Self::parse_imports(input, &header, &ctx)?);
+
+        let delay_imports = timed!(
+            metrics.delay_imports,
+            Self::parse_delay_imports(input, &header, &ctx)?
+        );
+
+        let export = timed!(metrics.exports, Self::parse_export(input, &header, &ctx)?);
+
+        let resources = timed!(
+            metrics.resources,
+            Self::parse_resources(input, &header, &ctx)?
+        );
+
+        let debug = timed!(metrics.debug, Self::parse_debug(input, &header, &ctx)?);
+
+        let relocations = timed!(
+            metrics.relocations,
+            Self::parse_relocations(input, &header, &ctx)?
+        );
+
+        let tls = timed!(metrics.tls, Self::parse_tls(input, &header, &ctx)?);
+
+        #[cfg(feature = "dotnet")]
+        let clr = timed!(metrics.clr, Self::parse_clr(input, &header)?);
 
         Ok((
             i,
@@ -55,22 +197,1376 @@ impl<'a> PE<'a> {
                 file: input,
                 header,
                 imports,
+                delay_imports,
                 export,
+                resources,
+                debug,
+                relocations,
+                tls,
+                #[cfg(feature = "dotnet")]
+                clr,
+                #[cfg(feature = "metrics")]
+                metrics,
+                annotations: ctx.take_annotations(),
             },
         ))
     }
+
+    /// Parses the headers, which are required, and then each data directory independently.
+    /// Unlike `parse`, an error in a directory is kept in its own field
+    /// instead of failing the whole parse, so that the headers and the other directories are still available.
+    pub fn parse_headers_then_directories(
+        input: parse::Input<'a>,
+        options: &ParseOptions,
+    ) -> DirectoryResult<'a, PartialPE<'a>> {
+        let ctx = ParseContext::from_options(options);
+        ctx.set_file(input);
+        let (_, header) = PEHeader::parse(input, &ctx)?;
+        Ok(PartialPE {
+            file: input,
+            imports: Self::parse_imports(input, &header, &ctx),
+            delay_imports: Self::parse_delay_imports(input, &header, &ctx),
+            export: Self::parse_export(input, &header, &ctx),
+            resources: Self::parse_resources(input, &header, &ctx),
+            debug: Self::parse_debug(input, &header, &ctx),
+            relocations: Self::parse_relocations(input, &header, &ctx),
+            tls: Self::parse_tls(input, &header, &ctx),
+            #[cfg(feature = "dotnet")]
+            clr: Self::parse_clr(input, &header),
+            header,
+            annotations: ctx.take_annotations(),
+        })
+    }
+
+    fn parse_imports(
+        input: parse::Input<'a>,
+        header: &PEHeader<'a>,
+        ctx: &ParseContext,
+    ) -> DirectoryResult<'a, Option<Imports>> {
+        let (_, imports) = Imports::parse_with_header(input, header, ctx)?;
+        Ok(imports)
+    }
+
+    fn parse_delay_imports(
+        input: parse::Input<'a>,
+        header: &PEHeader<'a>,
+        ctx: &ParseContext,
+    ) -> DirectoryResult<'a, Option<DelayImportTable>> {
+        match header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(DirectoryEntry::DelayImport)
+        {
+            Some(delay_import_directory) if delay_import_directory.is_present() => {
+                let (_, delay_imports) = DelayImportTable::parse(
+                    input,
+                    delay_import_directory,
+                    &header.sections,
+                    header.nt_header.optional_header.is_64bit(),
+                    header.nt_header.optional_header.image_base(),
+                    ctx,
+                )?;
+                Ok(delay_imports)
+            }
+            _ => {
+                let (_, delay_imports) = DelayImportTable::parse_didat(
+                    input,
+                    &header.sections,
+                    header.nt_header.optional_header.is_64bit(),
+                    header.nt_header.optional_header.image_base(),
+                    ctx,
+                )?;
+                Ok(delay_imports)
+            }
+        }
+    }
+
+    fn parse_export(
+        input: parse::Input<'a>,
+        header: &PEHeader<'a>,
+        ctx: &ParseContext,
+    ) -> DirectoryResult<'a, Option<ExportDirectoryTable>> {
+        match header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(DirectoryEntry::Export)
+        {
+            Some(export_directory) => {
+                let (_, export) =
+                    ExportDirectoryTable::parse(input, export_directory, &header.sections, ctx)?;
+                Ok(export)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn parse_resources(
+        input: parse::Input<'a>,
+        header: &PEHeader<'a>,
+        ctx: &ParseContext,
+    ) -> DirectoryResult<'a, Option<ResourceDirectoryTable>> {
+        match header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(DirectoryEntry::Resource)
+        {
+            Some(resource_directory) if resource_directory.is_present() => {
+                let (_, resources) = ResourceDirectoryTable::parse(
+                    input,
+                    resource_directory,
+                    &header.sections,
+                    ctx,
+                )?;
+                Ok(resources)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn parse_debug(
+        input: parse::Input<'a>,
+        header: &PEHeader<'a>,
+        ctx: &ParseContext,
+    ) -> DirectoryResult<'a, Option<DebugDirectory>> {
+        match header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(DirectoryEntry::Debug)
+        {
+            Some(debug_directory) if debug_directory.is_present() => {
+                let (_, debug) =
+                    DebugDirectory::parse(input, debug_directory, &header.sections, ctx)?;
+                Ok(debug)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn parse_relocations(
+        input: parse::Input<'a>,
+        header: &PEHeader<'a>,
+        ctx: &ParseContext,
+    ) -> DirectoryResult<'a, Option<BaseRelocationTable>> {
+        match header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(DirectoryEntry::BaseRelocation)
+        {
+            Some(base_relocation_directory) if base_relocation_directory.is_present() => {
+                let (_, relocations) = BaseRelocationTable::parse(
+                    input,
+                    base_relocation_directory,
+                    &header.sections,
+                    ctx,
+                )?;
+                Ok(relocations)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn parse_tls(
+        input: parse::Input<'a>,
+        header: &PEHeader<'a>,
+        ctx: &ParseContext,
+    ) -> DirectoryResult<'a, Option<TlsDirectory>> {
+        let optional_header = &header.nt_header.optional_header;
+        match optional_header.find_directory_by_entry(DirectoryEntry::Tls) {
+            Some(tls_directory) if tls_directory.is_present() => {
+                let (_, tls) = TlsDirectory::parse(
+                    input,
+                    tls_directory,
+                    &header.sections,
+                    optional_header.image_base(),
+                    optional_header.is_64bit(),
+                    ctx,
+                )?;
+                Ok(tls)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    #[cfg(feature = "dotnet")]
+    fn parse_clr(
+        input: parse::Input<'a>,
+        header: &PEHeader<'a>,
+    ) -> DirectoryResult<'a, Option<ClrHeader>> {
+        match header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(DirectoryEntry::ClrRuntime)
+        {
+            Some(clr_directory) => {
+                let (_, clr) = ClrHeader::parse(input, clr_directory, &header.sections)?;
+                Ok(clr)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The properties commonly looked at when triaging files, see `scanner::Scanner` for scanning many files
+    pub fn summary(&self) -> Summary {
+        let file_header = &self.header.nt_header.file_header;
+        let descriptors = self
+            .imports
+            .as_ref()
+            .map(|imports| imports.directory_table.descriptors())
+            .unwrap_or_default();
+        #[cfg(feature = "dotnet")]
+        let is_dotnet = self.clr.is_some();
+        #[cfg(not(feature = "dotnet"))]
+        let is_dotnet = false;
+        Summary {
+            machine: file_header.machine,
+            is_64bit: self.is_64bit(),
+            is_dll: file_header.has_characteristic(FileCharacteristic::Dll),
+            datetime: file_header.datetime,
+            num_of_sections: self.header.sections.len(),
+            image_base: self.image_base(),
+            address_of_entry_point: self
+                .header
+                .nt_header
+                .optional_header
+                .address_of_entry_point(),
+            num_of_imported_dlls: descriptors.len(),
+            num_of_imported_functions: descriptors
+                .iter()
+                .map(|descriptor| descriptor.function_count())
+                .sum(),
+            num_of_exported_functions: self
+                .export
+                .as_ref()
+                .map(|export| export.functions.len())
+                .unwrap_or(0),
+            has_resources: self.resources.is_some(),
+            has_tls: self.has_tls(),
+            is_dotnet,
+            pdb_symbol_path: self.pdb_symbol_path(),
+        }
+    }
+
+    /// The size of the file implied by the headers, i.e. the end of the last raw data of the sections
+    /// or the end of the headers when there is no section data
+    pub fn declared_size(&self) -> u32 {
+        self.header.declared_size()
+    }
+
+    /// Checks that the file holds everything its headers declare before parsing the directories,
+    /// so that a partial download fails with its expected length rather than deep in a directory parser:
+    /// the headers and the section table, the raw data of the sections, and the file range of each
+    /// present data directory. The certificate table isn't mapped, its address is a file offset.
+    /// Headers which don't parse are reported with the length they span as far as it can be read,
+    /// since a partial download is what usually cuts them.
+    pub fn check_completeness(input: parse::Input<'a>) -> Result<(), Truncation> {
+        let truncation = |expected_len: u64| Truncation {
+            expected_len: usize::try_from(expected_len).unwrap_or(usize::MAX),
+            actual_len: input.len(),
+        };
+        let ctx = ParseContext::default();
+        let header = match PEHeader::parse(input, &ctx) {
+            Ok((_, header)) => header,
+            Err(_) => return Err(truncation(Self::headers_len(input))),
+        };
+        let sections = &header.sections;
+        let directory_ends = header
+            .nt_header
+            .optional_header
+            .data_directories()
+            .iter()
+            .filter(|directory| directory.is_present())
+            .filter_map(|directory| {
+                let start = directory.virtual_address as u64;
+                let end = start + directory.size as u64;
+                if directory.entry == DirectoryEntry::Certificate {
+                    return Some(end);
+                }
+                // the headers are mapped as they are in the file, and only the raw data of a section is,
+                // the rest of the section being zero-filled
+                let size_of_headers = header.nt_header.optional_header.size_of_headers() as u64;
+                match sections.find_by_address(directory.virtual_address) {
+                    Some(section) => {
+                        let offset = section.rva_to_offset(directory.virtual_address)? as u64;
+                        let raw_end =
+                            section.ptr_to_raw_data as u64 + section.size_of_raw_data as u64;
+                        Some((offset + directory.size as u64).min(raw_end))
+                    }
+                    None if start < size_of_headers => Some(end.min(size_of_headers)),
+                    None => None,
+                }
+            });
+        let expected_len = directory_ends
+            .chain(Some(header.declared_size() as u64))
+            .chain(Some(Self::headers_len(input)))
+            .max()
+            .unwrap_or_default();
+        if (input.len() as u64) < expected_len {
+            return Err(truncation(expected_len));
+        }
+        Ok(())
+    }
+
+    /// The length of the headers up to the end of the section table, read from the fields locating them:
+    /// `lfanew`, the number of sections and the size of the optional header.
+    /// Only the fields which are in the file are taken into account.
+    fn headers_len(input: parse::Input) -> u64 {
+        // the signature and the file header
+        const NT_HEADER_START: u64 = 24;
+        let lfanew = match utils::read_u32_at(input, 0x3c) {
+            Some(lfanew) => lfanew as u64,
+            None => return DosHeader::SIZE as u64,
+        };
+        let field = |offset: u64| utils::read_u16_at(input, usize::try_from(offset).ok()?);
+        match (field(lfanew + 6), field(lfanew + 20)) {
+            (Some(num_of_sections), Some(size_of_optional_header)) => {
+                lfanew
+                    + NT_HEADER_START
+                    + size_of_optional_header as u64
+                    + num_of_sections as u64 * Section::SIZE as u64
+            }
+            _ => lfanew + NT_HEADER_START,
+        }
+    }
+
+    /// Whether the file is shorter than `declared_size`, in which case some reads of section data fail
+    pub fn is_truncated(&self) -> bool {
+        self.file.len() < self.declared_size() as usize
+    }
+
+    /// The number of bytes following `declared_size`, e.g. an installer payload or the certificate table
+    pub fn overlay_size(&self) -> usize {
+        self.file
+            .len()
+            .saturating_sub(self.declared_size() as usize)
+    }
+
+    /// The bytes following `declared_size`, empty when there is no overlay
+    pub fn overlay(&self) -> &'a [u8] {
+        self.file
+            .get(self.declared_size() as usize..)
+            .unwrap_or_default()
+    }
+
+    /// The `(section name, file range)` of the raw data of each section, in the order of the section table,
+    /// e.g. to carve the sections into separate files. The ranges are clamped to the file length,
+    /// so the header slack and the overlay are the gaps between them.
+    pub fn section_raw_ranges(&self) -> Vec<(String, Range<usize>)> {
+        self.header
+            .sections
+            .iter()
+            .map(|section| (section.name.clone(), section.raw_range(self.file.len())))
+            .collect()
+    }
+
+    /// The bytes following the last structure parsed sequentially, i.e. the section table,
+    /// which are the remaining input returned by `PE::parse` and discarded by `TryFrom`.
+    /// Unlike `overlay`, which follows the raw data of the sections, this includes the section data
+    /// since the directories are read at their offsets rather than in sequence.
+    pub fn trailing_data(&self) -> &'a [u8] {
+        let sections = &self.header.sections;
+        let end = sections.table_offset() + sections.table_size();
+        self.file.get(end..).unwrap_or_default()
+    }
+
+    /// The format of the overlay recognized by its signature, e.g. the payload of an installer
+    pub fn overlay_kind(&self) -> Option<OverlayKind> {
+        OverlayKind::sniff(self.overlay())
+    }
+
+    /// The installer family the image is the setup program of, from its section names, its overlay and its manifest.
+    /// See `InstallerKind::classify` for the signals.
+    pub fn installer_kind(&self) -> Option<InstallerKind> {
+        let section_names = self
+            .header
+            .sections
+            .iter()
+            .map(|section| section.name.as_str())
+            .collect::<Vec<&str>>();
+        let manifest = self.resources.as_ref().and_then(|resources| {
+            resources
+                .data_entries(ResourceType::Manifest)
+                .first()
+                .and_then(|entry| entry.decode_text(self.file, &self.header.sections))
+        });
+        InstallerKind::classify(
+            &section_names,
+            self.overlay_kind(),
+            self.overlay(),
+            manifest.as_deref(),
+        )
+    }
+
+    /// The file offset of the bundle header of a .NET single-file bundle, whose host is a native executable
+    /// with the assemblies of the application appended, so `clr` is None for bundles.
+    pub fn dotnet_bundle_header_offset(&self) -> Option<u64> {
+        overlay::find_dotnet_bundle_header(self.file)
+    }
+
+    /// The bytes of the RT_RCDATA resource named `name`, where installers and packers often stash payloads.
+    /// The data is truncated when the file is shorter than the declared size.
+    pub fn rcdata(&self, name: &str) -> Option<&'a [u8]> {
+        self.resources
+            .as_ref()?
+            .find_named(ResourceType::RcData, name)?
+            .data(self.file, &self.header.sections)
+    }
+
+    /// Whether the file is a .NET single-file bundle, see `dotnet_bundle_header_offset`
+    pub fn is_dotnet_bundle(&self) -> bool {
+        self.dotnet_bundle_header_offset().is_some()
+    }
+
+    /// Whether the file offsets equal the RVAs throughout, i.e. `file_alignment` equals `section_alignment`
+    /// and the raw data of every section is at its RVA, as in tiny or specially-built images.
+    /// RVAs can then be used as file offsets without looking up their section.
+    pub fn is_flat_mapped(&self) -> bool {
+        let optional_header = &self.header.nt_header.optional_header;
+        optional_header.file_alignment() == optional_header.section_alignment()
+            && self
+                .header
+                .sections
+                .iter()
+                .filter(|section| section.size_of_raw_data != 0)
+                .all(|section| section.ptr_to_raw_data == section.vir_addr)
+    }
+
+    /// Size of the headers computed from the end of the section table rounded up to `file_alignment`.
+    /// A value different from the declared `size_of_headers` indicates header slack that can hide data.
+    pub fn actual_headers_size(&self) -> u32 {
+        let section_table_end =
+            self.header.sections.table_offset() + self.header.sections.table_size();
+        utils::align_up(
+            u32::try_from(section_table_end).unwrap_or(u32::MAX),
+            self.header.nt_header.optional_header.file_alignment(),
+        )
+    }
+
+    /// Whether the computed headers size matches the declared `size_of_headers`
+    pub fn headers_size_matches(&self) -> bool {
+        self.actual_headers_size() == self.header.nt_header.optional_header.size_of_headers()
+    }
+
+    /// The bytes between the end of the section table and the raw data of the first section,
+    /// i.e. the padding of the headers, where injected data frequently hides.
+    /// Without section data, the slack runs up to `size_of_headers`. Truncated at the end of the file.
+    pub fn header_slack(&self) -> &'a [u8] {
+        let sections = &self.header.sections;
+        let start = sections.table_offset() + sections.table_size();
+        let end = self
+            .first_raw_data_offset()
+            .unwrap_or(self.header.nt_header.optional_header.size_of_headers())
+            as usize;
+        let end = end.min(self.file.len());
+        self.file.get(start..end).unwrap_or_default()
+    }
+
+    /// The raw data offset of the first section in the file, ignoring the sections without raw data
+    fn first_raw_data_offset(&self) -> Option<u32> {
+        self.header
+            .sections
+            .iter()
+            .filter(|section| section.size_of_raw_data != 0 && section.ptr_to_raw_data != 0)
+            .map(|section| section.ptr_to_raw_data)
+            .min()
+    }
+
+    /// The raw data offset of the first section minus `size_of_headers`: positive when padding follows
+    /// the declared headers, and negative when a section starts inside them, so that its bytes are
+    /// parsed as both headers and section data. 0 without section data.
+    pub fn header_to_first_section_gap(&self) -> i64 {
+        match self.first_raw_data_offset() {
+            Some(offset) => {
+                offset as i64 - self.header.nt_header.optional_header.size_of_headers() as i64
+            }
+            None => 0,
+        }
+    }
+
+    /// The memory protection of the header region, which the loader always maps read-only,
+    /// so that a memory map can list it like the sections with `Section::permissions`
+    pub fn header_permissions(&self) -> &'static str {
+        "r--"
+    }
+
+    /// The bytes from the start of the file through `size_of_headers`, which the loader maps read-only,
+    /// e.g. to cache the headers and re-parse them later without the rest of the file.
+    /// The region extends to the end of the section table when `size_of_headers` is smaller,
+    /// so that it can always be re-parsed, and is truncated at the end of the file.
+    pub fn header_region(&self) -> &'a [u8] {
+        let sections = &self.header.sections;
+        let section_table_end = sections.table_offset() + sections.table_size();
+        let end = (self.header.nt_header.optional_header.size_of_headers() as usize)
+            .max(section_table_end)
+            .min(self.file.len());
+        &self.file[..end]
+    }
+
+    /// The layout of the mapped image: the headers, then each section, sorted by RVA,
+    /// with the ranges no section maps up to `size_of_image` as unmapped regions.
+    /// The virtual sizes are rounded up to `OptionalHeader::section_alignment()` as the loader does.
+    pub fn memory_map(&self) -> Vec<MappedRegion> {
+        let optional_header = &self.header.nt_header.optional_header;
+        let alignment = optional_header.section_alignment();
+        let headers = MappedRegion {
+            name: MappedRegion::HEADERS.to_string(),
+            rva: 0,
+            virtual_size: utils::align_up(optional_header.size_of_headers(), alignment),
+            raw_offset: 0,
+            raw_size: self.header_region().len() as u32,
+            permissions: self.header_permissions(),
+        };
+        let sections = self.header.sections.iter().map(|section| MappedRegion {
+            name: section.name.clone(),
+            rva: section.vir_addr,
+            virtual_size: utils::align_up(section.mapped_size(), alignment),
+            raw_offset: section.ptr_to_raw_data,
+            raw_size: section.size_of_raw_data,
+            permissions: section.permissions(),
+        });
+        memory_map::with_gaps(
+            std::iter::once(headers).chain(sections).collect(),
+            optional_header.size_of_image(),
+        )
+    }
+
+    /// The SizeOfImage the loader derives from the sections, i.e. the end of the last section in memory
+    /// rounded up to `OptionalHeader::section_alignment()`, and at least the aligned headers.
+    /// A section occupies its `Section::mapped_size`, as the loader does.
+    /// This is the size of the buffer to allocate when mapping the image.
+    pub fn computed_size_of_image(&self) -> u32 {
+        let optional_header = &self.header.nt_header.optional_header;
+        let alignment = optional_header.section_alignment();
+        self.header
+            .sections
+            .iter()
+            .map(|section| {
+                utils::align_up(
+                    section.vir_addr.saturating_add(section.mapped_size()),
+                    alignment,
+                )
+            })
+            .fold(
+                utils::align_up(optional_header.size_of_headers(), alignment),
+                u32::max,
+            )
+    }
+
+    /// Same as `computed_size_of_image`
+    pub fn virtual_size(&self) -> u32 {
+        self.computed_size_of_image()
+    }
+
+    /// Whether the computed size of the image matches the declared `size_of_image`.
+    /// A larger `size_of_image` or one that isn't a multiple of `section_of_alignment` is a loader red flag.
+    pub fn size_of_image_matches(&self) -> bool {
+        self.computed_size_of_image() == self.header.nt_header.optional_header.size_of_image()
+    }
+
+    /// Reads a u16 value at the rva, or None when the rva is not mapped or the file is too short
+    pub fn read_u16_at_rva(&self, rva: u32) -> Option<u16> {
+        let offset = self.header.sections.rva_to_offset(rva)?;
+        utils::read_u16_at(self.file, offset as usize)
+    }
+
+    /// Reads a u32 value at the rva, or None when the rva is not mapped or the file is too short
+    pub fn read_u32_at_rva(&self, rva: u32) -> Option<u32> {
+        let offset = self.header.sections.rva_to_offset(rva)?;
+        utils::read_u32_at(self.file, offset as usize)
+    }
+
+    /// Reads a u64 value at the rva, or None when the rva is not mapped or the file is too short
+    pub fn read_u64_at_rva(&self, rva: u32) -> Option<u64> {
+        let offset = self.header.sections.rva_to_offset(rva)?;
+        utils::read_u64_at(self.file, offset as usize)
+    }
+
+    /// The bytes of a data directory, truncated when the file is shorter than the declared size.
+    /// None when the directory is absent or its RVA is not mapped by a section.
+    pub fn directory_bytes(&self, entry: DirectoryEntry) -> Option<&'a [u8]> {
+        let directory = self
+            .header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(entry)
+            .filter(|directory| directory.is_present())?;
+        // the certificate table is not mapped, its address is a file offset
+        let offset = if entry == DirectoryEntry::Certificate {
+            directory.virtual_address
+        } else {
+            self.header
+                .sections
+                .rva_to_offset(directory.virtual_address)?
+        } as usize;
+        let end = offset
+            .saturating_add(directory.size as usize)
+            .min(self.file.len());
+        self.file.get(offset..end)
+    }
+
+    /// The raw base relocation blocks, for loaders applying the relocations themselves
+    pub fn reloc_directory_bytes(&self) -> Option<&'a [u8]> {
+        self.directory_bytes(DirectoryEntry::BaseRelocation)
+    }
+
+    /// The name, version and public key token of the .NET assembly, or None for native images
+    #[cfg(feature = "dotnet")]
+    pub fn dotnet_assembly(&self) -> Option<AssemblyInfo> {
+        let metadata = self.clr.as_ref()?.metadata.as_ref()?;
+        AssemblyInfo::parse(self.file, metadata)
+    }
+
+    pub fn machine(&self) -> Machine {
+        self.header.nt_header.file_header.machine
+    }
+
+    /// The subsystem required to run the image, or None for values unknown to the parser
+    pub fn subsystem(&self) -> Option<Subsystem> {
+        self.header.nt_header.optional_header.subsystem()
+    }
+
+    pub fn sections(&self) -> &Sections {
+        &self.header.sections
+    }
+
+    /// The descriptors of the imported DLLs, empty without import directory
+    pub fn import_descriptors(&self) -> &[ImportDescriptor] {
+        match &self.imports {
+            Some(imports) => imports.directory_table.descriptors(),
+            None => &[],
+        }
+    }
+
+    /// The exported functions, empty without export directory
+    pub fn exported_functions(&self) -> &[ExportedFunction] {
+        match &self.export {
+            Some(export) => &export.functions,
+            None => &[],
+        }
+    }
+
+    /// The sections whose permissions contradict their conventional name, e.g. a writable `.text`,
+    /// in the order of the section table
+    pub fn characteristic_anomalies(&self) -> Vec<Anomaly> {
+        self.header
+            .sections
+            .iter()
+            .filter_map(Anomaly::of_section)
+            .collect()
+    }
+
+    /// A label for the target of a call or a jump, e.g. for a disassembler: the name of the export at the rva,
+    /// `#<ordinal>` for an export by ordinal only, or `DLL!function` for an import address table slot
+    /// read by an indirect call. Exports take precedence; None when nothing starts at the rva.
+    pub fn symbolicate_rva(&self, rva: u32) -> Option<String> {
+        let exports = self
+            .exported_functions()
+            .iter()
+            .filter(|function| function.rva == rva && function.forwarder.is_none());
+        let mut first_export = None;
+        for function in exports {
+            if let Some(name) = &function.name {
+                return Some(name.clone());
+            }
+            first_export.get_or_insert(function.ordinal);
+        }
+        if let Some(ordinal) = first_export {
+            return Some(format!("#{}", ordinal));
+        }
+        self.import_descriptors().iter().find_map(|descriptor| {
+            let (_, entry) = descriptor
+                .iat_slots(self.is_64bit())
+                .into_iter()
+                .find(|(slot, _)| *slot == rva)?;
+            Some(format!(
+                "{}!{}",
+                descriptor.name().unwrap_or("?"),
+                entry.identifier()
+            ))
+        })
+    }
+
+    /// The `(this module, DLL)` edges of the dependency graph: one per imported DLL and one per DLL
+    /// targeted by a forwarder, e.g. `NTDLL.dll` for `NTDLL.RtlAllocateHeap`, without duplicates.
+    /// This module is named by the export directory, or by `file_name` since the parser never sees the path.
+    pub fn dependency_edges(&self, file_name: &str) -> Vec<(String, String)> {
+        let this_module = match &self.export {
+            Some(export) if !export.dll_name.is_empty() => export.dll_name.clone(),
+            _ => file_name.to_string(),
+        };
+        let imported = self
+            .import_descriptors()
+            .iter()
+            .filter_map(|descriptor| descriptor.name())
+            .map(String::from);
+        // the loader appends .dll to the module part of a forwarder
+        let forwarded = self
+            .exported_functions()
+            .iter()
+            .filter_map(|function| function.forwarder.as_deref()?.rsplit_once('.'))
+            .map(|(dll, _)| format!("{}.dll", dll));
+        let mut edges = vec![];
+        for dll in imported.chain(forwarded) {
+            let edge = (this_module.clone(), dll);
+            if !edges.contains(&edge) {
+                edges.push(edge);
+            }
+        }
+        edges
+    }
+
+    /// The preferred load address regardless of the bitness
+    pub fn image_base(&self) -> u64 {
+        self.header.nt_header.optional_header.image_base()
+    }
+
+    /// The stack and heap sizes of the optional header regardless of the bitness
+    pub fn memory_config(&self) -> MemoryConfig {
+        self.header.nt_header.optional_header.memory_config()
+    }
+
+    /// Converts a virtual address, e.g. a TLS callback or a relocation target, to an RVA.
+    /// Returns None when the address is below the image base or beyond `size_of_image`.
+    pub fn va_to_rva(&self, va: u64) -> Option<u32> {
+        let rva = u32::try_from(va.checked_sub(self.image_base())?).ok()?;
+        if rva < self.header.nt_header.optional_header.size_of_image() {
+            Some(rva)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the TLS data directory is present, a cheap check which doesn't need the TLS directory to be parsed
+    pub fn has_tls(&self) -> bool {
+        self.header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(DirectoryEntry::Tls)
+            .is_some_and(|directory| directory.is_present())
+    }
+
+    /// Whether the parsed TLS directory has callbacks, which run before the entry point
+    pub fn has_tls_callbacks(&self) -> bool {
+        self.tls
+            .as_ref()
+            .is_some_and(|tls| !tls.callbacks.is_empty())
+    }
+
+    /// The RVAs of the TLS callbacks located in a writable or non-executable section, or in no section at all.
+    /// Callbacks run before the entry point, and one outside regular code is a common anti-analysis trick,
+    /// e.g. code unpacked at runtime before a debugger breaks at the entry point.
+    /// Callbacks outside the image are not mapped to an RVA and are not included.
+    pub fn tls_callbacks_in_writable_sections(&self) -> Vec<u32> {
+        self.tls
+            .iter()
+            .flat_map(|tls| tls.callbacks.iter())
+            .filter_map(|callback| self.va_to_rva(*callback))
+            .filter(|rva| match self.header.sections.find_by_address(*rva) {
+                Some(section) => section.is_writable() || !section.is_executable(),
+                None => true,
+            })
+            .collect()
+    }
+
+    /// Whether the rva lands in an executable section, e.g. to validate a call or jump target.
+    /// False for the headers and the RVAs no section maps.
+    pub fn is_executable_rva(&self, rva: u32) -> bool {
+        self.header
+            .sections
+            .find_by_address(rva)
+            .is_some_and(|section| section.is_executable())
+    }
+
+    /// The section containing the entry point, None for DLLs without entry point
+    /// or an entry point outside the sections, e.g. in the headers
+    pub fn entry_point_section(&self) -> Option<&Section> {
+        let entry_point = self
+            .header
+            .nt_header
+            .optional_header
+            .address_of_entry_point();
+        if entry_point == 0 {
+            return None;
+        }
+        self.header.sections.find_by_address(entry_point)
+    }
+
+    /// The offset of the entry point from the start of its section, see `entry_point_section`
+    pub fn entry_point_section_offset(&self) -> Option<u32> {
+        let section = self.entry_point_section()?;
+        Some(
+            self.header
+                .nt_header
+                .optional_header
+                .address_of_entry_point()
+                - section.vir_addr,
+        )
+    }
+
+    /// Candidate RVAs of the original entry point of a packed file, e.g. to set breakpoints while unpacking,
+    /// ranked by likelihood: the TLS callbacks which run first, the declared entry point,
+    /// then the start of the executable sections other than the one of the declared entry point.
+    /// Each RVA is listed once, at its highest rank.
+    pub fn likely_oep_hints(&self) -> Vec<u32> {
+        let entry_point = self
+            .header
+            .nt_header
+            .optional_header
+            .address_of_entry_point();
+        let entry_point_section = self.entry_point_section();
+        let tls_callbacks = self
+            .tls
+            .iter()
+            .flat_map(|tls| tls.callbacks.iter())
+            .filter_map(|callback| self.va_to_rva(*callback));
+        let section_starts = self
+            .header
+            .sections
+            .iter()
+            .filter(|section| section.is_executable())
+            .filter(|section| !entry_point_section.is_some_and(|ep| std::ptr::eq(ep, *section)))
+            .map(|section| section.vir_addr);
+        let mut hints = vec![];
+        for rva in tls_callbacks
+            .chain(Some(entry_point).filter(|rva| *rva != 0))
+            .chain(section_starts)
+        {
+            if !hints.contains(&rva) {
+                hints.push(rva);
+            }
+        }
+        hints
+    }
+
+    /// Converts an RVA to a virtual address at the preferred load address
+    pub fn rva_to_va(&self, rva: u32) -> u64 {
+        self.image_base().wrapping_add(rva as u64)
+    }
+
+    /// Whether the image can't be rebased, which defeats ASLR: either the linker marked the relocations
+    /// as stripped with `FileCharacteristic::RelocsStripped`, or there is no base relocation directory to apply
+    pub fn relocations_stripped(&self) -> bool {
+        let flagged = self
+            .header
+            .nt_header
+            .file_header
+            .has_characteristic(FileCharacteristic::RelocsStripped);
+        let has_directory = self
+            .header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(DirectoryEntry::BaseRelocation)
+            .is_some_and(|directory| directory.is_present());
+        flagged || !has_directory
+    }
+
+    /// Whether the image must be loaded at `preferred_base`: it doesn't opt into ASLR with
+    /// `IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE` and can't be rebased anyway, see `relocations_stripped`.
+    /// The loader fails to load such an image when the range is taken instead of relocating it.
+    /// An image without DYNAMIC_BASE but with relocations is only relocated on conflicts.
+    pub fn prefers_fixed_base(&self) -> bool {
+        let dynamic_base = self
+            .header
+            .nt_header
+            .optional_header
+            .dll_characteristic_flags()
+            .contains(&DllCharacteristic::DynamicBase);
+        !dynamic_base && self.relocations_stripped()
+    }
+
+    /// The address the image is linked at, i.e. `image_base`.
+    /// With ASLR the actual base is chosen by the loader, which applies the base relocations.
+    pub fn preferred_base(&self) -> u64 {
+        self.image_base()
+    }
+
+    /// Whether the raw data of some sections overlap in the file, see `Sections::raw_overlapping` for the pairs
+    pub fn sections_raw_overlap(&self) -> bool {
+        !self.header.sections.raw_overlapping().is_empty()
+    }
+
+    /// Whether the key of the Rich header matches its checksum, or None when there is no Rich header
+    pub fn rich_header_checksum_valid(&self) -> Option<bool> {
+        let rich_header = self.header.dos_header.rich_header()?;
+        Some(rich_header.verify_checksum(self.file))
+    }
+
+    /// The checksum of the file computed the way the linker does, to compare with the declared `check_sum`
+    pub fn computed_checksum(&self) -> u32 {
+        // CheckSum is at the same offset of the PE32 and the PE32+ optional header
+        let checksum_offset = self.nt_header_offset() + 24 + 64;
+        utils::pe_checksum(self.file, checksum_offset)
+    }
+
+    /// Whether the declared checksum matches the computed one.
+    /// Most user-mode images leave it zero, which is reported as a mismatch.
+    pub fn checksum_valid(&self) -> bool {
+        self.computed_checksum() == self.header.nt_header.optional_header.check_sum()
+    }
+
+    /// Like `checksum_valid`, telling the usual zero checksum apart from a mismatch, which is a sign of tampering
+    pub fn checksum_status(&self) -> ChecksumStatus {
+        if self.header.nt_header.optional_header.check_sum() == 0 {
+            ChecksumStatus::Absent
+        } else if self.checksum_valid() {
+            ChecksumStatus::Valid
+        } else {
+            ChecksumStatus::Invalid
+        }
+    }
+
+    /// A single line identifying the image for logs, in the spirit of `file`,
+    /// e.g. "PE32+ executable (GUI) x86-64, 6 sections, signed, .NET"
+    pub fn identity_line(&self) -> String {
+        let file_header = &self.header.nt_header.file_header;
+        let optional_header = &self.header.nt_header.optional_header;
+        let format = if self.is_64bit() { "PE32+" } else { "PE32" };
+        let kind = if self.is_driver() {
+            "driver"
+        } else if file_header.has_characteristic(FileCharacteristic::Dll) {
+            "DLL"
+        } else {
+            "executable"
+        };
+        let subsystem = match optional_header.subsystem() {
+            Some(Subsystem::WindowsGui) | Some(Subsystem::WindowsCeGui) => "GUI".to_string(),
+            Some(Subsystem::WindowsCui) | Some(Subsystem::Os2Cui) | Some(Subsystem::PosixCui) => {
+                "console".to_string()
+            }
+            Some(Subsystem::Native) | Some(Subsystem::NativeWindows) => "native".to_string(),
+            Some(subsystem) => subsystem.to_string(),
+            None => "unknown subsystem".to_string(),
+        };
+        let machine = match file_header.machine {
+            Machine::Amd64 => "x86-64".to_string(),
+            Machine::I386 => "Intel 80386".to_string(),
+            Machine::Arm64 => "Aarch64".to_string(),
+            machine => machine.to_string(),
+        };
+        let num_of_sections = self.header.sections.len();
+        let mut line = format!(
+            "{} {} ({}) {}, {} section{}",
+            format,
+            kind,
+            subsystem,
+            machine,
+            num_of_sections,
+            if num_of_sections == 1 { "" } else { "s" }
+        );
+        if self.directory_presence().certificate {
+            line.push_str(", signed");
+        }
+        #[cfg(feature = "dotnet")]
+        if self.clr.is_some() {
+            line.push_str(", .NET");
+        }
+        line
+    }
+
+    /// The file offset of the NT header, which is `lfanew` of the DOS header
+    pub fn nt_header_offset(&self) -> usize {
+        self.header.nt_header.offset
+    }
+
+    /// The offsets of every `PE\0\0` signature in the input, in file order, without parsing it.
+    /// A signature at another offset than `nt_header_offset` may be a decoy header planted for analysis tools
+    /// which look for the signature instead of following `lfanew`.
+    pub fn find_all_pe_signatures(input: &[u8]) -> Vec<usize> {
+        input
+            .windows(4)
+            .enumerate()
+            .filter(|(_, window)| *window == b"PE\0\0")
+            .map(|(offset, _)| offset)
+            .collect()
+    }
+
+    /// Whether the image is PE32+
+    pub fn is_64bit(&self) -> bool {
+        self.header.nt_header.optional_header.is_64bit()
+    }
+
+    /// The bitness told by the magic of the optional header.
+    /// Parsing rejects ROM images, so a parsed image is never `Bitness::Rom`, see `OptionalHeaderMagic::bitness`.
+    pub fn bitness(&self) -> Bitness {
+        self.header.nt_header.optional_header.magic().bitness()
+    }
+
+    /// Whether the image is a kernel-mode driver.
+    /// There is no single bit marking drivers, so this is a heuristic: the subsystem is native
+    /// and the image imports from the kernel (`ntoskrnl.exe`) or the HAL (`hal.dll`).
+    /// The imports tell drivers apart from native user-mode processes like `smss.exe`, which only import `ntdll.dll`.
+    pub fn is_driver(&self) -> bool {
+        const KERNEL_MODULES: [&str; 2] = ["ntoskrnl.exe", "hal.dll"];
+        let subsystem = self.header.nt_header.optional_header.subsystem();
+        if !matches!(
+            subsystem,
+            Some(Subsystem::Native) | Some(Subsystem::NativeWindows)
+        ) {
+            return false;
+        }
+        self.imports.as_ref().is_some_and(|imports| {
+            imports
+                .directory_table
+                .descriptors()
+                .iter()
+                .any(|descriptor| {
+                    descriptor.name().is_some_and(|name| {
+                        KERNEL_MODULES
+                            .iter()
+                            .any(|module| name.eq_ignore_ascii_case(module))
+                    })
+                })
+        })
+    }
+
+    /// The toolchain which most likely built the image, from the Rich header, the linker version
+    /// and the CodeView debug entry. See `BuildOrigin::classify` for the heuristic.
+    pub fn build_origin(&self) -> BuildOrigin {
+        BuildOrigin::classify(
+            self.header.dos_header.has_rich_header(),
+            self.header.nt_header.optional_header.linker_version(),
+            self.debug
+                .as_ref()
+                .is_some_and(|debug| debug.codeview.is_some()),
+        )
+    }
+
+    /// Whether each data directory is present, which is cheap since no directory is parsed
+    pub fn directory_presence(&self) -> DirectoryPresence {
+        self.header
+            .nt_header
+            .optional_header
+            .data_directories()
+            .presence()
+    }
+
+    /// The sections whose virtual range intersects the range of the directory.
+    /// A directory spanning several sections, or none, explains partial or missing parse results,
+    /// since directory parsers read from the section containing the start of the directory.
+    /// The certificate directory covers no section since its address is a file offset.
+    pub fn sections_covering_directory(&self, entry: DirectoryEntry) -> Vec<&Section> {
+        let directory = match self
+            .header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(entry)
+            .filter(|directory| directory.is_present() && entry != DirectoryEntry::Certificate)
+        {
+            Some(directory) => directory,
+            None => return vec![],
+        };
+        let start = directory.virtual_address;
+        let end = start.saturating_add(directory.size);
+        self.header
+            .sections
+            .iter()
+            .filter(|section| {
+                section.vir_addr < end
+                    && start < section.vir_addr.saturating_add(section.mapped_size())
+            })
+            .collect()
+    }
+
+    /// The section containing the start of the export directory, conventionally `.edata` but `.rdata`
+    /// for most linkers, where the names and the forwarder strings are expected to live too.
+    /// None without export directory or when its RVA is not mapped.
+    pub fn export_section(&self) -> Option<&Section> {
+        let directory = self
+            .header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(DirectoryEntry::Export)
+            .filter(|directory| directory.is_present())?;
+        self.header
+            .sections
+            .find_by_address(directory.virtual_address)
+    }
+
+    /// The present data directories whose RVA is not mapped by any section, e.g. pointing into the headers
+    /// or beyond the image, which is why such a directory may fail to parse or be ignored.
+    /// The certificate directory is left out since its address is a file offset by design,
+    /// and so is the bound import directory, which is located in the headers by design.
+    pub fn orphan_directories(&self) -> Vec<DirectoryEntry> {
+        self.header
+            .nt_header
+            .optional_header
+            .data_directories()
+            .iter()
+            .filter(|directory| {
+                directory.is_present()
+                    && !matches!(
+                        directory.entry,
+                        DirectoryEntry::Certificate | DirectoryEntry::BoundImport
+                    )
+            })
+            .filter(|directory| {
+                self.header
+                    .sections
+                    .find_by_address(directory.virtual_address)
+                    .is_none()
+            })
+            .map(|directory| directory.entry)
+            .collect()
+    }
+
+    /// Every (RVA, size) region the headers point into: the present data directories and the entry point.
+    /// The certificate directory is left out since its address is a file offset rather than an RVA.
+    pub fn referenced_rvas(&self) -> Vec<(RvaReference, u32, u32)> {
+        let optional_header = &self.header.nt_header.optional_header;
+        let mut references: Vec<(RvaReference, u32, u32)> = optional_header
+            .data_directories()
+            .iter()
+            .filter(|directory| {
+                directory.is_present() && directory.entry != DirectoryEntry::Certificate
+            })
+            .map(|directory| {
+                (
+                    RvaReference::Directory(directory.entry),
+                    directory.virtual_address,
+                    directory.size,
+                )
+            })
+            .collect();
+        let entry_point = optional_header.address_of_entry_point();
+        if entry_point != 0 {
+            references.push((RvaReference::EntryPoint, entry_point, 0));
+        }
+        references
+    }
+
+    /// Whether the image is marked compatible with CET shadow stacks,
+    /// which is set in the extended DLL characteristics of the debug directory
+    pub fn supports_cet(&self) -> bool {
+        self.ex_dll_characteristics() & IMAGE_DLLCHARACTERISTICS_EX_CET_COMPAT != 0
+    }
+
+    /// Whether CET shadow stacks are enforced in strict mode for the image
+    pub fn cet_compat_strict_mode(&self) -> bool {
+        self.ex_dll_characteristics() & IMAGE_DLLCHARACTERISTICS_EX_CET_COMPAT_STRICT_MODE != 0
+    }
+
+    fn ex_dll_characteristics(&self) -> u32 {
+        self.debug
+            .as_ref()
+            .and_then(|debug| debug.ex_dll_characteristics)
+            .unwrap_or(0)
+    }
+
+    /// The path of the PDB file on a symbol server, `<pdbname>/<guid><age>/<pdbname>`,
+    /// when the debug directory has a CodeView entry
+    pub fn pdb_symbol_path(&self) -> Option<String> {
+        self.debug
+            .as_ref()
+            .and_then(|debug| debug.codeview.as_ref())
+            .map(|codeview| codeview.symbol_path())
+    }
+
+    /// RVA of the value stored in the global pointer register on IA64 and MIPS.
+    /// Unlike the other directories the size is always zero, so only the address is checked.
+    pub fn global_pointer(&self) -> Option<u32> {
+        self.header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(DirectoryEntry::Globalptr)
+            .map(|directory| directory.virtual_address)
+            .filter(|rva| *rva != 0)
+    }
+
+    /// The raw architecture-specific data, e.g. fixups on IA64, or None when the directory is absent
+    pub fn architecture_data(&self) -> Option<&'a [u8]> {
+        self.directory_bytes(DirectoryEntry::Architecture)
+    }
+
+    /// Whether the architecture directory is set on x86 or x64, where it is reserved and must be zero
+    pub fn has_nonstandard_architecture_directory(&self) -> bool {
+        let is_x86 = matches!(self.machine(), Machine::I386 | Machine::Amd64);
+        is_x86
+            && self
+                .header
+                .nt_header
+                .optional_header
+                .find_directory_by_entry(DirectoryEntry::Architecture)
+                .is_some_and(|directory| directory.virtual_address != 0 || directory.size != 0)
+    }
+
+    /// Compares the export directory timestamp with the file header timestamp to detect time-stomping
+    pub fn timestamp_consistency(&self) -> TimestampConsistency {
+        let export = match &self.export {
+            Some(export) => export,
+            None => return TimestampConsistency::NoExports,
+        };
+        if export.time_date_stamp == 0 {
+            TimestampConsistency::ExportZeroed
+        } else if export.time_date_stamp == self.header.nt_header.file_header.time_date_stamp {
+            TimestampConsistency::Consistent
+        } else {
+            TimestampConsistency::Mismatch
+        }
+    }
+
+    /// The bound import descriptors written by `bind.exe`, or None when the directory is absent.
+    /// The directory is usually located in the headers, which are mapped at RVA 0 and not covered by a section.
+    /// The module names are read in the default `StringMode`, see `bound_imports_with_options`.
+    pub fn bound_imports(&self) -> Option<BoundImportTable> {
+        self.bound_imports_with_options(&ParseOptions::default())
+            .and_then(Result::ok)
+    }
+
+    /// Parses the bound import descriptors like `bound_imports`, reading the module names in the string mode
+    /// of the options, which fails on a name that is not valid UTF-8 in `StringMode::Strict`
+    pub fn bound_imports_with_options(
+        &self,
+        options: &ParseOptions,
+    ) -> Option<DirectoryResult<'a, BoundImportTable>> {
+        let directory = self
+            .header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(DirectoryEntry::BoundImport)
+            .filter(|directory| directory.is_present())?;
+        let offset = match self
+            .header
+            .sections
+            .rva_to_offset(directory.virtual_address)
+        {
+            Some(offset) => offset,
+            None if directory.virtual_address
+                < self.header.nt_header.optional_header.size_of_headers() =>
+            {
+                directory.virtual_address
+            }
+            None => return None,
+        } as usize;
+        let end = offset
+            .saturating_add(directory.size as usize)
+            .min(self.file.len());
+        let ctx = ParseContext::from_options(options);
+        Some(BoundImportTable::parse(self.file.get(offset..end)?, &ctx))
+    }
+
+    /// The timestamps of the file header, the export directory, the bound delay imports and the bound imports.
+    /// Comparing them tells whether the file header timestamp was modified, see `Timestamps`.
+    /// The dates are converted from the raw timestamps even when `ParseOptions::decode_timestamps` is false.
+    pub fn timestamps(&self) -> Timestamps {
+        let to_datetime =
+            |timestamp: u32| DateTime::from_timestamp(timestamp as i64, 0).unwrap_or_default();
+        Timestamps {
+            file_header: to_datetime(self.header.nt_header.file_header.time_date_stamp),
+            export: self
+                .export
+                .as_ref()
+                .filter(|export| export.time_date_stamp != 0)
+                .map(|export| to_datetime(export.time_date_stamp)),
+            delay_imports: self
+                .delay_imports
+                .iter()
+                .flat_map(|table| table.descriptors())
+                .filter(|descriptor| descriptor.time_date_stamp != 0)
+                .map(|descriptor| {
+                    (
+                        descriptor.name().unwrap_or_default().to_string(),
+                        to_datetime(descriptor.time_date_stamp),
+                    )
+                })
+                .collect(),
+            bound_imports: self
+                .bound_imports()
+                .iter()
+                .flat_map(|table| table.descriptors())
+                .map(|descriptor| {
+                    (
+                        descriptor.module_name.clone(),
+                        to_datetime(descriptor.time_date_stamp),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// RVA and size of the Import Address Table region pointed by the IAT data directory
+    pub fn iat_range(&self) -> Option<(u32, u32)> {
+        self.header
+            .nt_header
+            .optional_header
+            .find_directory_by_entry(DirectoryEntry::ImportAddressTable)
+            .filter(|iat| iat.is_present())
+            .map(|iat| (iat.virtual_address, iat.size))
+    }
+
+    /// Reads the whole Import Address Table as pointer-sized entries.
+    /// The entries are the same as the import lookup table on disk and the bound addresses for bound imports.
+    pub fn iat_entries(&self) -> Vec<u64> {
+        let (rva, size) = match self.iat_range() {
+            Some(range) => range,
+            None => return vec![],
+        };
+        let offset = match self.header.sections.rva_to_offset(rva) {
+            Some(offset) => (offset as usize).min(self.file.len()),
+            None => return vec![],
+        };
+        let end = offset.saturating_add(size as usize).min(self.file.len());
+        let data = &self.file[offset..end];
+        if self.is_64bit() {
+            data.chunks_exact(8).map(LittleEndian::read_u64).collect()
+        } else {
+            data.chunks_exact(4)
+                .map(|entry| LittleEndian::read_u32(entry) as u64)
+                .collect()
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for PE<'a> {
+    type Error = nom::Err<errors::PEError<parse::Input<'a>>>;
+
+    /// Parses the whole file, discarding the remaining input returned by `PE::parse`,
+    /// which is still available with `PE::trailing_data`
+    fn try_from(input: &'a [u8]) -> Result<Self, Self::Error> {
+        Self::parse(input).map(|(_, pe)| pe)
+    }
 }
 
+/// `{:#}` prints a compact rendering with a line per header, section, imported DLL and so on,
+/// leaving out the fields rarely looked at
 impl<'a> fmt::Display for PE<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "{}", self.header)?;
+        if f.alternate() {
+            writeln!(f, "{:#}", self.header)?;
+        } else {
+            writeln!(f, "{}", self.header)?;
+        }
         match &self.imports {
+            Some(i) if f.alternate() => writeln!(f, "{:#}", i)?,
             Some(i) => writeln!(f, "{}", i)?,
             None => (),
         };
-        match &self.export {
-            Some(e) => writeln!(f, "{}", e),
-            None => Ok(()),
+        if let Some(delay_imports) = &self.delay_imports {
+            writeln!(f, "{}", delay_imports)?;
+        }
+        if let Some(e) = &self.export {
+            if f.alternate() {
+                writeln!(f, "{:#}", e)?;
+            } else {
+                writeln!(f, "{}", e)?;
+            }
+        }
+        if let Some(resources) = &self.resources {
+            writeln!(f, "{}", resources)?;
+        }
+        if let Some(debug) = &self.debug {
+            writeln!(f, "{}", debug)?;
+        }
+        match &self.relocations {
+            Some(relocations) if f.alternate() => writeln!(f, "{:#}", relocations)?,
+            Some(relocations) => writeln!(f, "{}", relocations)?,
+            None => (),
+        };
+        if let Some(tls) = &self.tls {
+            writeln!(f, "{}", tls)?;
+        }
+        #[cfg(feature = "dotnet")]
+        if let Some(clr) = &self.clr {
+            writeln!(f, "{}", clr)?;
         }
+        Ok(())
     }
 }
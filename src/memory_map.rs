@@ -0,0 +1,116 @@
+use std::fmt;
+use std::fmt::Formatter;
+
+/// A region of the image once mapped by the loader: the headers, a section or a gap between them,
+/// see `PE::memory_map`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedRegion {
+    pub name: String, // the section name, "headers" or "<unmapped>" for a gap
+    pub rva: u32,
+    pub virtual_size: u32, // rounded up to `OptionalHeader::section_alignment()`
+    pub raw_offset: u32,
+    pub raw_size: u32, // 0 for the gaps and the sections without raw data
+    pub permissions: &'static str,
+}
+
+impl MappedRegion {
+    pub const HEADERS: &'static str = "headers";
+    pub const UNMAPPED: &'static str = "<unmapped>";
+
+    /// Whether the region is a gap which no section maps, so that accessing it faults
+    pub fn is_unmapped(&self) -> bool {
+        self.name == Self::UNMAPPED
+    }
+
+    fn end(&self) -> u32 {
+        self.rva.saturating_add(self.virtual_size)
+    }
+}
+
+/// Sorts the regions by RVA and inserts an unmapped region before each region starting after the end
+/// of the previous ones, and up to `size_of_image`. Overlapping regions are kept as they are.
+pub(crate) fn with_gaps(mut regions: Vec<MappedRegion>, size_of_image: u32) -> Vec<MappedRegion> {
+    regions.sort_by_key(|region| region.rva);
+    let gap = |start: u32, end: u32| MappedRegion {
+        name: MappedRegion::UNMAPPED.to_string(),
+        rva: start,
+        virtual_size: end - start,
+        raw_offset: 0,
+        raw_size: 0,
+        permissions: "---",
+    };
+    let mut map = vec![];
+    let mut end = 0;
+    for region in regions {
+        if region.rva > end {
+            map.push(gap(end, region.rva));
+        }
+        end = end.max(region.end());
+        map.push(region);
+    }
+    if size_of_image > end {
+        map.push(gap(end, size_of_image));
+    }
+    map
+}
+
+impl fmt::Display for MappedRegion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:#010x}-{:#010x} {} {}",
+            self.rva,
+            self.end(),
+            self.permissions,
+            self.name
+        )?;
+        if self.raw_size != 0 {
+            write!(f, " (raw {:#x}+{:#x})", self.raw_offset, self.raw_size)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{with_gaps, MappedRegion};
+
+    fn region(name: &str, rva: u32, virtual_size: u32) -> MappedRegion {
+        MappedRegion {
+            name: name.to_string(),
+            rva,
+            virtual_size,
+            raw_offset: rva,
+            raw_size: virtual_size,
+            permissions: "r--",
+        }
+    }
+
+    #[test]
+    fn gaps() {
+        let map = with_gaps(
+            vec![
+                region(".data", 0x3000, 0x1000),
+                region(MappedRegion::HEADERS, 0, 0x1000),
+                region(".text", 0x1000, 0x1000),
+            ],
+            0x5000,
+        );
+        let layout: Vec<(&str, u32, u32)> = map
+            .iter()
+            .map(|region| (region.name.as_str(), region.rva, region.virtual_size))
+            .collect();
+        assert_eq!(
+            layout,
+            vec![
+                ("headers", 0, 0x1000),
+                (".text", 0x1000, 0x1000),
+                ("<unmapped>", 0x2000, 0x1000),
+                (".data", 0x3000, 0x1000),
+                ("<unmapped>", 0x4000, 0x1000),
+            ]
+        );
+        assert!(map[2].is_unmapped());
+        assert_eq!(map[2].to_string(), "0x00002000-0x00003000 --- <unmapped>");
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::time::Duration;
+
+/// Time spent parsing each part of the file, recorded when the `metrics` feature is enabled
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParseMetrics {
+    pub headers: Duration,
+    pub imports: Duration,
+    pub delay_imports: Duration,
+    pub exports: Duration,
+    pub resources: Duration,
+    pub debug: Duration,
+    pub relocations: Duration,
+    pub tls: Duration,
+    #[cfg(feature = "dotnet")]
+    pub clr: Duration,
+}
+
+impl ParseMetrics {
+    /// Total time spent parsing the recorded parts
+    pub fn total(&self) -> Duration {
+        let total = self.headers
+            + self.imports
+            + self.delay_imports
+            + self.exports
+            + self.resources
+            + self.debug
+            + self.relocations
+            + self.tls;
+        #[cfg(feature = "dotnet")]
+        let total = total + self.clr;
+        total
+    }
+}
+
+impl fmt::Display for ParseMetrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "ParseMetrics")?;
+        write!(
+            f,
+            "  Headers: {:?}, Imports: {:?}, DelayImports: {:?}, Exports: {:?}, Resources: {:?}, Debug: {:?}, Relocations: {:?}, Tls: {:?}",
+            self.headers, self.imports, self.delay_imports, self.exports, self.resources, self.debug, self.relocations, self.tls
+        )?;
+        #[cfg(feature = "dotnet")]
+        write!(f, ", Clr: {:?}", self.clr)?;
+        write!(f, ", Total: {:?}", self.total())
+    }
+}
@@ -0,0 +1,296 @@
+use crate::annotations::{Annotations, FieldSpan};
+use crate::visitor::Visitor;
+use crate::{errors, parse, utils};
+use nom::error::context;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+
+/// Options of `PE::parse_with_options`
+#[derive(Debug, Clone, Copy)]
+pub struct ParseOptions {
+    pub budget: ParseBudget,
+    pub string_mode: StringMode,
+    /// Whether the timestamps of the headers are converted to dates.
+    /// When false, only their raw values are kept and the dates are left at the Unix epoch,
+    /// which saves the conversion in bulk scans and never fails on bogus timestamps.
+    pub decode_timestamps: bool,
+    /// Whether the hint/name entries of the functions imported by name are read.
+    /// When false, the imports only record the RVA of their entry, see `ImportEntry::ByNameUnresolved`,
+    /// which is enough to list the imported DLLs and count their functions much faster.
+    pub resolve_import_names: bool,
+    /// Whether the byte range of every field of the headers is collected in `PE::annotations`,
+    /// e.g. for a structure-aware hex viewer
+    pub annotations: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            budget: ParseBudget::default(),
+            string_mode: StringMode::default(),
+            decode_timestamps: true,
+            resolve_import_names: true,
+            annotations: false,
+        }
+    }
+}
+
+/// How the names read from the file, e.g. of DLLs, imported and exported functions and sections,
+/// are decoded when they are not valid UTF-8, which is common in corrupted or obfuscated files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StringMode {
+    /// Invalid sequences are replaced with the Unicode replacement character
+    #[default]
+    Lossy,
+    /// Parsing fails with an error on invalid UTF-8
+    Strict,
+    /// Each byte of an invalid sequence is escaped as `\xNN`, so that the original bytes can be recovered
+    RawBytes,
+}
+
+/// Limits on the number of entries collected while parsing,
+/// so that inflated counts in an untrusted file can't cause huge memory use.
+/// Parsing fails with an error when a limit is exceeded.
+#[derive(Debug, Clone, Copy)]
+pub struct ParseBudget {
+    /// The maximum number of entries of a single table, e.g. the functions of the export address table
+    pub max_entries_per_table: usize,
+    /// The maximum number of entries of all the tables of the file across the directories,
+    /// which bounds the total work of a file keeping each table under `max_entries_per_table`
+    pub max_total_entries: usize,
+}
+
+impl ParseBudget {
+    /// No limits, which is only appropriate for trusted files
+    pub const UNLIMITED: ParseBudget = ParseBudget {
+        max_entries_per_table: usize::MAX,
+        max_total_entries: usize::MAX,
+    };
+}
+
+impl Default for ParseBudget {
+    fn default() -> Self {
+        // resource directory tables have up to 2 * 65535 entries
+        Self {
+            max_entries_per_table: 0x20000,
+            max_total_entries: 0x100000,
+        }
+    }
+}
+
+/// Counts the entries collected during a parse against a `ParseBudget`
+#[derive(Debug, Default)]
+pub struct BudgetTracker {
+    budget: ParseBudget,
+    total: Cell<usize>,
+}
+
+impl BudgetTracker {
+    pub fn new(budget: ParseBudget) -> Self {
+        Self {
+            budget,
+            total: Cell::new(0),
+        }
+    }
+
+    /// Starts counting the entries of a table
+    pub fn table(&self, name: &'static str) -> TableBudget<'_> {
+        TableBudget {
+            budget: self.budget,
+            total: &self.total,
+            name,
+            count: 0,
+        }
+    }
+}
+
+/// The state of a parse passed to every parser:
+/// the `BudgetTracker` of the parse, the options changing what the parsers read,
+/// and the visitor the parsers call as they produce the parts of the file.
+pub struct ParseContext<'v> {
+    budget: BudgetTracker,
+    string_mode: StringMode,
+    decode_timestamps: bool,
+    resolve_import_names: bool,
+    annotations: Option<Annotations>,
+    visitor: RefCell<Option<&'v mut dyn Visitor>>,
+}
+
+impl<'v> ParseContext<'v> {
+    pub fn from_options(options: &ParseOptions) -> Self {
+        Self {
+            budget: BudgetTracker::new(options.budget),
+            string_mode: options.string_mode,
+            decode_timestamps: options.decode_timestamps,
+            resolve_import_names: options.resolve_import_names,
+            annotations: options.annotations.then(Annotations::default),
+            visitor: RefCell::new(None),
+        }
+    }
+
+    pub fn budget(&self) -> &BudgetTracker {
+        &self.budget
+    }
+
+    pub fn string_mode(&self) -> StringMode {
+        self.string_mode
+    }
+
+    /// Sets the visitor called by the parsers, see `PE::parse_with_visitor`
+    pub fn with_visitor(self, visitor: &'v mut dyn Visitor) -> Self {
+        Self {
+            visitor: RefCell::new(Some(visitor)),
+            ..self
+        }
+    }
+
+    /// Calls the visitor of the parse, if any
+    pub(crate) fn visit(&self, hook: impl FnOnce(&mut dyn Visitor)) {
+        if let Some(visitor) = self.visitor.borrow_mut().as_mut() {
+            hook(&mut **visitor);
+        }
+    }
+
+    pub fn decode_timestamps(&self) -> bool {
+        self.decode_timestamps
+    }
+
+    pub fn resolve_import_names(&self) -> bool {
+        self.resolve_import_names
+    }
+
+    /// Sets the file the offsets of the annotated fields are relative to, see `ParseOptions::annotations`
+    pub fn set_file(&self, file: parse::Input) {
+        if let Some(annotations) = &self.annotations {
+            annotations.set_file(file);
+        }
+    }
+
+    /// Parses a field labelled with `name` like `nom::error::context`,
+    /// recording its span when the fields are annotated
+    pub fn field<'b, 'a, O, F>(
+        &'b self,
+        name: &'static str,
+        parser: F,
+    ) -> impl FnMut(parse::Input<'a>) -> parse::Result<'a, O> + 'b
+    where
+        'a: 'b,
+        O: 'b,
+        F: nom::Parser<parse::Input<'a>, O, errors::PEError<parse::Input<'a>>> + 'b,
+    {
+        let mut parser = context(name, parser);
+        let annotations = &self.annotations;
+        move |i: parse::Input<'a>| {
+            let (rest, output) = parser(i)?;
+            if let Some(annotations) = annotations {
+                annotations.record(name, i, i.len() - rest.len());
+            }
+            Ok((rest, output))
+        }
+    }
+
+    /// The spans of the annotated fields in the order they were parsed, leaving none collected
+    pub fn take_annotations(&self) -> Vec<FieldSpan> {
+        self.annotations
+            .as_ref()
+            .map(Annotations::take)
+            .unwrap_or_default()
+    }
+
+    /// Reads a null-terminated name in the string mode of the parse,
+    /// failing with an error naming what is read when the name is not valid UTF-8 in `StringMode::Strict`
+    pub fn read_name<'a>(
+        &self,
+        input: parse::Input<'a>,
+        slice: &[u8],
+        what: &'static str,
+    ) -> Result<String, nom::Err<errors::PEError<parse::Input<'a>>>> {
+        utils::read_null_terminated_string_with_mode(slice, self.string_mode).ok_or_else(|| {
+            errors::PEError::from_string(input, format!("{} is not valid UTF-8", what))
+        })
+    }
+}
+
+impl Default for ParseContext<'_> {
+    fn default() -> Self {
+        Self::from_options(&ParseOptions::default())
+    }
+}
+
+impl fmt::Debug for ParseContext<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseContext")
+            .field("budget", &self.budget)
+            .field("string_mode", &self.string_mode)
+            .field("decode_timestamps", &self.decode_timestamps)
+            .field("resolve_import_names", &self.resolve_import_names)
+            .field("annotations", &self.annotations)
+            .field("has_visitor", &self.visitor.borrow().is_some())
+            .finish()
+    }
+}
+
+/// Counts the entries of a single table
+pub struct TableBudget<'t> {
+    budget: ParseBudget,
+    total: &'t Cell<usize>,
+    name: &'static str,
+    count: usize,
+}
+
+impl<'t> TableBudget<'t> {
+    /// Reserves entries of the table before collecting them.
+    /// Tables terminated by a null entry can reserve their entries one by one.
+    pub fn reserve<'a>(
+        &mut self,
+        input: parse::Input<'a>,
+        count: usize,
+    ) -> Result<(), nom::Err<errors::PEError<parse::Input<'a>>>> {
+        let budget = self.budget;
+        self.count = self.count.saturating_add(count);
+        if self.count > budget.max_entries_per_table {
+            return Err(errors::PEError::from_string(
+                input,
+                format!(
+                    "{} has more than {} entries",
+                    self.name, budget.max_entries_per_table
+                ),
+            ));
+        }
+        let total = self.total.get().saturating_add(count);
+        if total > budget.max_total_entries {
+            return Err(errors::PEError::from_string(
+                input,
+                format!(
+                    "the file has more than {} entries in total, exceeded by {}",
+                    budget.max_total_entries, self.name
+                ),
+            ));
+        }
+        self.total.set(total);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BudgetTracker, ParseBudget};
+
+    #[test]
+    fn budget_limits() {
+        let input: &[u8] = &[];
+        let tracker = BudgetTracker::new(ParseBudget {
+            max_entries_per_table: 3,
+            max_total_entries: 5,
+        });
+
+        let mut sections = tracker.table("section table");
+        assert!(sections.reserve(input, 2).is_ok());
+        assert!(sections.reserve(input, 1).is_ok());
+        assert!(sections.reserve(input, 1).is_err());
+
+        let mut exports = tracker.table("export address table");
+        assert!(exports.reserve(input, 2).is_ok());
+        assert!(exports.reserve(input, 1).is_err());
+    }
+}
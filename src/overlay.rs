@@ -0,0 +1,92 @@
+use crate::utils;
+use derive_more::Display;
+
+/// The format of the data appended after the end of the image, see `PE::overlay_kind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum OverlayKind {
+    Zip,
+    Cab,
+    SevenZip,
+    Rar,
+    Msi, // an OLE compound file, which is the container of MSI packages
+    Nsis,
+    InnoSetup,
+}
+
+impl OverlayKind {
+    /// Recognizes the format by the signature at the start of the overlay.
+    /// This is only a sniff of the magic bytes, the data following them is not validated.
+    pub fn sniff(overlay: &[u8]) -> Option<Self> {
+        const SIGNATURES: [(&[u8], OverlayKind); 7] = [
+            (b"PK\x03\x04", OverlayKind::Zip),
+            (b"MSCF", OverlayKind::Cab),
+            (b"7z\xbc\xaf\x27\x1c", OverlayKind::SevenZip),
+            (b"Rar!\x1a\x07", OverlayKind::Rar),
+            (b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1", OverlayKind::Msi),
+            (b"Inno Setup Setup Data", OverlayKind::InnoSetup),
+            (b"idska32\x1a", OverlayKind::InnoSetup),
+        ];
+        if let Some((_, kind)) = SIGNATURES
+            .iter()
+            .find(|(signature, _)| overlay.starts_with(signature))
+        {
+            return Some(*kind);
+        }
+        // the NSIS first header is the flags followed by 0xdeadbeef and "NullsoftInst"
+        match overlay.get(4..20) {
+            Some(b"\xef\xbe\xad\xdeNullsoftInst") => Some(OverlayKind::Nsis),
+            _ => None,
+        }
+    }
+}
+
+/// The SHA-256 of ".net core bundle", which the .NET host embeds right after the placeholder of the bundle header offset
+const DOTNET_BUNDLE_SIGNATURE: [u8; 32] = [
+    0x8b, 0x12, 0x02, 0xb9, 0x6a, 0x61, 0x20, 0x38, 0x72, 0x7b, 0x93, 0x02, 0x14, 0xd7, 0xa0, 0x32,
+    0x13, 0xf5, 0xb9, 0xe6, 0xef, 0xae, 0x33, 0x18, 0xee, 0x3b, 0x2d, 0xce, 0x24, 0xb3, 0x6a, 0xae,
+];
+
+/// Finds the file offset of the bundle header of a .NET single-file bundle, see `PE::dotnet_bundle_header_offset`.
+/// The host keeps the offset in the 8 bytes preceding the signature, which are zero until the bundler
+/// appends the files of the application and the bundle manifest to the host.
+pub fn find_dotnet_bundle_header(pe_file: &[u8]) -> Option<u64> {
+    let signature = pe_file
+        .windows(DOTNET_BUNDLE_SIGNATURE.len())
+        .rposition(|window| window == DOTNET_BUNDLE_SIGNATURE)?;
+    let header_offset = utils::read_u64_at(pe_file, signature.checked_sub(8)?)?;
+    Some(header_offset).filter(|offset| *offset != 0 && *offset < pe_file.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_dotnet_bundle_header, OverlayKind, DOTNET_BUNDLE_SIGNATURE};
+
+    #[test]
+    fn sniff() {
+        assert_eq!(
+            OverlayKind::sniff(b"PK\x03\x04\x14\x00"),
+            Some(OverlayKind::Zip)
+        );
+        assert_eq!(OverlayKind::sniff(b"MSCF\0\0\0\0"), Some(OverlayKind::Cab));
+        assert_eq!(
+            OverlayKind::sniff(b"\0\0\0\0\xef\xbe\xad\xdeNullsoftInst\0\0"),
+            Some(OverlayKind::Nsis)
+        );
+        assert_eq!(OverlayKind::sniff(b"PK"), None);
+        assert_eq!(OverlayKind::sniff(b""), None);
+    }
+
+    #[test]
+    fn dotnet_bundle_header() {
+        let mut pe_file = vec![0u8; 0x100];
+        pe_file[0x80..0x88].copy_from_slice(&0x180u64.to_le_bytes());
+        pe_file[0x88..0xa8].copy_from_slice(&DOTNET_BUNDLE_SIGNATURE);
+        // the bundle manifest is not there yet
+        assert_eq!(find_dotnet_bundle_header(&pe_file), None);
+        pe_file.resize(0x200, 0);
+        assert_eq!(find_dotnet_bundle_header(&pe_file), Some(0x180));
+        // a host which has not been bundled
+        pe_file[0x80..0x88].copy_from_slice(&[0; 8]);
+        assert_eq!(find_dotnet_bundle_header(&pe_file), None);
+    }
+}
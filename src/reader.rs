@@ -0,0 +1,178 @@
+use crate::exports::export_directory_table::ExportDirectoryTable;
+use crate::headers::dos::DosHeader;
+use crate::headers::nt::{DataDirectory, DirectoryEntry};
+use crate::headers::sections::{Section, Sections};
+use crate::headers::PEHeader;
+use crate::imports::Imports;
+use crate::options::{ParseContext, ParseOptions};
+use crate::resources::resource_directory_table::ResourceDirectoryTable;
+use crate::scanner::ScanError;
+use crate::utils;
+use std::io::{Read, Seek, SeekFrom};
+
+/// Parses the data directories of a file one at a time from a `Read + Seek` source,
+/// e.g. to read the resources of a huge executable without loading the whole file.
+///
+/// Only the headers are kept in memory, parsed once when the reader is created. Each directory is parsed from the raw data of the section
+/// containing it, so RVAs pointing into other sections, e.g. import names in another section, are not resolved.
+#[derive(Debug)]
+pub struct SeekingReader<R> {
+    reader: R,
+    options: ParseOptions,
+    headers: Vec<u8>,
+    // what the directories are read with, from the parsed headers
+    sections: Sections,
+    directories: Vec<DataDirectory>,
+    is_64bit: bool,
+}
+
+impl<R: Read + Seek> SeekingReader<R> {
+    /// Reads the headers, which are parsed to fail early on files which are not PE files
+    pub fn new(mut reader: R, options: ParseOptions) -> Result<Self, ScanError> {
+        let file_len = reader.seek(SeekFrom::End(0)).map_err(ScanError::Io)?;
+        // the DOS header gives the offset of the NT headers,
+        // whose file header gives the size of the optional header and the number of sections
+        let dos_header = read_at(&mut reader, 0, DosHeader::SIZE)?;
+        let lfanew = utils::read_u32_at(&dos_header, 0x3c).unwrap_or_default() as u64;
+        if lfanew > file_len {
+            return Err(ScanError::Parse(format!(
+                "lfanew {} is beyond the end of the file ({} bytes)",
+                lfanew, file_len
+            )));
+        }
+        let file_header = read_at(&mut reader, lfanew + 4, 20)?;
+        let num_of_sections = utils::read_u16_at(&file_header, 2).unwrap_or_default() as u64;
+        let size_of_optional_header =
+            utils::read_u16_at(&file_header, 16).unwrap_or_default() as u64;
+        // bounded by the file, which the headers can't extend beyond
+        let headers_size = lfanew
+            .checked_add(4 + 20)
+            .and_then(|size| size.checked_add(size_of_optional_header))
+            .and_then(|size| size.checked_add(num_of_sections * Section::SIZE as u64))
+            .map_or(file_len, |size| size.min(file_len));
+        let headers = read_at(
+            &mut reader,
+            0,
+            usize::try_from(headers_size).unwrap_or(usize::MAX),
+        )?;
+
+        let ctx = ParseContext::from_options(&options);
+        let (sections, directories, is_64bit) = {
+            let (_, header) = PEHeader::parse(&headers, &ctx).map_err(ScanError::from_parse)?;
+            let optional_header = &header.nt_header.optional_header;
+            (
+                header.sections.clone(),
+                optional_header.data_directories().iter().copied().collect(),
+                optional_header.is_64bit(),
+            )
+        };
+        Ok(Self {
+            reader,
+            options,
+            headers,
+            sections,
+            directories,
+            is_64bit,
+        })
+    }
+
+    /// Parses the headers kept in memory again, borrowing them.
+    /// The directories are read with the sections and the data directories parsed in `new`.
+    pub fn header(&self) -> Result<PEHeader<'_>, ScanError> {
+        let ctx = ParseContext::from_options(&self.options);
+        let (_, header) = PEHeader::parse(&self.headers, &ctx).map_err(ScanError::from_parse)?;
+        Ok(header)
+    }
+
+    /// Reads up to `size` bytes at the RVA, fewer when the section or the file ends before
+    pub fn read_rva(&mut self, rva: u32, size: usize) -> Result<Vec<u8>, ScanError> {
+        match self.sections.rva_to_offset(rva) {
+            Some(offset) => self.read_at(offset as u64, size),
+            None => Ok(vec![]),
+        }
+    }
+
+    pub fn imports(&mut self) -> Result<Option<Imports>, ScanError> {
+        let is_64bit = self.is_64bit;
+        let (directory, data, sections) = match self.directory_section(DirectoryEntry::Import)? {
+            Some(view) => view,
+            None => return Ok(None),
+        };
+        let ctx = ParseContext::from_options(&self.options);
+        let (_, imports) = Imports::parse(&data, directory, &sections, is_64bit, &ctx)
+            .map_err(ScanError::from_parse)?;
+        Ok(Some(imports))
+    }
+
+    pub fn export(&mut self) -> Result<Option<ExportDirectoryTable>, ScanError> {
+        let (directory, data, sections) = match self.directory_section(DirectoryEntry::Export)? {
+            Some(view) => view,
+            None => return Ok(None),
+        };
+        let ctx = ParseContext::from_options(&self.options);
+        let (_, export) = ExportDirectoryTable::parse(&data, directory, &sections, &ctx)
+            .map_err(ScanError::from_parse)?;
+        Ok(export)
+    }
+
+    /// The resource tree. The data of the resources is read with `read_rva`.
+    pub fn resources(&mut self) -> Result<Option<ResourceDirectoryTable>, ScanError> {
+        let (directory, data, sections) = match self.directory_section(DirectoryEntry::Resource)? {
+            Some(view) => view,
+            None => return Ok(None),
+        };
+        let ctx = ParseContext::from_options(&self.options);
+        let (_, resources) = ResourceDirectoryTable::parse(&data, directory, &sections, &ctx)
+            .map_err(ScanError::from_parse)?;
+        Ok(resources)
+    }
+
+    /// Reads the raw data of the section containing the directory when it is present,
+    /// with the section relocated to offset 0 so that the directory parsers can run on the data alone.
+    /// The whole section is read rather than the range of the directory, because the tables the directory
+    /// refers to, e.g. the import lookup tables and the names of the imports, are outside of its range,
+    /// so the memory used is bounded by the raw size of the section.
+    fn directory_section(
+        &mut self,
+        entry: DirectoryEntry,
+    ) -> Result<Option<(DataDirectory, Vec<u8>, Sections)>, ScanError> {
+        let directory = match self
+            .directories
+            .iter()
+            .find(|directory| directory.entry == entry)
+        {
+            Some(directory) if directory.is_present() => *directory,
+            _ => return Ok(None),
+        };
+        let section = match self.sections.find_by_address(directory.virtual_address) {
+            Some(section) => section.clone(),
+            None => return Ok(None),
+        };
+        let data = self.read_at(
+            section.ptr_to_raw_data as u64,
+            section.size_of_raw_data as usize,
+        )?;
+        let section = Section {
+            ptr_to_raw_data: 0,
+            ..section
+        };
+        Ok(Some((directory, data, Sections::new(vec![section], 0))))
+    }
+
+    /// Reads up to `size` bytes at the file offset, fewer at the end of the file
+    fn read_at(&mut self, offset: u64, size: usize) -> Result<Vec<u8>, ScanError> {
+        read_at(&mut self.reader, offset, size)
+    }
+}
+
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, size: usize) -> Result<Vec<u8>, ScanError> {
+    reader
+        .seek(SeekFrom::Start(offset))
+        .map_err(ScanError::Io)?;
+    let mut data = Vec::with_capacity(size.min(0x100000));
+    reader
+        .take(size as u64)
+        .read_to_end(&mut data)
+        .map_err(ScanError::Io)?;
+    Ok(data)
+}
@@ -0,0 +1,255 @@
+use crate::headers::nt::DataDirectory;
+use crate::headers::sections::Sections;
+use crate::options::ParseContext;
+use crate::{errors, parse};
+use derive_more::Display;
+use derive_try_from_primitive::TryFromPrimitive;
+use nom::error::context;
+use nom::number::complete::{le_u16, le_u32};
+use nom::sequence::tuple;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// The base relocation blocks pointed by the base relocation data directory,
+/// listing the addresses the loader patches when the image is not loaded at its preferred base
+#[derive(Debug)]
+pub struct BaseRelocationTable {
+    pub blocks: Vec<BaseRelocationBlock>,
+}
+
+impl BaseRelocationTable {
+    pub fn parse<'a>(
+        pe_file: parse::Input<'a>,
+        base_relocation_directory: DataDirectory,
+        sections: &Sections,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Option<Self>> {
+        sections.check_directory_mapped(
+            pe_file,
+            base_relocation_directory,
+            "base relocation directory",
+        )?;
+        let start = match sections.rva_to_offset(base_relocation_directory.virtual_address) {
+            Some(offset) if (offset as usize) < pe_file.len() => offset as usize,
+            _ => return Ok((pe_file, None)),
+        };
+        let end = start
+            .saturating_add(base_relocation_directory.size as usize)
+            .min(pe_file.len());
+        let blocks = Self::parse_blocks(&pe_file[start..end], ctx)?;
+        Ok((pe_file, Some(Self { blocks })))
+    }
+
+    /// Parses the blocks following one another up to the end of the data.
+    /// A block whose size can't even hold its own header terminates the table.
+    fn parse_blocks<'a>(
+        data: parse::Input<'a>,
+        ctx: &ParseContext,
+    ) -> Result<Vec<BaseRelocationBlock>, nom::Err<errors::PEError<parse::Input<'a>>>> {
+        let mut table_budget = ctx.budget().table("base relocation table");
+        let mut blocks = vec![];
+        let mut i = data;
+        while i.len() >= BaseRelocationBlock::HEADER_SIZE {
+            let (entries_input, (page_rva, size_of_block)) =
+                tuple((context("PageRVA", le_u32), context("SizeOfBlock", le_u32)))(i)?;
+            if (size_of_block as usize) < BaseRelocationBlock::HEADER_SIZE {
+                break;
+            }
+            // the size includes the header, and is capped by the data when it is overstated
+            let entries_size = (size_of_block as usize - BaseRelocationBlock::HEADER_SIZE)
+                .min(entries_input.len());
+            let count = entries_size / 2;
+            table_budget.reserve(entries_input, count)?;
+            let mut entries = Vec::with_capacity(count);
+            let mut entries_input = entries_input;
+            for _ in 0..count {
+                let (next, entry) = context("TypeOffset", le_u16)(entries_input)?;
+                entries_input = next;
+                let relocation = BaseRelocation {
+                    typ: (entry >> 12) as u8,
+                    offset: entry & 0x0fff,
+                };
+                // ABSOLUTE entries are no-ops padding the block to a 4-byte boundary
+                if relocation.relocation_type() != Some(BaseRelocationType::Absolute) {
+                    entries.push(relocation);
+                }
+            }
+            blocks.push(BaseRelocationBlock {
+                page_rva,
+                size_of_block,
+                entries,
+            });
+            i = &i[(size_of_block as usize).min(i.len())..];
+        }
+        Ok(blocks)
+    }
+
+    /// The RVAs of all the locations patched by the loader
+    pub fn rvas(&self) -> impl Iterator<Item = u32> + '_ {
+        self.blocks.iter().flat_map(|block| {
+            block
+                .entries
+                .iter()
+                .map(move |entry| entry.rva(block.page_rva))
+        })
+    }
+}
+
+/// A block of relocations within a 4 KB page
+#[derive(Debug)]
+pub struct BaseRelocationBlock {
+    pub page_rva: u32,
+    pub size_of_block: u32,           // including the 8-byte header
+    pub entries: Vec<BaseRelocation>, // without the ABSOLUTE padding entries
+}
+
+impl BaseRelocationBlock {
+    pub const HEADER_SIZE: usize = 8;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BaseRelocation {
+    pub typ: u8,     // the high 4 bits of the entry
+    pub offset: u16, // the low 12 bits of the entry, relative to the page
+}
+
+impl BaseRelocation {
+    pub fn relocation_type(&self) -> Option<BaseRelocationType> {
+        BaseRelocationType::try_from(self.typ).ok()
+    }
+
+    pub fn rva(&self, page_rva: u32) -> u32 {
+        page_rva.wrapping_add(self.offset as u32)
+    }
+}
+
+/// The types 5, 7, 8 and 9 have different meanings depending on the machine, e.g. MIPS, ARM or RISC-V
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, Display)]
+#[repr(u8)]
+pub enum BaseRelocationType {
+    Absolute = 0,
+    High = 1,
+    Low = 2,
+    HighLow = 3,
+    HighAdj = 4,
+    MachineSpecific5 = 5,
+    Reserved = 6,
+    MachineSpecific7 = 7,
+    MachineSpecific8 = 8,
+    MachineSpecific9 = 9,
+    Dir64 = 10,
+}
+
+/// Prints the relocations grouped by page like `dumpbin /RELOCATIONS`, with the offset of the entry
+/// within the page and its type. The alternate form prints a line per page only.
+impl fmt::Display for BaseRelocationTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "BaseRelocations")?;
+        for block in &self.blocks {
+            if f.alternate() {
+                writeln!(
+                    f,
+                    "  Page RVA: {:#x}, Entries: {}",
+                    block.page_rva,
+                    block.entries.len()
+                )?;
+                continue;
+            }
+            writeln!(
+                f,
+                "  Page RVA: {:#x}, SizeOfBlock: {:#x}, Entries: {}",
+                block.page_rva,
+                block.size_of_block,
+                block.entries.len()
+            )?;
+            for entry in &block.entries {
+                writeln!(f, "{}", entry)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for BaseRelocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.relocation_type() {
+            Some(relocation_type) => write!(
+                f,
+                "    {:03x}  {}",
+                self.offset,
+                relocation_type.to_string().to_uppercase()
+            ),
+            None => write!(f, "    {:03x}  UNKNOWN({})", self.offset, self.typ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BaseRelocationTable, BaseRelocationType};
+    use crate::options::ParseContext;
+
+    fn block(page_rva: u32, size_of_block: u32, entries: &[u16]) -> Vec<u8> {
+        let mut data = page_rva.to_le_bytes().to_vec();
+        data.extend_from_slice(&size_of_block.to_le_bytes());
+        for entry in entries {
+            data.extend_from_slice(&entry.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn padding_entries() {
+        // three entries padded with an ABSOLUTE entry to a 4-byte boundary
+        let mut data = block(0x1000, 16, &[0x3010, 0x3020, 0xa030, 0x0000]);
+        data.extend(block(0x2000, 12, &[0x3ff0, 0x0000]));
+        let blocks = BaseRelocationTable::parse_blocks(&data, &ParseContext::default()).unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].entries.len(), 3);
+        assert_eq!(
+            blocks[0].entries[2].relocation_type(),
+            Some(BaseRelocationType::Dir64)
+        );
+        assert_eq!(blocks[1].entries.len(), 1);
+        let table = BaseRelocationTable { blocks };
+        assert_eq!(
+            table.rvas().collect::<Vec<u32>>(),
+            vec![0x1010, 0x1020, 0x1030, 0x2ff0]
+        );
+        assert_eq!(
+            table.to_string(),
+            "BaseRelocations
+  Page RVA: 0x1000, SizeOfBlock: 0x10, Entries: 3
+    010  HIGHLOW
+    020  HIGHLOW
+    030  DIR64
+  Page RVA: 0x2000, SizeOfBlock: 0xc, Entries: 1
+    ff0  HIGHLOW
+"
+        );
+    }
+
+    #[test]
+    fn malformed_block_sizes() {
+        let ctx = ParseContext::default();
+        // a zero size terminates the table instead of looping on the same block
+        let mut data = block(0x1000, 12, &[0x3010, 0x3020]);
+        data.extend(block(0x2000, 0, &[0x3010, 0x3020]));
+        assert_eq!(
+            BaseRelocationTable::parse_blocks(&data, &ctx)
+                .unwrap()
+                .len(),
+            1
+        );
+        // so does a size smaller than the header
+        let data = block(0x1000, 4, &[0x3010, 0x3020]);
+        assert!(BaseRelocationTable::parse_blocks(&data, &ctx)
+            .unwrap()
+            .is_empty());
+        // an overstated size is capped by the data
+        let data = block(0x1000, 0x1000, &[0x3010, 0x3020]);
+        let blocks = BaseRelocationTable::parse_blocks(&data, &ctx).unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].entries.len(), 2);
+    }
+}
@@ -0,0 +1 @@
+pub mod base_relocation_table;
@@ -0,0 +1,84 @@
+pub const UTF_16LE: u32 = 1200;
+pub const UTF_16BE: u32 = 1201;
+pub const WINDOWS_1252: u32 = 1252;
+pub const US_ASCII: u32 = 20127;
+pub const ISO_8859_1: u32 = 28591;
+pub const UTF_8: u32 = 65001;
+
+/// Decodes text stored in the code page of a resource data entry.
+///
+/// # Arguments
+///
+/// * `data` - The raw bytes of the resource.
+/// * `code_page` - The `CodePage` of the resource data entry. Zero means the code page is not specified.
+///
+/// # Returns
+///
+/// The decoded text. A byte order mark decides the encoding when the code page is zero,
+/// and UTF-8 is assumed for code pages without a built-in decoder.
+/// Invalid sequences are replaced with the Unicode replacement character.
+pub fn decode(data: &[u8], code_page: u32) -> String {
+    match code_page {
+        UTF_16LE => decode_utf16(strip_bom(data, &[0xff, 0xfe]), u16::from_le_bytes),
+        UTF_16BE => decode_utf16(strip_bom(data, &[0xfe, 0xff]), u16::from_be_bytes),
+        WINDOWS_1252 => data.iter().map(|byte| windows_1252(*byte)).collect(),
+        ISO_8859_1 => data.iter().map(|byte| *byte as char).collect(),
+        US_ASCII => data
+            .iter()
+            .map(|byte| {
+                if byte.is_ascii() {
+                    *byte as char
+                } else {
+                    char::REPLACEMENT_CHARACTER
+                }
+            })
+            .collect(),
+        0 if data.starts_with(&[0xff, 0xfe]) => decode(data, UTF_16LE),
+        0 if data.starts_with(&[0xfe, 0xff]) => decode(data, UTF_16BE),
+        _ => String::from_utf8_lossy(strip_bom(data, &[0xef, 0xbb, 0xbf])).into_owned(),
+    }
+}
+
+fn strip_bom<'a>(data: &'a [u8], bom: &[u8]) -> &'a [u8] {
+    data.strip_prefix(bom).unwrap_or(data)
+}
+
+fn decode_utf16(data: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|unit| from_bytes([unit[0], unit[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Windows-1252 matches ISO-8859-1 except for 0x80-0x9F
+fn windows_1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '\u{20ac}', '\u{fffd}', '\u{201a}', '\u{0192}', '\u{201e}', '\u{2026}', '\u{2020}',
+        '\u{2021}', '\u{02c6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{fffd}',
+        '\u{017d}', '\u{fffd}', '\u{fffd}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}',
+        '\u{2022}', '\u{2013}', '\u{2014}', '\u{02dc}', '\u{2122}', '\u{0161}', '\u{203a}',
+        '\u{0153}', '\u{fffd}', '\u{017e}', '\u{0178}',
+    ];
+    match byte {
+        0x80..=0x9f => HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_code_pages() {
+        assert_eq!(decode(b"a\0b\0", UTF_16LE), "ab");
+        assert_eq!(decode(b"\xff\xfea\0b\0", UTF_16LE), "ab");
+        assert_eq!(decode(b"\0a\0b", UTF_16BE), "ab");
+        assert_eq!(decode(b"\x80 caf\xe9", WINDOWS_1252), "\u{20ac} caf\u{e9}");
+        assert_eq!(decode(b"caf\xe9", ISO_8859_1), "caf\u{e9}");
+        assert_eq!(decode("café".as_bytes(), UTF_8), "café");
+        assert_eq!(decode(b"\xef\xbb\xbfxml", 0), "xml");
+        assert_eq!(decode(b"\xff\xfex\0", 0), "x");
+    }
+}
@@ -0,0 +1,161 @@
+use crate::parse;
+use nom::error::context;
+use nom::number::complete::{le_u16, le_u32, le_u8};
+use nom::sequence::tuple;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// A GRPICONDIR, the data of an RT_GROUP_ICON or RT_GROUP_CURSOR resource listing the RT_ICON or RT_CURSOR
+/// resources that make up an icon or a cursor in different sizes and color depths
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconGroup {
+    pub typ: u16, // 1 for icons and 2 for cursors
+    pub entries: Vec<IconDirEntry>,
+}
+
+impl IconGroup {
+    pub const ICON: u16 = 1;
+    pub const CURSOR: u16 = 2;
+
+    /// Parses the header and as many entries as fit in the data when the count is overstated
+    pub fn parse(i: parse::Input) -> parse::Result<Self> {
+        let (mut i, (_, typ, count)) = tuple((
+            context("Reserved", le_u16),
+            context("Type", le_u16),
+            context("Count", le_u16),
+        ))(i)?;
+        let count = (count as usize).min(i.len() / IconDirEntry::SIZE);
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            let (next, entry) = if typ == Self::CURSOR {
+                IconDirEntry::parse_cursor(i)?
+            } else {
+                IconDirEntry::parse_icon(i)?
+            };
+            entries.push(entry);
+            i = next;
+        }
+        Ok((i, Self { typ, entries }))
+    }
+
+    pub fn is_cursor(&self) -> bool {
+        self.typ == Self::CURSOR
+    }
+}
+
+/// A GRPICONDIRENTRY describing one image of the group.
+/// Icons store the dimensions in a byte each where 0 means 256, cursors in 16-bit values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IconDirEntry {
+    pub width: u16,
+    pub height: u16,
+    pub color_count: u8, // 0 for cursors and for icons of 256 colors or more
+    pub planes: u16,
+    pub bit_count: u16,
+    pub bytes_in_res: u32,
+    pub id: u16, // the ID of the RT_ICON or RT_CURSOR resource holding the image
+}
+
+impl IconDirEntry {
+    pub const SIZE: usize = 14;
+
+    fn parse_icon(i: parse::Input) -> parse::Result<Self> {
+        let (i, (width, height, color_count, _, planes, bit_count, bytes_in_res, id)) =
+            tuple((
+                context("Width", le_u8),
+                context("Height", le_u8),
+                context("ColorCount", le_u8),
+                context("Reserved", le_u8),
+                context("Planes", le_u16),
+                context("BitCount", le_u16),
+                context("BytesInRes", le_u32),
+                context("ID", le_u16),
+            ))(i)?;
+        let dimension = |size: u8| if size == 0 { 256 } else { size as u16 };
+        Ok((
+            i,
+            Self {
+                width: dimension(width),
+                height: dimension(height),
+                color_count,
+                planes,
+                bit_count,
+                bytes_in_res,
+                id,
+            },
+        ))
+    }
+
+    fn parse_cursor(i: parse::Input) -> parse::Result<Self> {
+        let (i, (width, height, planes, bit_count, bytes_in_res, id)) = tuple((
+            context("Width", le_u16),
+            context("Height", le_u16),
+            context("Planes", le_u16),
+            context("BitCount", le_u16),
+            context("BytesInRes", le_u32),
+            context("ID", le_u16),
+        ))(i)?;
+        Ok((
+            i,
+            Self {
+                width,
+                height,
+                color_count: 0,
+                planes,
+                bit_count,
+                bytes_in_res,
+                id,
+            },
+        ))
+    }
+}
+
+impl fmt::Display for IconGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}",
+            if self.is_cursor() {
+                "CursorGroup"
+            } else {
+                "IconGroup"
+            }
+        )?;
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "  {}x{}, {} bpp, {} bytes, ID: {}",
+                entry.width, entry.height, entry.bit_count, entry.bytes_in_res, entry.id
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IconGroup;
+
+    #[test]
+    fn icon_group() {
+        let mut data = vec![0, 0, 1, 0, 3, 0];
+        // a 16x16 4-bit icon and a 256x256 32-bit icon
+        data.extend_from_slice(&[16, 16, 16, 0, 1, 0, 4, 0, 0x28, 0x01, 0, 0, 1, 0]);
+        data.extend_from_slice(&[0, 0, 0, 0, 1, 0, 32, 0, 0x00, 0x10, 0, 0, 2, 0]);
+        // the third entry is cut by the end of the data
+        data.extend_from_slice(&[32, 32]);
+        let (_, group) = IconGroup::parse(&data).unwrap();
+        assert!(!group.is_cursor());
+        assert_eq!(group.entries.len(), 2);
+        assert_eq!(
+            (group.entries[0].width, group.entries[0].bit_count),
+            (16, 4)
+        );
+        assert_eq!(group.entries[0].bytes_in_res, 0x128);
+        assert_eq!(
+            (group.entries[1].width, group.entries[1].height),
+            (256, 256)
+        );
+        assert_eq!(group.entries[1].id, 2);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod code_page;
+pub mod icon_group;
+pub mod resource_directory_table;
+pub mod version_info;
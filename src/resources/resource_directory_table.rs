@@ -0,0 +1,524 @@
+use crate::headers::nt::DataDirectory;
+use crate::headers::sections::Sections;
+use crate::options::ParseContext;
+use crate::resources::code_page;
+use crate::resources::icon_group::IconGroup;
+use crate::resources::version_info::VersionInfo;
+use crate::{errors, parse, utils};
+use chrono::{DateTime, Utc};
+use derive_more::Display;
+use derive_try_from_primitive::TryFromPrimitive;
+use nom::error::context;
+use nom::number::complete::{le_u16, le_u32};
+use nom::sequence::tuple;
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// A Resource Directory Table, the root of which is pointed by the resource data directory.
+/// The root is indexed by type, the second level by name and the third level by language.
+#[derive(Debug)]
+pub struct ResourceDirectoryTable {
+    pub characteristics: u32,
+    pub time_date_stamp: u32,
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub num_of_name_entries: u16,
+    pub num_of_id_entries: u16,
+    pub entries: Vec<ResourceDirectoryEntry>,
+}
+
+impl ResourceDirectoryTable {
+    // the tree normally has three levels, deeper trees are only found in crafted files
+    const MAX_DEPTH: usize = 8;
+    const HIGH_BIT: u32 = 0x80000000;
+
+    pub fn parse<'a>(
+        pe_file: parse::Input<'a>,
+        resource_directory: DataDirectory,
+        sections: &Sections,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Option<Self>> {
+        sections.check_directory_mapped(pe_file, resource_directory, "resource directory")?;
+        let offset = match sections.rva_to_offset(resource_directory.virtual_address) {
+            Some(offset) if (offset as usize) < pe_file.len() => offset as usize,
+            _ => return Ok((pe_file, None)),
+        };
+        // offsets in the tree are relative to the beginning of the root table
+        let resource_data = &pe_file[offset..];
+        let mut walk = Walk {
+            visited: HashSet::new(),
+            // each entry of a well-formed tree occupies its own 8 bytes
+            remaining_entries: resource_data.len() / 8,
+            ctx,
+        };
+        let (_, table) = Self::parse_table(resource_data, 0, 0, &mut walk)?;
+        Ok((pe_file, Some(table)))
+    }
+
+    /// The TimeDateStamp of the table, which may differ from the one of the file header.
+    /// None when the linker left it zero, as most do.
+    pub fn timestamp(&self) -> Option<DateTime<Utc>> {
+        if self.time_date_stamp == 0 {
+            return None;
+        }
+        DateTime::from_timestamp(self.time_date_stamp as i64, 0)
+    }
+
+    /// The major and minor version of the table
+    pub fn version(&self) -> (u16, u16) {
+        (self.major_version, self.minor_version)
+    }
+
+    /// The data entries of all the resources of the type, in the order of the tree
+    pub fn data_entries(&self, resource_type: ResourceType) -> Vec<&ResourceDataEntry> {
+        let mut data_entries = vec![];
+        for entry in &self.entries {
+            if entry.resource_type() == Some(resource_type) {
+                entry.node.collect_data_entries(&mut data_entries);
+            }
+        }
+        data_entries
+    }
+
+    /// The first data entry, i.e. of the first language, of the resource of the type named `name`.
+    /// Names are compared case-insensitively like `FindResource` does, since resource compilers store them uppercase.
+    pub fn find_named(
+        &self,
+        resource_type: ResourceType,
+        name: &str,
+    ) -> Option<&ResourceDataEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.resource_type() == Some(resource_type))
+            .filter_map(|entry| match &entry.node {
+                ResourceNode::Table(table) => Some(table),
+                ResourceNode::Data(_) => None,
+            })
+            .flat_map(|table| table.entries.iter())
+            .find(|entry| {
+                matches!(&entry.id, ResourceId::Name(entry_name) if entry_name.to_uppercase() == name.to_uppercase())
+            })
+            .and_then(|entry| {
+                let mut data_entries = vec![];
+                entry.node.collect_data_entries(&mut data_entries);
+                data_entries.first().copied()
+            })
+    }
+
+    /// Every resource of the tree with its type, name and language, in the order of the tree,
+    /// e.g. to dump all the resources. The data is empty when its RVA isn't mapped by a section.
+    /// Data entries found elsewhere than at the third level, or under a named language, are only found
+    /// in crafted files and are skipped.
+    pub fn iter_leaves<'s, 'a: 's>(
+        &'s self,
+        pe_file: &'a [u8],
+        sections: &'s Sections,
+    ) -> impl Iterator<Item = ResourceLeaf<'a>> + 's {
+        self.entries.iter().flat_map(move |type_entry| {
+            type_entry
+                .node
+                .entries()
+                .iter()
+                .flat_map(move |name_entry| {
+                    name_entry
+                        .node
+                        .entries()
+                        .iter()
+                        .filter_map(move |language_entry| {
+                            let (language, data_entry) =
+                                match (&language_entry.id, &language_entry.node) {
+                                    (ResourceId::Id(language), ResourceNode::Data(data_entry)) => {
+                                        (*language, data_entry)
+                                    }
+                                    _ => return None,
+                                };
+                            Some(ResourceLeaf {
+                                resource_type: type_entry.id.clone(),
+                                name: name_entry.id.clone(),
+                                language,
+                                code_page: data_entry.code_page,
+                                data_rva: data_entry.data_rva,
+                                data: data_entry.data(pe_file, sections).unwrap_or_default(),
+                            })
+                        })
+                })
+        })
+    }
+
+    /// The icon and cursor groups, listing the sizes of the icons and cursors and their resource IDs.
+    /// Groups whose data can't be read or parsed are skipped.
+    pub fn icon_groups(&self, pe_file: &[u8], sections: &Sections) -> Vec<IconGroup> {
+        let mut data_entries = self.data_entries(ResourceType::GroupIcon);
+        data_entries.extend(self.data_entries(ResourceType::GroupCursor));
+        data_entries
+            .into_iter()
+            .filter_map(|data_entry| data_entry.data(pe_file, sections))
+            .filter_map(|data| IconGroup::parse(data).ok())
+            .map(|(_, group)| group)
+            .collect()
+    }
+
+    /// The version information of the first RT_VERSION resource which parses, None when there is none
+    pub fn version_info(&self, pe_file: &[u8], sections: &Sections) -> Option<VersionInfo> {
+        self.data_entries(ResourceType::Version)
+            .into_iter()
+            .filter_map(|data_entry| data_entry.data(pe_file, sections))
+            .find_map(VersionInfo::parse)
+    }
+
+    fn parse_table<'a>(
+        resource_data: parse::Input<'a>,
+        offset: usize,
+        depth: usize,
+        walk: &mut Walk,
+    ) -> parse::Result<'a, Self> {
+        if offset >= resource_data.len() {
+            return Err(errors::PEError::offset_out_of_bounds(
+                resource_data,
+                offset,
+                resource_data.len(),
+                "resource directory table",
+            ));
+        }
+        walk.visited.insert(offset);
+
+        let (
+            mut i,
+            (
+                characteristics,
+                time_date_stamp,
+                major_version,
+                minor_version,
+                num_of_name_entries,
+                num_of_id_entries,
+            ),
+        ) = tuple((
+            context("Characteristics", le_u32),
+            context("TimeDateStamp", le_u32),
+            context("MajorVersion", le_u16),
+            context("MinorVersion", le_u16),
+            context("NumberOfNameEntries", le_u16),
+            context("NumberOfIdEntries", le_u16),
+        ))(&resource_data[offset..])?;
+
+        let num_of_entries =
+            (num_of_name_entries as usize + num_of_id_entries as usize).min(walk.remaining_entries);
+        walk.ctx
+            .budget()
+            .table("resource directory table")
+            .reserve(i, num_of_entries)?;
+        let mut entries = vec![];
+        for _ in 0..num_of_name_entries as usize + num_of_id_entries as usize {
+            if walk.remaining_entries == 0 {
+                break;
+            }
+            walk.remaining_entries -= 1;
+            let (next, (name_or_id, child_offset)) =
+                tuple((context("NameOrId", le_u32), context("Offset", le_u32)))(i)?;
+            i = next;
+
+            let id = if name_or_id & Self::HIGH_BIT != 0 {
+                let (_, name) =
+                    Self::read_name(resource_data, (name_or_id & !Self::HIGH_BIT) as usize)?;
+                ResourceId::Name(name)
+            } else {
+                ResourceId::Id(name_or_id)
+            };
+
+            let node = if child_offset & Self::HIGH_BIT != 0 {
+                let child_offset = (child_offset & !Self::HIGH_BIT) as usize;
+                // skip loops and shared subtrees which would make the tree explode
+                if depth + 1 >= Self::MAX_DEPTH || walk.visited.contains(&child_offset) {
+                    continue;
+                }
+                let (_, table) = Self::parse_table(resource_data, child_offset, depth + 1, walk)?;
+                ResourceNode::Table(table)
+            } else {
+                let (_, data_entry) =
+                    ResourceDataEntry::parse(resource_data, child_offset as usize)?;
+                ResourceNode::Data(data_entry)
+            };
+            entries.push(ResourceDirectoryEntry { id, node });
+        }
+
+        Ok((
+            i,
+            Self {
+                characteristics,
+                time_date_stamp,
+                major_version,
+                minor_version,
+                num_of_name_entries,
+                num_of_id_entries,
+                entries,
+            },
+        ))
+    }
+
+    fn fmt_entries(&self, f: &mut Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        for entry in &self.entries {
+            match (&entry.id, entry.resource_type()) {
+                (_, Some(resource_type)) if depth == 1 => write!(f, "{}{}", indent, resource_type)?,
+                (ResourceId::Name(name), _) => write!(f, "{}{}", indent, name)?,
+                (ResourceId::Id(id), _) => write!(f, "{}{}", indent, id)?,
+            }
+            match &entry.node {
+                ResourceNode::Table(table) => {
+                    writeln!(f)?;
+                    table.fmt_entries(f, depth + 1)?;
+                }
+                ResourceNode::Data(data) => writeln!(
+                    f,
+                    ": rva: {:#x}, size: {}, code page: {}",
+                    data.data_rva, data.size, data.code_page
+                )?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a name, which is a u16 length followed by as many UTF-16LE code units
+    fn read_name(resource_data: parse::Input, offset: usize) -> parse::Result<String> {
+        let i = resource_data.get(offset..).unwrap_or_default();
+        let (i, _) = context("NameLength", le_u16)(i)?;
+        Ok((
+            i,
+            utils::read_utf16_pstr(resource_data, offset).unwrap_or_default(),
+        ))
+    }
+}
+
+/// The state shared while walking the tree, which bounds the work done on crafted files
+struct Walk<'b, 'v> {
+    visited: HashSet<usize>, // offsets of the tables already parsed
+    remaining_entries: usize,
+    ctx: &'b ParseContext<'v>,
+}
+
+#[derive(Debug)]
+pub struct ResourceDirectoryEntry {
+    pub id: ResourceId,
+    pub node: ResourceNode,
+}
+
+impl ResourceDirectoryEntry {
+    /// The resource type when the entry is in the root table
+    pub fn resource_type(&self) -> Option<ResourceType> {
+        match self.id {
+            ResourceId::Id(id) => ResourceType::try_from(id).ok(),
+            ResourceId::Name(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceId {
+    Name(String),
+    Id(u32),
+}
+
+#[derive(Debug)]
+pub enum ResourceNode {
+    Table(ResourceDirectoryTable),
+    Data(ResourceDataEntry),
+}
+
+impl ResourceNode {
+    /// The entries of a table, none for a data entry
+    fn entries(&self) -> &[ResourceDirectoryEntry] {
+        match self {
+            Self::Table(table) => &table.entries,
+            Self::Data(_) => &[],
+        }
+    }
+
+    fn collect_data_entries<'a>(&'a self, data_entries: &mut Vec<&'a ResourceDataEntry>) {
+        match self {
+            Self::Table(table) => {
+                for entry in &table.entries {
+                    entry.node.collect_data_entries(data_entries);
+                }
+            }
+            Self::Data(data_entry) => data_entries.push(data_entry),
+        }
+    }
+}
+
+/// A resource with the path to its data entry in the tree, see `ResourceDirectoryTable::iter_leaves`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceLeaf<'a> {
+    pub resource_type: ResourceId, // a `ResourceType` for the predefined types
+    pub name: ResourceId,
+    pub language: u32, // the LANGID, 0 for language-neutral resources
+    pub code_page: u32,
+    pub data_rva: u32,
+    pub data: &'a [u8],
+}
+
+impl ResourceLeaf<'_> {
+    /// The predefined type of the resource, None for the types named by a string
+    pub fn predefined_type(&self) -> Option<ResourceType> {
+        match self.resource_type {
+            ResourceId::Id(id) => ResourceType::try_from(id).ok(),
+            ResourceId::Name(_) => None,
+        }
+    }
+}
+
+/// The leaf of the resource tree describing where the resource data is
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceDataEntry {
+    pub data_rva: u32,
+    pub size: u32,
+    pub code_page: u32, // used to decode text resources, 0 when unspecified
+    pub reserved: u32,
+}
+
+impl ResourceDataEntry {
+    fn parse(resource_data: parse::Input, offset: usize) -> parse::Result<Self> {
+        let i = resource_data.get(offset..).unwrap_or_default();
+        let (i, (data_rva, size, code_page, reserved)) = tuple((
+            context("DataRva", le_u32),
+            context("Size", le_u32),
+            context("Codepage", le_u32),
+            context("Reserved", le_u32),
+        ))(i)?;
+        Ok((
+            i,
+            Self {
+                data_rva,
+                size,
+                code_page,
+                reserved,
+            },
+        ))
+    }
+
+    /// The resource data, truncated when the file is shorter than the declared size
+    pub fn data<'a>(&self, pe_file: &'a [u8], sections: &Sections) -> Option<&'a [u8]> {
+        let start = sections.rva_to_offset(self.data_rva)? as usize;
+        let end = start.saturating_add(self.size as usize).min(pe_file.len());
+        pe_file.get(start..end)
+    }
+
+    /// Decodes the resource data as text in the code page of the entry,
+    /// e.g. for manifests, HTML or RCDATA holding text
+    pub fn decode_text(&self, pe_file: &[u8], sections: &Sections) -> Option<String> {
+        self.data(pe_file, sections)
+            .map(|data| code_page::decode(data, self.code_page))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, Display)]
+#[repr(u32)]
+pub enum ResourceType {
+    Cursor = 1,
+    Bitmap = 2,
+    Icon = 3,
+    Menu = 4,
+    Dialog = 5,
+    String = 6,
+    FontDir = 7,
+    Font = 8,
+    Accelerator = 9,
+    RcData = 10,
+    MessageTable = 11,
+    GroupCursor = 12,
+    GroupIcon = 14,
+    Version = 16,
+    DlgInclude = 17,
+    PlugPlay = 19,
+    Vxd = 20,
+    AniCursor = 21,
+    AniIcon = 22,
+    Html = 23,
+    Manifest = 24,
+}
+
+impl fmt::Display for ResourceDirectoryTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Resources")?;
+        self.fmt_entries(f, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ResourceDirectoryTable, ResourceId, ResourceLeaf, ResourceType};
+    use crate::headers::nt::{DataDirectory, DirectoryEntry};
+    use crate::headers::sections::{Section, Sections};
+    use crate::options::ParseContext;
+
+    fn table(num_of_name_entries: u16, num_of_id_entries: u16) -> Vec<u8> {
+        let mut table = vec![0u8; 12];
+        table.extend_from_slice(&num_of_name_entries.to_le_bytes());
+        table.extend_from_slice(&num_of_id_entries.to_le_bytes());
+        table
+    }
+
+    fn entry(name_or_id: u32, offset: u32) -> Vec<u8> {
+        let mut entry = name_or_id.to_le_bytes().to_vec();
+        entry.extend_from_slice(&offset.to_le_bytes());
+        entry
+    }
+
+    #[test]
+    fn named_resource() {
+        // RCDATA > "PAYLOAD" > 0x409, with the data at 0x70
+        let mut pe_file = table(0, 1);
+        pe_file.extend(entry(ResourceType::RcData as u32, 0x80000018));
+        pe_file.extend(table(1, 0));
+        pe_file.extend(entry(0x80000060, 0x80000030));
+        pe_file.extend(table(0, 1));
+        pe_file.extend(entry(0x409, 0x48));
+        for field in [0x70u32, 4, 1252, 0] {
+            pe_file.extend_from_slice(&field.to_le_bytes());
+        }
+        pe_file.resize(0x60, 0);
+        pe_file.extend_from_slice(&7u16.to_le_bytes());
+        for unit in "PAYLOAD".encode_utf16() {
+            pe_file.extend_from_slice(&unit.to_le_bytes());
+        }
+        pe_file.extend_from_slice(b"abcd");
+        pe_file.resize(0x100, 0);
+        let sections = Sections::new(vec![Section::for_test(".rsrc", 0, 0x100, 0, 0x100, 0)], 0);
+        let directory = DataDirectory {
+            entry: DirectoryEntry::Resource,
+            virtual_address: 0,
+            size: 0x100,
+        };
+        let (_, resources) =
+            ResourceDirectoryTable::parse(&pe_file, directory, &sections, &ParseContext::default())
+                .unwrap();
+        let resources = resources.unwrap();
+
+        let data_entry = resources
+            .find_named(ResourceType::RcData, "payload")
+            .unwrap();
+        assert_eq!(data_entry.data(&pe_file, &sections), Some(&b"abcd"[..]));
+        assert!(resources
+            .find_named(ResourceType::RcData, "OTHER")
+            .is_none());
+        assert!(resources
+            .find_named(ResourceType::Html, "PAYLOAD")
+            .is_none());
+
+        let leaves = resources
+            .iter_leaves(&pe_file, &sections)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            leaves,
+            vec![ResourceLeaf {
+                resource_type: ResourceId::Id(10),
+                name: ResourceId::Name("PAYLOAD".to_string()),
+                language: 0x409,
+                code_page: 1252,
+                data_rva: 0x70,
+                data: b"abcd",
+            }]
+        );
+        assert_eq!(leaves[0].predefined_type(), Some(ResourceType::RcData));
+    }
+}
@@ -0,0 +1,320 @@
+use crate::utils;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// A VS_VERSIONINFO, the data of an RT_VERSION resource holding the version of the file
+/// and strings like the company and the product name, per language and code page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionInfo {
+    pub fixed_file_info: Option<FixedFileInfo>,
+    pub string_tables: Vec<StringTable>,
+    translations: Vec<(u16, u16)>,
+}
+
+impl VersionInfo {
+    /// Parses the blocks of the data, skipping the blocks which don't fit in their parent.
+    /// None when the data is not a VS_VERSIONINFO.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let root = Block::parse(data)?;
+        if root.key != "VS_VERSION_INFO" {
+            return None;
+        }
+        let fixed_file_info = FixedFileInfo::parse(root.value);
+        let mut string_tables = vec![];
+        let mut translations = vec![];
+        for child in root.children() {
+            match child.key.as_str() {
+                "StringFileInfo" => string_tables.extend(child.children().map(|table| {
+                    StringTable {
+                        key: table.key.clone(),
+                        strings: table
+                            .children()
+                            .map(|string| (string.key.clone(), string.text()))
+                            .collect(),
+                    }
+                })),
+                "VarFileInfo" => {
+                    for var in child.children().filter(|var| var.key == "Translation") {
+                        // each translation is a language ID followed by a code page
+                        translations.extend(var.value.chunks_exact(4).filter_map(|translation| {
+                            Some((
+                                utils::read_u16_at(translation, 0)?,
+                                utils::read_u16_at(translation, 2)?,
+                            ))
+                        }));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(Self {
+            fixed_file_info,
+            string_tables,
+            translations,
+        })
+    }
+
+    /// The (language ID, code page) pairs of the VarFileInfo block, e.g. (0x0409, 1200) for U.S. English in UTF-16.
+    /// The string tables are keyed by these pairs as 8 hex digits, see `StringTable::translation`.
+    pub fn translations(&self) -> Vec<(u16, u16)> {
+        self.translations.clone()
+    }
+
+    /// The value of the string in the first string table having it, e.g. "ProductName"
+    pub fn string(&self, key: &str) -> Option<&str> {
+        self.string_tables
+            .iter()
+            .flat_map(|table| table.strings.iter())
+            .find(|(string_key, _)| string_key == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// The strings of a language and code page, keyed like "040904b0"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringTable {
+    pub key: String,
+    pub strings: Vec<(String, String)>,
+}
+
+impl StringTable {
+    /// The (language ID, code page) pair of the key, which matches one of `VersionInfo::translations`
+    pub fn translation(&self) -> Option<(u16, u16)> {
+        if self.key.len() != 8 {
+            return None;
+        }
+        let language = u16::from_str_radix(self.key.get(..4)?, 16).ok()?;
+        let code_page = u16::from_str_radix(self.key.get(4..)?, 16).ok()?;
+        Some((language, code_page))
+    }
+}
+
+/// A VS_FIXEDFILEINFO holding the binary version of the file and the product
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedFileInfo {
+    pub file_version: (u16, u16, u16, u16),
+    pub product_version: (u16, u16, u16, u16),
+    pub file_flags_mask: u32,
+    pub file_flags: u32,
+    pub file_os: u32,
+    pub file_type: u32,
+    pub file_subtype: u32,
+}
+
+impl FixedFileInfo {
+    const SIGNATURE: u32 = 0xfeef04bd;
+    pub const SIZE: usize = 52;
+
+    fn parse(value: &[u8]) -> Option<Self> {
+        let field = |index: usize| utils::read_u32_at(value, index * 4);
+        if field(0)? != Self::SIGNATURE {
+            return None;
+        }
+        let version =
+            |ms: u32, ls: u32| ((ms >> 16) as u16, ms as u16, (ls >> 16) as u16, ls as u16);
+        Some(Self {
+            file_version: version(field(2)?, field(3)?),
+            product_version: version(field(4)?, field(5)?),
+            file_flags_mask: field(6)?,
+            file_flags: field(7)?,
+            file_os: field(8)?,
+            file_type: field(9)?,
+            file_subtype: field(10)?,
+        })
+    }
+}
+
+/// The common layout of the blocks of a VS_VERSIONINFO: the length of the block, the length of the value,
+/// the type of the value (1 for text), a UTF-16 key, the value and the children blocks, each aligned on 4 bytes
+struct Block<'a> {
+    key: String,
+    value: &'a [u8],
+    children: &'a [u8],
+}
+
+impl<'a> Block<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        let length = (utils::read_u16_at(data, 0)? as usize).min(data.len());
+        let data = &data[..length];
+        let value_length = utils::read_u16_at(data, 2)? as usize;
+        let typ = utils::read_u16_at(data, 4)?;
+        let mut units = vec![];
+        let mut offset = 6;
+        loop {
+            match utils::read_u16_at(data, offset)? {
+                0 => break,
+                unit => units.push(unit),
+            }
+            offset += 2;
+        }
+        let key = String::from_utf16_lossy(&units);
+        let value_start = Self::align(offset + 2).min(length);
+        // the length of a text value is in characters
+        let value_size = if typ == 1 {
+            value_length * 2
+        } else {
+            value_length
+        };
+        let value_end = value_start.saturating_add(value_size).min(length);
+        let children_start = Self::align(value_end).min(length);
+        Some(Self {
+            key,
+            value: &data[value_start..value_end],
+            children: &data[children_start..],
+        })
+    }
+
+    fn children(&self) -> impl Iterator<Item = Block<'a>> {
+        let mut rest = self.children;
+        std::iter::from_fn(move || {
+            let length = utils::read_u16_at(rest, 0)? as usize;
+            if length == 0 {
+                return None;
+            }
+            let block = Block::parse(rest)?;
+            rest = rest.get(Self::align(length)..).unwrap_or_default();
+            Some(block)
+        })
+    }
+
+    /// The value as a null-terminated UTF-16 string
+    fn text(&self) -> String {
+        let units = self
+            .value
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .take_while(|unit| *unit != 0)
+            .collect::<Vec<u16>>();
+        String::from_utf16_lossy(&units)
+    }
+
+    fn align(offset: usize) -> usize {
+        (offset + 3) & !3
+    }
+}
+
+impl fmt::Display for VersionInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "VersionInfo:")?;
+        if let Some(fixed_file_info) = &self.fixed_file_info {
+            let (major, minor, build, revision) = fixed_file_info.file_version;
+            writeln!(
+                f,
+                "  FileVersion: {}.{}.{}.{}",
+                major, minor, build, revision
+            )?;
+        }
+        for table in &self.string_tables {
+            writeln!(f, "  StringTable {}:", table.key)?;
+            for (key, value) in &table.strings {
+                writeln!(f, "    {}: {}", key, value)?;
+            }
+        }
+        for (language, code_page) in &self.translations {
+            writeln!(
+                f,
+                "  Translation: language {:#06x}, code page {}",
+                language, code_page
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VersionInfo;
+
+    fn block(key: &str, value: &[u8], typ: u16, children: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0; 6];
+        for unit in key.encode_utf16().chain(Some(0)) {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+        data.resize((data.len() + 3) & !3, 0);
+        data.extend_from_slice(value);
+        for child in children {
+            data.resize((data.len() + 3) & !3, 0);
+            data.extend_from_slice(child);
+        }
+        let value_length = if typ == 1 {
+            value.len() / 2
+        } else {
+            value.len()
+        };
+        let length = data.len() as u16;
+        data[0..2].copy_from_slice(&length.to_le_bytes());
+        data[2..4].copy_from_slice(&(value_length as u16).to_le_bytes());
+        data[4..6].copy_from_slice(&typ.to_le_bytes());
+        data
+    }
+
+    fn text(value: &str) -> Vec<u8> {
+        value
+            .encode_utf16()
+            .chain(Some(0))
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn translations() {
+        let mut fixed = vec![0u8; 52];
+        fixed[0..4].copy_from_slice(&0xfeef04bdu32.to_le_bytes());
+        fixed[8..12].copy_from_slice(&(10u32 << 16).to_le_bytes());
+        fixed[12..16].copy_from_slice(&(19041u32 << 16 | 1).to_le_bytes());
+        let mut translations = vec![];
+        for (language, code_page) in [(0x0409u16, 1200u16), (0x0411, 932)] {
+            translations.extend_from_slice(&language.to_le_bytes());
+            translations.extend_from_slice(&code_page.to_le_bytes());
+        }
+        let data = block(
+            "VS_VERSION_INFO",
+            &fixed,
+            0,
+            &[
+                block(
+                    "StringFileInfo",
+                    &[],
+                    1,
+                    &[
+                        block(
+                            "040904b0",
+                            &[],
+                            1,
+                            &[block("ProductName", &text("Example"), 1, &[])],
+                        ),
+                        block(
+                            "041103a4",
+                            &[],
+                            1,
+                            &[block("ProductName", &text("Sample"), 1, &[])],
+                        ),
+                    ],
+                ),
+                block(
+                    "VarFileInfo",
+                    &[],
+                    1,
+                    &[block("Translation", &translations, 0, &[])],
+                ),
+            ],
+        );
+
+        let version_info = VersionInfo::parse(&data).unwrap();
+        assert_eq!(
+            version_info.fixed_file_info.unwrap().file_version,
+            (10, 0, 19041, 1)
+        );
+        assert_eq!(
+            version_info.translations(),
+            vec![(0x0409, 1200), (0x0411, 932)]
+        );
+        assert_eq!(version_info.string_tables.len(), 2);
+        assert_eq!(
+            version_info.string_tables[1].translation(),
+            Some((0x0411, 932))
+        );
+        assert_eq!(version_info.string_tables[1].strings[0].1, "Sample");
+        assert_eq!(version_info.string("ProductName"), Some("Example"));
+    }
+}
@@ -0,0 +1,79 @@
+use crate::errors::PEError;
+use crate::options::ParseOptions;
+use crate::parse;
+use crate::summary::Summary;
+use crate::PE;
+use std::fmt;
+use std::fmt::Formatter;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Summarizes many files in a row, reusing the same read buffer for every file
+/// so that scanning a large corpus doesn't allocate a buffer per file.
+#[derive(Debug, Default)]
+pub struct Scanner {
+    buffer: Vec<u8>,
+    options: ParseOptions,
+}
+
+impl Scanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self {
+            buffer: vec![],
+            options,
+        }
+    }
+
+    /// Reads and parses the file at the path, returning its summary.
+    /// The buffer keeps the capacity of the largest file scanned so far.
+    pub fn scan_path<P: AsRef<Path>>(&mut self, path: P) -> Result<Summary, ScanError> {
+        self.buffer.clear();
+        File::open(path)
+            .and_then(|mut file| file.read_to_end(&mut self.buffer))
+            .map_err(ScanError::Io)?;
+        self.scan_bytes()
+    }
+
+    fn scan_bytes(&self) -> Result<Summary, ScanError> {
+        let (_, pe) =
+            PE::parse_with_options(&self.buffer, &self.options).map_err(ScanError::from_parse)?;
+        Ok(pe.summary())
+    }
+}
+
+#[derive(Debug)]
+pub enum ScanError {
+    Io(std::io::Error),
+    Parse(String),
+}
+
+impl ScanError {
+    /// The parse error borrows the input, so only its messages are kept
+    pub(crate) fn from_parse(err: nom::Err<PEError<parse::Input>>) -> Self {
+        ScanError::Parse(match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) => err
+                .errors
+                .iter()
+                .map(|(_, kind)| kind.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+            nom::Err::Incomplete(_) => "incomplete input".to_string(),
+        })
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read the file: {}", err),
+            Self::Parse(message) => write!(f, "failed to parse the file: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ScanError {}
@@ -0,0 +1,53 @@
+use crate::headers::nt::Machine;
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::fmt::Formatter;
+
+/// The properties of a PE file commonly looked at when triaging many files,
+/// owning its data so that it outlives the parsed file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub machine: Machine,
+    pub is_64bit: bool,
+    pub is_dll: bool,
+    pub datetime: DateTime<Utc>,
+    pub num_of_sections: usize,
+    pub image_base: u64,
+    pub address_of_entry_point: u32,
+    pub num_of_imported_dlls: usize,
+    pub num_of_imported_functions: usize,
+    pub num_of_exported_functions: usize,
+    pub has_resources: bool,
+    pub has_tls: bool,
+    pub is_dotnet: bool,
+    pub pdb_symbol_path: Option<String>,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Summary")?;
+        writeln!(
+            f,
+            "  Machine: {}, 64-bit: {}, DLL: {}, .NET: {}, TimeDateStamp: {}",
+            self.machine, self.is_64bit, self.is_dll, self.is_dotnet, self.datetime
+        )?;
+        writeln!(
+            f,
+            "  Sections: {}, ImageBase: {:#x}, AddressOfEntryPoint: {:#x}",
+            self.num_of_sections, self.image_base, self.address_of_entry_point
+        )?;
+        writeln!(
+            f,
+            "  Imported DLLs: {}, Imported functions: {}, Exported functions: {}, Resources: {}, TLS: {}",
+            self.num_of_imported_dlls,
+            self.num_of_imported_functions,
+            self.num_of_exported_functions,
+            self.has_resources,
+            self.has_tls
+        )?;
+        if let Some(pdb_symbol_path) = &self.pdb_symbol_path {
+            writeln!(f, "  PDB: {}", pdb_symbol_path)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::fmt::Formatter;
+
+/// The timestamps recorded in the image, see `PE::timestamps`.
+/// The linker writes the same value to the file header and the export directory,
+/// while the delay and bound import timestamps are those of the imported DLLs at binding time,
+/// so that a tool rewriting the file header alone leaves the others telling a different story.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timestamps {
+    pub file_header: DateTime<Utc>,
+    pub export: Option<DateTime<Utc>>, // None without exports or with a zeroed timestamp
+    pub delay_imports: Vec<(String, DateTime<Utc>)>, // the bound delay-loaded DLLs
+    pub bound_imports: Vec<(String, DateTime<Utc>)>,
+}
+
+impl Timestamps {
+    /// Whether the export timestamp, when present, is the file header timestamp
+    pub fn export_matches(&self) -> bool {
+        self.export.is_none_or(|export| export == self.file_header)
+    }
+}
+
+impl fmt::Display for Timestamps {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Timestamps")?;
+        writeln!(f, "  FileHeader: {}", self.file_header)?;
+        if let Some(export) = self.export {
+            writeln!(f, "  Export: {}", export)?;
+        }
+        for (dll_name, datetime) in &self.delay_imports {
+            writeln!(f, "  Delay import {}: {}", dll_name, datetime)?;
+        }
+        for (dll_name, datetime) in &self.bound_imports {
+            writeln!(f, "  Bound import {}: {}", dll_name, datetime)?;
+        }
+        Ok(())
+    }
+}
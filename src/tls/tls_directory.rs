@@ -0,0 +1,184 @@
+use crate::headers::nt::DataDirectory;
+use crate::headers::sections::Sections;
+use crate::options::ParseContext;
+use crate::{errors, parse, utils};
+use nom::error::context;
+use nom::number::complete::{le_u32, le_u64};
+use nom::sequence::tuple;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// The TLS directory pointed by the TLS data directory.
+/// Unlike most structures of the image, its addresses are virtual addresses, not RVAs.
+#[derive(Debug)]
+pub struct TlsDirectory {
+    pub start_address_of_raw_data: u64,
+    pub end_address_of_raw_data: u64,
+    pub address_of_index: u64,
+    pub address_of_callbacks: u64, // VA of the null-terminated array of callback VAs
+    pub size_of_zero_fill: u32,
+    pub characteristics: u32,
+    pub callbacks: Vec<u64>, // the VAs of the callbacks, which run before the entry point
+}
+
+impl TlsDirectory {
+    pub fn parse<'a>(
+        pe_file: parse::Input<'a>,
+        tls_directory: DataDirectory,
+        sections: &Sections,
+        image_base: u64,
+        is_64bit: bool,
+        ctx: &ParseContext,
+    ) -> parse::Result<'a, Option<Self>> {
+        sections.check_directory_mapped(pe_file, tls_directory, "TLS directory")?;
+        let data = match sections
+            .rva_to_offset(tls_directory.virtual_address)
+            .and_then(|offset| pe_file.get(offset as usize..))
+        {
+            Some(data) => data,
+            None => return Ok((pe_file, None)),
+        };
+        let address = |i| {
+            if is_64bit {
+                context("Address", le_u64)(i)
+            } else {
+                context("Address", le_u32)(i).map(|(i, address)| (i, address as u64))
+            }
+        };
+        let (
+            _,
+            (
+                start_address_of_raw_data,
+                end_address_of_raw_data,
+                address_of_index,
+                address_of_callbacks,
+                size_of_zero_fill,
+                characteristics,
+            ),
+        ) = tuple((
+            context("StartAddressOfRawData", address),
+            context("EndAddressOfRawData", address),
+            context("AddressOfIndex", address),
+            context("AddressOfCallBacks", address),
+            context("SizeOfZeroFill", le_u32),
+            context("Characteristics", le_u32),
+        ))(data)?;
+
+        let callbacks = Self::read_callbacks(
+            pe_file,
+            address_of_callbacks,
+            sections,
+            image_base,
+            is_64bit,
+            ctx,
+        )?;
+        Ok((
+            pe_file,
+            Some(Self {
+                start_address_of_raw_data,
+                end_address_of_raw_data,
+                address_of_index,
+                address_of_callbacks,
+                size_of_zero_fill,
+                characteristics,
+                callbacks,
+            }),
+        ))
+    }
+
+    /// Reads the callback VAs up to the null terminator or the end of the file
+    fn read_callbacks<'a>(
+        pe_file: parse::Input<'a>,
+        address_of_callbacks: u64,
+        sections: &Sections,
+        image_base: u64,
+        is_64bit: bool,
+        ctx: &ParseContext,
+    ) -> Result<Vec<u64>, nom::Err<errors::PEError<parse::Input<'a>>>> {
+        let mut offset = match address_of_callbacks
+            .checked_sub(image_base)
+            .and_then(|rva| u32::try_from(rva).ok())
+            .and_then(|rva| sections.rva_to_offset(rva))
+        {
+            Some(offset) => offset as usize,
+            None => return Ok(vec![]),
+        };
+        let mut callbacks_budget = ctx.budget().table("TLS callbacks");
+        let mut callbacks = vec![];
+        loop {
+            let callback = if is_64bit {
+                utils::read_u64_at(pe_file, offset)
+            } else {
+                utils::read_u32_at(pe_file, offset).map(|callback| callback as u64)
+            };
+            match callback {
+                Some(callback) if callback != 0 => {
+                    callbacks_budget.reserve(pe_file, 1)?;
+                    callbacks.push(callback);
+                }
+                _ => break,
+            }
+            offset += if is_64bit { 8 } else { 4 };
+        }
+        Ok(callbacks)
+    }
+}
+
+impl fmt::Display for TlsDirectory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "TlsDirectory")?;
+        writeln!(
+            f,
+            "  RawData: {:#x}-{:#x}, AddressOfIndex: {:#x}, AddressOfCallBacks: {:#x}, SizeOfZeroFill: {}, Characteristics: {:#x}",
+            self.start_address_of_raw_data,
+            self.end_address_of_raw_data,
+            self.address_of_index,
+            self.address_of_callbacks,
+            self.size_of_zero_fill,
+            self.characteristics
+        )?;
+        for callback in &self.callbacks {
+            writeln!(f, "  Callback: {:#x}", callback)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TlsDirectory;
+    use crate::headers::nt::{DataDirectory, DirectoryEntry};
+    use crate::headers::sections::{Section, Sections};
+    use crate::options::ParseContext;
+
+    #[test]
+    fn callbacks() {
+        let image_base = 0x400000u32;
+        let mut pe_file = vec![];
+        for field in [0u32, 0, 0, image_base + 0x40, 0, 0] {
+            pe_file.extend_from_slice(&field.to_le_bytes());
+        }
+        pe_file.resize(0x40, 0);
+        for callback in [image_base + 0x1000, image_base + 0x1010, 0] {
+            pe_file.extend_from_slice(&callback.to_le_bytes());
+        }
+        let sections = Sections::new(vec![Section::for_test(".rdata", 0, 0x100, 0, 0x100, 0)], 0);
+        let directory = DataDirectory {
+            entry: DirectoryEntry::Tls,
+            virtual_address: 0,
+            size: 0x18,
+        };
+        let (_, tls) = TlsDirectory::parse(
+            &pe_file,
+            directory,
+            &sections,
+            image_base as u64,
+            false,
+            &ParseContext::default(),
+        )
+        .unwrap();
+        let tls = tls.unwrap();
+        assert_eq!(tls.address_of_callbacks, 0x400040);
+        assert_eq!(tls.callbacks, vec![0x401000, 0x401010]);
+    }
+}
@@ -1,19 +1,119 @@
+use crate::options::StringMode;
 use byteorder::{ByteOrder, LittleEndian};
+use std::fmt::Write;
 
-/// Reads a null-terminated string from a byte buffer, starting at a specified index.
+/// The default maximum length of the strings read from the file, so that a missing null terminator
+/// doesn't turn the rest of the file into a single string.
+pub const MAX_STRING_LENGTH: usize = 1024;
+
+/// The suffix appended to a string cut at `MAX_STRING_LENGTH`.
+pub const TRUNCATED_MARKER: &str = "...";
+
+/// Reads a null-terminated string from a byte buffer, reading at most `MAX_STRING_LENGTH` bytes.
 ///
 /// # Arguments
 ///
-/// * `buffer` - A byte slice from which to read data.
-/// * `start` - The index at which to begin reading.
+/// * `slice` - A byte slice starting at the string.
 ///
 /// # Returns
 ///
 /// A string read from the buffer. If the data in the buffer is not valid UTF-8,
 /// invalid sequences are replaced with the Unicode replacement character.
+/// A string without a null terminator within `MAX_STRING_LENGTH` bytes is suffixed with `TRUNCATED_MARKER`.
 pub fn read_null_terminated_string(slice: &[u8]) -> String {
-    let len = slice.iter().position(|&c| c == 0).unwrap_or(slice.len());
-    String::from_utf8_lossy(&slice[0..len]).into_owned()
+    match read_c_string(slice, MAX_STRING_LENGTH) {
+        (string, true) => string + TRUNCATED_MARKER,
+        (string, false) => string,
+    }
+}
+
+/// Reads a null-terminated string from a byte buffer, reading at most `max_len` bytes.
+///
+/// # Arguments
+///
+/// * `slice` - A byte slice starting at the string.
+/// * `max_len` - The maximum number of bytes of the string, excluding the null terminator.
+///
+/// # Returns
+///
+/// The string and whether it was truncated at `max_len` bytes.
+/// A string running up to the end of the buffer is not considered truncated.
+pub fn read_c_string(slice: &[u8], max_len: usize) -> (String, bool) {
+    let (bytes, truncated) = c_string_bytes(slice, max_len);
+    (String::from_utf8_lossy(bytes).into_owned(), truncated)
+}
+
+/// Finds the bytes of a null-terminated string, excluding the null terminator, reading at most `max_len` bytes.
+///
+/// # Returns
+///
+/// The bytes of the string and whether they were truncated at `max_len` bytes.
+pub fn c_string_bytes(slice: &[u8], max_len: usize) -> (&[u8], bool) {
+    let slice = &slice[..slice.len().min(max_len.saturating_add(1))];
+    match slice.iter().position(|&c| c == 0) {
+        Some(len) => (&slice[..len], false),
+        None if slice.len() > max_len => (&slice[..max_len], true),
+        None => (slice, false),
+    }
+}
+
+/// Reads a length-prefixed UTF-16LE string, like the names of the resource directory entries.
+///
+/// # Arguments
+///
+/// * `buffer` - The buffer holding the string.
+/// * `offset` - The offset of the u16 length, which counts UTF-16 code units and not bytes.
+///
+/// # Returns
+///
+/// The string, or `None` when the length can't be read. A string running beyond the end of the buffer
+/// is cut there, and unpaired surrogates are replaced with the Unicode replacement character.
+pub fn read_utf16_pstr(buffer: &[u8], offset: usize) -> Option<String> {
+    let length = read_u16_at(buffer, offset)? as usize;
+    let units = buffer[offset + 2..]
+        .chunks_exact(2)
+        .take(length)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .collect::<Vec<u16>>();
+    Some(String::from_utf16_lossy(&units))
+}
+
+/// Reads a null-terminated string like `read_null_terminated_string`, decoding it in the string mode.
+///
+/// # Returns
+///
+/// The string, or `None` when the string is not valid UTF-8 in `StringMode::Strict`.
+pub fn read_null_terminated_string_with_mode(slice: &[u8], mode: StringMode) -> Option<String> {
+    let (bytes, truncated) = c_string_bytes(slice, MAX_STRING_LENGTH);
+    let string = decode_string(bytes, mode)?;
+    Some(if truncated {
+        string + TRUNCATED_MARKER
+    } else {
+        string
+    })
+}
+
+/// Decodes bytes read from the file as UTF-8 in the string mode.
+///
+/// # Returns
+///
+/// The string, or `None` when the bytes are not valid UTF-8 in `StringMode::Strict`.
+/// In `StringMode::RawBytes`, each byte of an invalid sequence is escaped as `\xNN` so that the bytes can be recovered.
+pub fn decode_string(bytes: &[u8], mode: StringMode) -> Option<String> {
+    match mode {
+        StringMode::Lossy => Some(String::from_utf8_lossy(bytes).into_owned()),
+        StringMode::Strict => std::str::from_utf8(bytes).ok().map(String::from),
+        StringMode::RawBytes => {
+            let mut string = String::with_capacity(bytes.len());
+            for chunk in bytes.utf8_chunks() {
+                string.push_str(chunk.valid());
+                for byte in chunk.invalid() {
+                    let _ = write!(string, "\\x{:02x}", byte);
+                }
+            }
+            Some(string)
+        }
+    }
 }
 
 /// Reads an array of u32 values from a byte buffer, starting at a specified index.
@@ -40,3 +140,161 @@ pub fn read_array(buffer: &[u8], start: usize, count: usize) -> Vec<u32> {
     }
     result
 }
+
+/// Reads an array of u16 values from a byte buffer, starting at a specified index.
+///
+/// # Arguments
+///
+/// * `buffer` - A byte slice from which to read data.
+/// * `start` - The index at which to begin reading.
+/// * `count` - The number of u16 values to read.
+///
+/// # Returns
+///
+/// A vector of u16 values read from the buffer.
+pub fn read_u16_array(buffer: &[u8], start: usize, count: usize) -> Vec<u16> {
+    let mut result = Vec::with_capacity(count);
+    for i in 0..count {
+        let offset = start + i * 2;
+        if offset + 2 <= buffer.len() {
+            let value = LittleEndian::read_u16(&buffer[offset..offset + 2]);
+            result.push(value);
+        } else {
+            break;
+        }
+    }
+    result
+}
+
+/// Rounds a value up to the next multiple of the alignment.
+///
+/// # Arguments
+///
+/// * `value` - The value to round up.
+/// * `alignment` - The alignment, e.g. `file_alignment` or `section_of_alignment`.
+///
+/// # Returns
+///
+/// The aligned value. The value is returned as is when the alignment is zero.
+pub fn align_up(value: u32, alignment: u32) -> u32 {
+    if alignment == 0 {
+        return value;
+    }
+    match value % alignment {
+        0 => value,
+        remainder => value.saturating_add(alignment - remainder),
+    }
+}
+
+/// Computes the checksum of an image like `CheckSumMappedFile`, which the loader verifies for drivers
+/// and the DLLs loaded at boot.
+///
+/// # Arguments
+///
+/// * `data` - The whole file.
+/// * `checksum_offset` - The file offset of the `CheckSum` field of the optional header, which is skipped.
+///
+/// # Returns
+///
+/// The 16-bit one's complement sum of the little-endian words of the file, plus the file size.
+pub fn pe_checksum(data: &[u8], checksum_offset: usize) -> u32 {
+    let mut sum = 0u64;
+    for (index, word) in data.chunks(2).enumerate() {
+        let offset = index * 2;
+        if offset >= checksum_offset && offset < checksum_offset + 4 {
+            continue;
+        }
+        // an odd trailing byte is padded with zero
+        sum += u16::from_le_bytes([word[0], word.get(1).copied().unwrap_or(0)]) as u64;
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    (sum as u32).wrapping_add(data.len() as u32)
+}
+
+/// Reads a little-endian u16 value at the offset of a byte buffer.
+///
+/// # Returns
+///
+/// The value, or `None` when the buffer is too short.
+pub fn read_u16_at(buffer: &[u8], offset: usize) -> Option<u16> {
+    buffer
+        .get(offset..offset.checked_add(2)?)
+        .map(LittleEndian::read_u16)
+}
+
+/// Reads a little-endian u32 value at the offset of a byte buffer.
+///
+/// # Returns
+///
+/// The value, or `None` when the buffer is too short.
+pub fn read_u32_at(buffer: &[u8], offset: usize) -> Option<u32> {
+    buffer
+        .get(offset..offset.checked_add(4)?)
+        .map(LittleEndian::read_u32)
+}
+
+/// Reads a little-endian u64 value at the offset of a byte buffer.
+///
+/// # Returns
+///
+/// The value, or `None` when the buffer is too short.
+pub fn read_u64_at(buffer: &[u8], offset: usize) -> Option<u64> {
+    buffer
+        .get(offset..offset.checked_add(8)?)
+        .map(LittleEndian::read_u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_string, read_c_string, read_null_terminated_string, read_utf16_pstr,
+        MAX_STRING_LENGTH,
+    };
+    use crate::options::StringMode;
+
+    #[test]
+    fn c_string_length_cap() {
+        assert_eq!(read_c_string(b"abc\0def", 8), ("abc".to_string(), false));
+        assert_eq!(read_c_string(b"abc\0def", 3), ("abc".to_string(), false));
+        assert_eq!(read_c_string(b"abcdef", 3), ("abc".to_string(), true));
+        assert_eq!(read_c_string(b"abc", 3), ("abc".to_string(), false));
+        assert_eq!(read_c_string(b"", 3), ("".to_string(), false));
+
+        let long = vec![b'a'; MAX_STRING_LENGTH * 4];
+        let string = read_null_terminated_string(&long);
+        assert_eq!(string.len(), MAX_STRING_LENGTH + 3);
+        assert!(string.ends_with("..."));
+    }
+
+    #[test]
+    fn utf16_pstr() {
+        let mut buffer = vec![0xff];
+        buffer.extend_from_slice(&3u16.to_le_bytes());
+        for unit in "RCDATA".encode_utf16() {
+            buffer.extend_from_slice(&unit.to_le_bytes());
+        }
+        // the length is 3 units, i.e. 6 bytes
+        assert_eq!(read_utf16_pstr(&buffer, 1).as_deref(), Some("RCD"));
+        buffer[1] = 10;
+        assert_eq!(read_utf16_pstr(&buffer, 1).as_deref(), Some("RCDATA"));
+        assert_eq!(read_utf16_pstr(&buffer, buffer.len() - 1), None);
+    }
+
+    #[test]
+    fn string_modes() {
+        let invalid = b"Get\xffProc";
+        assert_eq!(
+            decode_string(invalid, StringMode::Lossy).as_deref(),
+            Some("Get\u{fffd}Proc")
+        );
+        assert_eq!(decode_string(invalid, StringMode::Strict), None);
+        assert_eq!(
+            decode_string(invalid, StringMode::RawBytes).as_deref(),
+            Some("Get\\xffProc")
+        );
+        assert_eq!(
+            decode_string(b"GetProc", StringMode::Strict).as_deref(),
+            Some("GetProc")
+        );
+    }
+}
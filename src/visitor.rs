@@ -0,0 +1,23 @@
+use crate::exports::export_directory_table::ExportedFunction;
+use crate::headers::sections::Section;
+use crate::imports::import_directory_table::ImportEntry;
+
+/// Hooks called by the parsers of `PE::parse_with_visitor` as each part of the file is produced,
+/// e.g. to collect statistics over a corpus without walking the parsed structures afterwards.
+/// Every hook does nothing by default, so a visitor only implements the hooks it needs.
+pub trait Visitor {
+    fn on_section(&mut self, _section: &Section) {}
+
+    /// Called for each import descriptor, with an empty name when the descriptor has none
+    fn on_import_dll(&mut self, _dll_name: &str) {}
+
+    fn on_import(&mut self, _dll_name: &str, _entry: &ImportEntry) {}
+
+    fn on_export(&mut self, _function: &ExportedFunction) {}
+}
+
+/// The visitor of `PE::parse_with_options`, which ignores everything
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopVisitor;
+
+impl Visitor for NoopVisitor {}
@@ -0,0 +1,259 @@
+use crate::headers::dos::DosHeader;
+use crate::headers::nt::{DllCharacteristic, FileCharacteristic, Machine, Subsystem};
+use crate::headers::sections::{
+    Section, IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_READ,
+};
+use crate::utils;
+
+const NUMBER_OF_DIRECTORIES: u32 = 16;
+
+/// The DOS program printing the usual message and exiting, as linkers emit it after the DOS header
+const DOS_STUB: &[u8] = b"\x0e\x1f\xba\x0e\x00\xb4\x09\xcd\x21\xb8\x01\x4c\xcd\x21\
+This program cannot be run in DOS mode.\r\r\n$";
+
+/// Builds a minimal PE image holding the given code in a single `.text` section,
+/// e.g. to generate test fixtures or tiny programs:
+///
+/// ```
+/// use peparser::headers::nt::Machine;
+/// use peparser::writer::PeWriter;
+///
+/// // xor eax, eax; ret
+/// let image = PeWriter::new(Machine::Amd64).code(vec![0x31, 0xc0, 0xc3]).build();
+/// let (_, pe) = peparser::PE::parse(&image).unwrap();
+/// assert_eq!(pe.sections().len(), 1);
+/// ```
+///
+/// The image has no imports and no base relocations, so the code must not call other modules
+/// and the image is always loaded at `image_base`. PE32+ is written for 64-bit machines.
+#[derive(Debug, Clone)]
+pub struct PeWriter {
+    machine: Machine,
+    code: Vec<u8>,
+    entry_point: u32, // the offset of the entry point in the code
+    image_base: u64,
+    subsystem: Subsystem,
+    file_alignment: u32,
+    section_alignment: u32,
+    dll: bool,
+}
+
+impl PeWriter {
+    pub fn new(machine: Machine) -> Self {
+        let image_base = if Self::is_64bit_machine(machine) {
+            0x140000000
+        } else {
+            0x400000
+        };
+        Self {
+            machine,
+            code: vec![],
+            entry_point: 0,
+            image_base,
+            subsystem: Subsystem::WindowsCui,
+            file_alignment: 0x200,
+            section_alignment: 0x1000,
+            dll: false,
+        }
+    }
+
+    pub fn code(mut self, code: Vec<u8>) -> Self {
+        self.code = code;
+        self
+    }
+
+    /// The offset of the entry point from the start of the code, 0 by default
+    pub fn entry_point(mut self, offset: u32) -> Self {
+        self.entry_point = offset;
+        self
+    }
+
+    pub fn image_base(mut self, image_base: u64) -> Self {
+        self.image_base = image_base;
+        self
+    }
+
+    pub fn subsystem(mut self, subsystem: Subsystem) -> Self {
+        self.subsystem = subsystem;
+        self
+    }
+
+    /// Both alignments are powers of 2, and equal when the section alignment is below the page size
+    pub fn alignments(mut self, file_alignment: u32, section_alignment: u32) -> Self {
+        self.file_alignment = file_alignment;
+        self.section_alignment = section_alignment;
+        self
+    }
+
+    pub fn dll(mut self, dll: bool) -> Self {
+        self.dll = dll;
+        self
+    }
+
+    fn is_64bit_machine(machine: Machine) -> bool {
+        matches!(machine, Machine::Amd64 | Machine::Arm64 | Machine::Ia64)
+    }
+
+    /// Writes the image with the sizes derived from the alignments and a valid checksum
+    pub fn build(&self) -> Vec<u8> {
+        let is_64bit = Self::is_64bit_machine(self.machine);
+        let nt_header = DosHeader::SIZE + DOS_STUB.len().next_multiple_of(8);
+        let optional_header = nt_header + 24;
+        let size_of_optional_header: u16 = if is_64bit { 0xf0 } else { 0xe0 };
+        let section_table = optional_header + size_of_optional_header as usize;
+        let size_of_headers =
+            utils::align_up((section_table + Section::SIZE) as u32, self.file_alignment);
+        let text_rva = utils::align_up(size_of_headers, self.section_alignment);
+        let code_size = self.code.len() as u32;
+        let raw_size = utils::align_up(code_size, self.file_alignment);
+        let size_of_image = utils::align_up(text_rva + code_size.max(1), self.section_alignment);
+
+        let mut image = vec![0u8; (size_of_headers + raw_size) as usize];
+        let mut writer = Writer(&mut image);
+        // DOS header
+        writer.bytes(0, b"MZ");
+        for (offset, value) in [
+            (2, 0x90u16),
+            (4, 3),
+            (8, 4),
+            (12, 0xffff),
+            (16, 0xb8),
+            (24, 0x40),
+        ] {
+            writer.u16(offset, value);
+        }
+        writer.u32(0x3c, nt_header as u32);
+        writer.bytes(DosHeader::SIZE, DOS_STUB);
+        // file header
+        writer.bytes(nt_header, b"PE\0\0");
+        writer.u16(nt_header + 4, self.machine as u16);
+        writer.u16(nt_header + 6, 1);
+        writer.u16(nt_header + 20, size_of_optional_header);
+        let mut characteristics =
+            FileCharacteristic::ExecutableImage as u16 | FileCharacteristic::RelocsStripped as u16;
+        characteristics |= if is_64bit {
+            FileCharacteristic::LargeAddressAware as u16
+        } else {
+            FileCharacteristic::Machine32Bit as u16
+        };
+        if self.dll {
+            characteristics |= FileCharacteristic::Dll as u16;
+        }
+        writer.u16(nt_header + 22, characteristics);
+        // optional header, whose fields after base_of_code are shifted by the 8-byte image base of PE32+
+        let o = optional_header;
+        writer.u16(o, if is_64bit { 0x20b } else { 0x10b });
+        writer.bytes(o + 2, &[14, 0]);
+        writer.u32(o + 4, raw_size);
+        writer.u32(o + 16, text_rva + self.entry_point);
+        writer.u32(o + 20, text_rva);
+        if is_64bit {
+            writer.u64(o + 24, self.image_base);
+        } else {
+            writer.u32(o + 28, self.image_base as u32);
+        }
+        writer.u32(o + 32, self.section_alignment);
+        writer.u32(o + 36, self.file_alignment);
+        writer.u16(o + 40, 6);
+        writer.u16(o + 48, 6);
+        writer.u32(o + 56, size_of_image);
+        writer.u32(o + 60, size_of_headers);
+        writer.u16(o + 68, self.subsystem as u16);
+        writer.u16(o + 70, DllCharacteristic::NxCompat as u16);
+        let sizes = o + 72;
+        for (index, size) in [0x100000u64, 0x1000, 0x100000, 0x1000]
+            .into_iter()
+            .enumerate()
+        {
+            if is_64bit {
+                writer.u64(sizes + index * 8, size);
+            } else {
+                writer.u32(sizes + index * 4, size as u32);
+            }
+        }
+        let number_of_rva_and_sizes = if is_64bit { o + 108 } else { o + 92 };
+        writer.u32(number_of_rva_and_sizes, NUMBER_OF_DIRECTORIES);
+        // section table
+        writer.bytes(section_table, b".text");
+        writer.u32(section_table + 8, code_size);
+        writer.u32(section_table + 12, text_rva);
+        writer.u32(section_table + 16, raw_size);
+        writer.u32(section_table + 20, size_of_headers);
+        writer.u32(
+            section_table + 36,
+            IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_MEM_READ,
+        );
+        writer.bytes(size_of_headers as usize, &self.code);
+
+        let check_sum = utils::pe_checksum(&image, o + 64);
+        Writer(&mut image).u32(o + 64, check_sum);
+        image
+    }
+}
+
+/// Writes little-endian values at offsets of a zeroed image
+struct Writer<'a>(&'a mut [u8]);
+
+impl Writer<'_> {
+    fn bytes(&mut self, offset: usize, bytes: &[u8]) {
+        self.0[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    fn u16(&mut self, offset: usize, value: u16) {
+        self.bytes(offset, &value.to_le_bytes());
+    }
+
+    fn u32(&mut self, offset: usize, value: u32) {
+        self.bytes(offset, &value.to_le_bytes());
+    }
+
+    fn u64(&mut self, offset: usize, value: u64) {
+        self.bytes(offset, &value.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PeWriter;
+    use crate::headers::nt::{Machine, Subsystem};
+    use crate::PE;
+
+    #[test]
+    fn round_trip() {
+        for machine in [Machine::I386, Machine::Amd64] {
+            let image = PeWriter::new(machine)
+                .code(vec![0x90, 0x31, 0xc0, 0xc3])
+                .entry_point(1)
+                .subsystem(Subsystem::WindowsGui)
+                .build();
+            let (_, pe) = PE::parse(&image).unwrap();
+            assert_eq!(pe.machine(), machine);
+            assert_eq!(pe.is_64bit(), machine == Machine::Amd64);
+            assert_eq!(pe.subsystem(), Some(Subsystem::WindowsGui));
+            assert!(pe.size_of_image_matches());
+            assert!(pe.headers_size_matches());
+            assert!(pe.checksum_valid());
+            let entry_point = pe.entry_point_section().unwrap();
+            assert_eq!(entry_point.name, ".text");
+            assert_eq!(pe.entry_point_section_offset(), Some(1));
+            assert_eq!(
+                &entry_point.raw_data(&image)[..4],
+                &[0x90, 0x31, 0xc0, 0xc3]
+            );
+        }
+    }
+
+    #[test]
+    fn low_alignment_dll() {
+        let image = PeWriter::new(Machine::I386)
+            .code(vec![0xc3; 0x30])
+            .alignments(0x200, 0x200)
+            .dll(true)
+            .build();
+        let (_, pe) = PE::parse(&image).unwrap();
+        assert!(pe.summary().is_dll);
+        assert!(pe.is_flat_mapped());
+        assert_eq!(pe.header.nt_header.optional_header.size_of_image(), 0x400);
+        assert_eq!(image.len(), 0x400);
+    }
+}
@@ -0,0 +1,72 @@
+//! Images whose file alignment equals their section alignment, so that the file offsets equal the RVAs
+
+use peparser::PE;
+
+fn put_u16(data: &mut [u8], offset: usize, value: u16) {
+    data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// A PE32 executable aligned on 0x200 bytes both in the file and in memory,
+/// with a .text section whose virtual size is left zero followed by a .data section
+fn low_alignment_image(data_raw_offset: u32) -> Vec<u8> {
+    let mut pe_file = vec![0u8; 0x600];
+    pe_file[..2].copy_from_slice(b"MZ");
+    put_u32(&mut pe_file, 0x3c, 0x40);
+    pe_file[0x40..0x44].copy_from_slice(b"PE\0\0");
+    let file_header = 0x44;
+    put_u16(&mut pe_file, file_header, 0x14c);
+    put_u16(&mut pe_file, file_header + 2, 2);
+    put_u16(&mut pe_file, file_header + 16, 0xe0);
+    put_u16(&mut pe_file, file_header + 18, 0x102);
+    let optional_header = file_header + 20;
+    put_u16(&mut pe_file, optional_header, 0x10b);
+    put_u32(&mut pe_file, optional_header + 16, 0x210);
+    put_u32(&mut pe_file, optional_header + 28, 0x400000);
+    put_u32(&mut pe_file, optional_header + 32, 0x200);
+    put_u32(&mut pe_file, optional_header + 36, 0x200);
+    put_u32(&mut pe_file, optional_header + 56, 0x600);
+    put_u32(&mut pe_file, optional_header + 60, 0x200);
+    put_u16(&mut pe_file, optional_header + 68, 3);
+    put_u32(&mut pe_file, optional_header + 92, 16);
+    let section_table = optional_header + 0xe0;
+    for (index, (name, vir_size, rva, raw, characteristics)) in [
+        (b".text", 0, 0x200, 0x200, 0x60000020u32),
+        (b".data", 0x200, 0x400, data_raw_offset, 0xc0000040),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let section = section_table + index * 40;
+        pe_file[section..section + 5].copy_from_slice(name);
+        put_u32(&mut pe_file, section + 8, vir_size);
+        put_u32(&mut pe_file, section + 12, rva);
+        put_u32(&mut pe_file, section + 16, 0x200);
+        put_u32(&mut pe_file, section + 20, raw);
+        put_u32(&mut pe_file, section + 36, characteristics);
+    }
+    pe_file
+}
+
+#[test]
+fn flat_mapped() {
+    let pe_file = low_alignment_image(0x400);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(pe.is_flat_mapped());
+    assert_eq!(pe.entry_point_section().unwrap().name, ".text");
+    for rva in [0x200, 0x3ff, 0x400, 0x5ff] {
+        assert_eq!(pe.sections().rva_to_offset(rva), Some(rva));
+    }
+}
+
+#[test]
+fn same_alignment_but_moved_section() {
+    // the .data section shares the .text raw data, so its offsets differ from its RVAs
+    let pe_file = low_alignment_image(0x200);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(!pe.is_flat_mapped());
+    assert_eq!(pe.sections().rva_to_offset(0x410), Some(0x210));
+}
@@ -0,0 +1,1324 @@
+//! Inspects a whole PE file through the public accessors only, without relying on `Display`,
+//! so that the crate stays usable as a library and not only as a pretty-printer.
+
+use peparser::annotations::FieldSpan;
+use peparser::debug::codeview::CodeView;
+use peparser::debug::debug_directory::DebugType;
+use peparser::errors::{ErrorKind, Truncation};
+use peparser::exports::export_directory_table::{ExportedFunction, TimestampConsistency};
+use peparser::headers::nt::{
+    Bitness, ChecksumStatus, DirectoryEntry, DirectoryPresence, Machine, RvaReference, Subsystem,
+};
+use peparser::headers::sections::Section;
+use peparser::headers::PEHeader;
+use peparser::imports::import_directory_table::ImportEntry;
+use peparser::imports::ImportDiff;
+use peparser::options::{ParseBudget, ParseContext, ParseOptions, StringMode};
+use peparser::reader::SeekingReader;
+use peparser::scanner::{ScanError, Scanner};
+use peparser::visitor::Visitor;
+use peparser::writer::PeWriter;
+use peparser::PE;
+
+const TEXT_RVA: u32 = 0x1000;
+const RDATA_RVA: u32 = 0x2000;
+
+fn put_u16(data: &mut [u8], offset: usize, value: u16) {
+    data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn put_str(data: &mut [u8], offset: usize, value: &str) {
+    data[offset..offset + value.len()].copy_from_slice(value.as_bytes());
+}
+
+/// A PE32 console DLL with an executable .text section and a read-only .rdata section
+/// holding the import directory of KERNEL32.dll and the export directory of sample.dll
+fn sample() -> Vec<u8> {
+    let mut pe_file = vec![0u8; 0x600];
+    // DOS header
+    put_str(&mut pe_file, 0, "MZ");
+    put_u32(&mut pe_file, 0x3c, 0x40);
+    // NT header
+    put_str(&mut pe_file, 0x40, "PE\0\0");
+    let file_header = 0x44;
+    put_u16(&mut pe_file, file_header, 0x14c);
+    put_u16(&mut pe_file, file_header + 2, 2);
+    put_u16(&mut pe_file, file_header + 16, 0xe0);
+    put_u16(&mut pe_file, file_header + 18, 0x2102);
+    let optional_header = file_header + 20;
+    put_u16(&mut pe_file, optional_header, 0x10b);
+    put_u32(&mut pe_file, optional_header + 16, TEXT_RVA);
+    put_u32(&mut pe_file, optional_header + 28, 0x10000000);
+    put_u32(&mut pe_file, optional_header + 32, 0x1000);
+    put_u32(&mut pe_file, optional_header + 36, 0x200);
+    put_u32(&mut pe_file, optional_header + 56, 0x3000);
+    put_u32(&mut pe_file, optional_header + 60, 0x200);
+    put_u16(&mut pe_file, optional_header + 68, 3);
+    put_u32(&mut pe_file, optional_header + 72, 0x100000);
+    put_u32(&mut pe_file, optional_header + 76, 0x1000);
+    put_u32(&mut pe_file, optional_header + 80, 0x100000);
+    put_u32(&mut pe_file, optional_header + 84, 0x1000);
+    put_u32(&mut pe_file, optional_header + 92, 16);
+    // export and import directories
+    put_u32(&mut pe_file, optional_header + 96, RDATA_RVA + 0x180);
+    put_u32(&mut pe_file, optional_header + 100, 0x60);
+    put_u32(&mut pe_file, optional_header + 104, RDATA_RVA);
+    put_u32(&mut pe_file, optional_header + 108, 0x28);
+    // section table
+    let section_table = optional_header + 0xe0;
+    for (index, (name, rva, raw, characteristics)) in [
+        (".text", TEXT_RVA, 0x200, 0x60000020),
+        (".rdata", RDATA_RVA, 0x400, 0x40000040),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let section = section_table + index * 40;
+        put_str(&mut pe_file, section, name);
+        put_u32(&mut pe_file, section + 8, 0x200);
+        put_u32(&mut pe_file, section + 12, rva);
+        put_u32(&mut pe_file, section + 16, 0x200);
+        put_u32(&mut pe_file, section + 20, raw);
+        put_u32(&mut pe_file, section + 36, characteristics);
+    }
+    // .rdata, at RDATA_RVA in memory and 0x400 in the file
+    let rdata = 0x400;
+    put_u32(&mut pe_file, rdata, RDATA_RVA + 0x100);
+    put_u32(&mut pe_file, rdata + 12, RDATA_RVA + 0x140);
+    put_u32(&mut pe_file, rdata + 16, RDATA_RVA + 0x120);
+    for thunks in [rdata + 0x100, rdata + 0x120] {
+        put_u32(&mut pe_file, thunks, RDATA_RVA + 0x160);
+        put_u32(&mut pe_file, thunks + 4, 0x80000010);
+    }
+    put_str(&mut pe_file, rdata + 0x140, "KERNEL32.dll");
+    put_u16(&mut pe_file, rdata + 0x160, 5);
+    put_str(&mut pe_file, rdata + 0x162, "ExitProcess");
+    let export = rdata + 0x180;
+    put_u32(&mut pe_file, export + 12, RDATA_RVA + 0x1d0);
+    put_u32(&mut pe_file, export + 16, 1);
+    put_u32(&mut pe_file, export + 20, 2);
+    put_u32(&mut pe_file, export + 24, 1);
+    put_u32(&mut pe_file, export + 28, RDATA_RVA + 0x1b0);
+    put_u32(&mut pe_file, export + 32, RDATA_RVA + 0x1b8);
+    put_u32(&mut pe_file, export + 36, RDATA_RVA + 0x1bc);
+    put_u32(&mut pe_file, rdata + 0x1b0, TEXT_RVA);
+    put_u32(&mut pe_file, rdata + 0x1b4, TEXT_RVA + 0x10);
+    put_u32(&mut pe_file, rdata + 0x1b8, RDATA_RVA + 0x1c0);
+    put_str(&mut pe_file, rdata + 0x1c0, "Run");
+    put_str(&mut pe_file, rdata + 0x1d0, "sample.dll");
+    pe_file
+}
+
+#[test]
+fn inspect_without_display() {
+    let pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+
+    assert_eq!(pe.machine(), Machine::I386);
+    assert_eq!(pe.bitness(), Bitness::Bits32);
+    assert_eq!(pe.subsystem(), Some(Subsystem::WindowsCui));
+    assert!(!pe.has_tls());
+    assert!(!pe.has_tls_callbacks());
+    assert!(!pe.summary().has_tls);
+    let memory_config = pe.memory_config();
+    assert_eq!(
+        (memory_config.stack_reserve, memory_config.stack_commit),
+        (0x100000, 0x1000)
+    );
+    assert_eq!(
+        (memory_config.heap_reserve, memory_config.heap_commit),
+        (0x100000, 0x1000)
+    );
+
+    let sections: Vec<(&str, bool, bool, bool)> = pe
+        .sections()
+        .iter()
+        .map(|section| {
+            (
+                section.name.as_str(),
+                section.is_readable(),
+                section.is_writable(),
+                section.is_executable(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        sections,
+        vec![(".text", true, false, true), (".rdata", true, false, false)]
+    );
+    assert!(pe.is_executable_rva(TEXT_RVA + 0x10));
+    assert!(!pe.is_executable_rva(RDATA_RVA));
+    assert!(!pe.is_executable_rva(0x10));
+    assert!(!pe.is_executable_rva(0x5000));
+
+    let descriptors = pe.import_descriptors();
+    assert_eq!(descriptors.len(), 1);
+    assert_eq!(descriptors[0].name(), Some("KERNEL32.dll"));
+    let functions = descriptors[0].functions();
+    assert_eq!(functions.len(), 2);
+    match &functions[0] {
+        ImportEntry::ByName(import_by_name) => {
+            assert_eq!(import_by_name.name(), "ExitProcess");
+            assert_eq!(import_by_name.hint(), Some(5));
+        }
+        other => panic!("unexpected import {:?}", other),
+    }
+    assert!(matches!(
+        functions[1],
+        ImportEntry::ByOrdinal { ordinal: 16, .. }
+    ));
+
+    let exports: Vec<(u32, Option<&str>, u32)> = pe
+        .exported_functions()
+        .iter()
+        .map(|function| (function.ordinal, function.name.as_deref(), function.rva))
+        .collect();
+    assert_eq!(
+        exports,
+        vec![(1, Some("Run"), TEXT_RVA), (2, None, TEXT_RVA + 0x10)]
+    );
+    assert_eq!(pe.export.as_ref().unwrap().dll_name, "sample.dll");
+    assert_eq!(pe.export_section().unwrap().name, ".rdata");
+}
+
+#[test]
+fn reparse_header_region() {
+    let pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    let header_region = pe.header_region();
+    assert_eq!(header_region.len(), 0x200);
+    assert_eq!(pe.header_permissions(), "r--");
+
+    let (_, header) = PEHeader::parse(header_region, &ParseContext::default()).unwrap();
+    assert_eq!(header.nt_header.file_header.machine, Machine::I386);
+    let names: Vec<&str> = header
+        .sections
+        .iter()
+        .map(|section| section.name.as_str())
+        .collect();
+    assert_eq!(names, vec![".text", ".rdata"]);
+}
+
+#[test]
+fn annotated_fields() {
+    let pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(pe.annotations.is_empty());
+
+    let options = ParseOptions {
+        annotations: true,
+        ..ParseOptions::default()
+    };
+    let (_, pe) = PE::parse_with_options(&pe_file, &options).unwrap();
+    let span = |name: &str| -> Vec<(usize, usize)> {
+        pe.annotations
+            .iter()
+            .filter(|span| span.name == name)
+            .map(|span| (span.offset, span.len))
+            .collect()
+    };
+    assert_eq!(span("magic"), vec![(0, 2)]);
+    assert_eq!(span("lfanew"), vec![(0x3c, 4)]);
+    assert_eq!(span("Signature"), vec![(0x40, 4)]);
+    assert_eq!(span("Machine"), vec![(0x44, 2)]);
+    assert_eq!(span("AddressOfEntryPoint"), vec![(0x58 + 16, 4)]);
+    assert_eq!(span("ImportTable"), vec![(0x58 + 104, 8)]);
+    assert_eq!(span("Name"), vec![(0x138, 8), (0x160, 8)]);
+    // the spans are in the order of the file and don't overlap
+    assert!(pe
+        .annotations
+        .windows(2)
+        .all(|pair: &[FieldSpan]| pair[0].offset + pair[0].len <= pair[1].offset));
+}
+
+#[test]
+fn dependency_edges() {
+    let pe_file = sample();
+    let (_, mut pe) = PE::parse(&pe_file).unwrap();
+    let edge = |from: &str, to: &str| (from.to_string(), to.to_string());
+    assert_eq!(
+        pe.dependency_edges("renamed.dll"),
+        vec![edge("sample.dll", "KERNEL32.dll")]
+    );
+
+    let export = pe.export.as_mut().unwrap();
+    export.dll_name.clear();
+    for function in &mut export.functions {
+        function.forwarder = Some("NTDLL.RtlAllocateHeap".to_string());
+    }
+    assert_eq!(
+        pe.dependency_edges("renamed.dll"),
+        vec![
+            edge("renamed.dll", "KERNEL32.dll"),
+            edge("renamed.dll", "NTDLL.dll")
+        ]
+    );
+}
+
+#[test]
+fn checksum_status() {
+    let pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.checksum_status(), ChecksumStatus::Absent);
+
+    let mut image = PeWriter::new(Machine::Amd64).code(vec![0xc3]).build();
+    let (_, pe) = PE::parse(&image).unwrap();
+    assert_eq!(pe.checksum_status(), ChecksumStatus::Valid);
+    // patching the code after linking
+    let code = pe.entry_point_section().unwrap().ptr_to_raw_data as usize;
+    image[code] = 0xcc;
+    let (_, pe) = PE::parse(&image).unwrap();
+    assert_eq!(pe.checksum_status(), ChecksumStatus::Invalid);
+}
+
+#[test]
+fn check_completeness() {
+    let mut pe_file = sample();
+    assert_eq!(PE::check_completeness(&pe_file), Ok(()));
+
+    let truncation = PE::check_completeness(&pe_file[..0x500]).unwrap_err();
+    assert_eq!(
+        truncation,
+        Truncation {
+            expected_len: 0x600,
+            actual_len: 0x500
+        }
+    );
+    assert!(truncation.to_string().contains("1536 bytes"));
+
+    // a certificate table declared past the end of the file, in what should be the overlay
+    let certificate_directory = 0x58 + 128;
+    put_u32(&mut pe_file, certificate_directory, 0x600);
+    put_u32(&mut pe_file, certificate_directory + 4, 0x20);
+    assert_eq!(
+        PE::check_completeness(&pe_file).map_err(|truncation| truncation.expected_len),
+        Err(0x620)
+    );
+    // the file range of a directory ends with the raw data of its section, even with a huge size
+    put_u32(&mut pe_file, certificate_directory + 4, 0);
+    put_u32(&mut pe_file, 0x58 + 100, u32::MAX);
+    assert_eq!(PE::check_completeness(&pe_file), Ok(()));
+
+    // headers cut within the section table, which ends at 0x188
+    assert_eq!(
+        PE::check_completeness(&pe_file[..0x100]),
+        Err(Truncation {
+            expected_len: 0x188,
+            actual_len: 0x100
+        })
+    );
+    assert_eq!(
+        PE::check_completeness(&pe_file[..0x20]).map_err(|truncation| truncation.expected_len),
+        Err(0x40)
+    );
+}
+
+#[test]
+fn section_raw_ranges() {
+    let mut pe_file = sample();
+    // an overlay is not part of any range
+    pe_file.extend_from_slice(&[0xcc; 0x10]);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(
+        pe.section_raw_ranges(),
+        vec![
+            (".text".to_string(), 0x200..0x400),
+            (".rdata".to_string(), 0x400..0x600)
+        ]
+    );
+    assert_eq!(pe.overlay().len(), 0x10);
+}
+
+#[test]
+fn symbolicate_rva() {
+    let pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.symbolicate_rva(TEXT_RVA).as_deref(), Some("Run"));
+    assert_eq!(pe.symbolicate_rva(TEXT_RVA + 0x10).as_deref(), Some("#2"));
+    // the import address table of KERNEL32.dll is at RDATA_RVA + 0x120
+    assert_eq!(
+        pe.symbolicate_rva(RDATA_RVA + 0x120).as_deref(),
+        Some("KERNEL32.dll!ExitProcess")
+    );
+    assert!(pe
+        .symbolicate_rva(RDATA_RVA + 0x124)
+        .is_some_and(|label| label.starts_with("KERNEL32.dll!")));
+    assert_eq!(pe.symbolicate_rva(RDATA_RVA + 0x122), None);
+    assert_eq!(pe.symbolicate_rva(TEXT_RVA + 4), None);
+}
+
+#[test]
+fn architecture_directory() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(pe.architecture_data().is_none());
+    assert!(!pe.has_nonstandard_architecture_directory());
+
+    // the 8th data directory, reserved on x86
+    let architecture = 0x58 + 96 + 7 * 8;
+    put_u32(&mut pe_file, architecture, TEXT_RVA);
+    put_u32(&mut pe_file, architecture + 4, 0x10);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.architecture_data().map(|data| data.len()), Some(0x10));
+    assert!(pe.has_nonstandard_architecture_directory());
+}
+
+#[test]
+fn memory_map() {
+    let pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    let memory_map = pe.memory_map();
+    let regions: Vec<(&str, u32, u32, u32, &str)> = memory_map
+        .iter()
+        .map(|region| {
+            (
+                region.name.as_str(),
+                region.rva,
+                region.virtual_size,
+                region.raw_offset,
+                region.permissions,
+            )
+        })
+        .collect();
+    // each region is rounded up to the 0x1000 section alignment, so there's no gap
+    assert_eq!(
+        regions,
+        vec![
+            ("headers", 0, 0x1000, 0, "r--"),
+            (".text", TEXT_RVA, 0x1000, 0x200, "r-x"),
+            (".rdata", RDATA_RVA, 0x1000, 0x400, "r--"),
+        ]
+    );
+}
+
+#[test]
+fn trailing_data() {
+    let pe_file = sample();
+    let (remaining, pe) = PE::parse(&pe_file).unwrap();
+    // the section table ends at 0x188, so the trailing data includes the section data
+    assert_eq!(pe.trailing_data(), remaining);
+    assert_eq!(pe.trailing_data().len(), 0x600 - 0x188);
+    assert!(pe.overlay().is_empty());
+
+    let pe = PE::try_from(&pe_file[..]).unwrap();
+    assert_eq!(pe.trailing_data(), remaining);
+}
+
+#[test]
+fn out_of_range_ordinals() {
+    let pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    // checked against the exports of the sample itself, whose ordinals are 1 and 2
+    let exports = pe.export.as_ref().unwrap();
+    assert_eq!(exports.ordinal_range(), Some((1, 2)));
+    assert_eq!(
+        pe.import_descriptors()[0].out_of_range_ordinals(exports),
+        vec![16]
+    );
+}
+
+#[test]
+fn fixed_base() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.preferred_base(), 0x10000000);
+    // no base relocation directory and no DYNAMIC_BASE
+    assert!(pe.prefers_fixed_base());
+
+    put_u16(&mut pe_file, 0x58 + 70, 0x40);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(!pe.prefers_fixed_base());
+}
+
+#[test]
+fn decoy_pe_signature() {
+    let mut pe_file = sample();
+    put_str(&mut pe_file, 0x5f0, "PE\0\0");
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(PE::find_all_pe_signatures(&pe_file), vec![0x40, 0x5f0]);
+    assert_eq!(pe.nt_header_offset(), 0x40);
+}
+
+#[test]
+fn header_to_first_section_gap() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.header_to_first_section_gap(), 0);
+
+    // size_of_headers claims the .text raw data
+    put_u32(&mut pe_file, 0x58 + 60, 0x400);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.header_to_first_section_gap(), -0x200);
+}
+
+#[test]
+fn duplicate_import_descriptors() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(pe.imports.as_ref().unwrap().duplicate_dlls().is_empty());
+
+    // a second descriptor for KERNEL32.dll sharing the thunks of the first one
+    pe_file.copy_within(0x400..0x414, 0x414);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    let imports = pe.imports.as_ref().unwrap();
+    assert_eq!(pe.import_descriptors().len(), 2);
+    assert_eq!(imports.duplicate_dlls(), vec!["kernel32.dll"]);
+    assert_eq!(imports.as_map()["kernel32.dll"].len(), 4);
+}
+
+#[test]
+fn loader_flags() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(!pe.header.nt_header.optional_header.loader_flags_nonzero());
+
+    put_u32(&mut pe_file, 0x58 + 88, 0x1);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(pe.header.nt_header.optional_header.loader_flags_nonzero());
+}
+
+#[test]
+fn ordinal_only_exports() {
+    let pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    let exports = pe.export.as_ref().unwrap();
+    let ordinals: Vec<u32> = exports
+        .ordinal_only_exports(pe.file, pe.sections())
+        .iter()
+        .map(|function| function.ordinal)
+        .collect();
+    assert_eq!(ordinals, vec![2]);
+}
+
+#[test]
+fn total_entries_budget() {
+    let pe_file = sample();
+    // every table of the sample has at most 2 entries, but they add up beyond 5
+    let options = |max_total_entries| ParseOptions {
+        budget: ParseBudget {
+            max_entries_per_table: 3,
+            max_total_entries,
+        },
+        ..ParseOptions::default()
+    };
+    assert!(PE::parse_with_options(&pe_file, &options(usize::MAX)).is_ok());
+    let messages = match PE::parse_with_options(&pe_file, &options(5)) {
+        Err(nom::Err::Error(err)) | Err(nom::Err::Failure(err)) => err
+            .errors
+            .iter()
+            .map(|(_, kind)| kind.to_string())
+            .collect::<Vec<String>>(),
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    };
+    assert!(messages
+        .iter()
+        .any(|message| message.contains("more than 5 entries in total")));
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn parse_metrics() {
+    let pe_file = sample();
+    let started = std::time::Instant::now();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    let elapsed = started.elapsed();
+    let metrics = pe.metrics;
+    // a part can take no measurable time on a coarse clock, so only the bounds are checked:
+    // the recorded parts add up to the total, which is within the time of the whole parse
+    assert!(metrics.headers + metrics.imports + metrics.exports <= metrics.total());
+    assert!(metrics.total() <= elapsed);
+}
+
+#[derive(Default)]
+struct CountingVisitor {
+    sections: usize,
+    import_dlls: Vec<String>,
+    imports: usize,
+    exports: usize,
+}
+
+impl Visitor for CountingVisitor {
+    fn on_section(&mut self, _section: &Section) {
+        self.sections += 1;
+    }
+
+    fn on_import_dll(&mut self, dll_name: &str) {
+        self.import_dlls.push(dll_name.to_string());
+    }
+
+    fn on_import(&mut self, _dll_name: &str, _entry: &ImportEntry) {
+        self.imports += 1;
+    }
+
+    fn on_export(&mut self, _function: &ExportedFunction) {
+        self.exports += 1;
+    }
+}
+
+#[test]
+fn parse_with_visitor() {
+    let mut pe_file = sample();
+    let mut visitor = CountingVisitor::default();
+    assert!(PE::parse_with_visitor(&pe_file, &ParseOptions::default(), &mut visitor).is_ok());
+    assert_eq!(visitor.sections, 2);
+    assert_eq!(visitor.import_dlls, vec!["KERNEL32.dll"]);
+    assert_eq!(visitor.imports, 2);
+    assert_eq!(visitor.exports, 2);
+
+    // the hooks are called as the parts are parsed, so the parts before an error are still visited
+    pe_file[0x400 + 0x1c0] = 0xff;
+    let options = ParseOptions {
+        string_mode: StringMode::Strict,
+        ..ParseOptions::default()
+    };
+    let mut visitor = CountingVisitor::default();
+    assert!(PE::parse_with_visitor(&pe_file, &options, &mut visitor).is_err());
+    assert_eq!(visitor.sections, 2);
+    assert_eq!(visitor.import_dlls, vec!["KERNEL32.dll"]);
+    assert_eq!(visitor.imports, 2);
+    assert_eq!(visitor.exports, 0);
+}
+
+#[test]
+fn seeking_reader() {
+    let mut pe_file = sample();
+    // a resource tree RCDATA > 1 > 0x409 in .rdata, whose data is in .text
+    let resources = 0x400 + 0xa0;
+    put_u32(&mut pe_file, 0x58 + 112, RDATA_RVA + 0xa0);
+    put_u32(&mut pe_file, 0x58 + 116, 0x58);
+    for (table, id, offset) in [
+        (0, 10, 0x80000018),
+        (0x18, 1, 0x80000030),
+        (0x30, 0x409, 0x48),
+    ] {
+        put_u16(&mut pe_file, resources + table + 14, 1);
+        put_u32(&mut pe_file, resources + table + 16, id);
+        put_u32(&mut pe_file, resources + table + 20, offset);
+    }
+    put_u32(&mut pe_file, resources + 0x48, TEXT_RVA);
+    put_u32(&mut pe_file, resources + 0x4c, 4);
+    put_str(&mut pe_file, 0x200, "data");
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+
+    let mut reader =
+        SeekingReader::new(std::io::Cursor::new(&pe_file), ParseOptions::default()).unwrap();
+    assert_eq!(reader.header().unwrap().to_string(), pe.header.to_string());
+    assert_eq!(
+        reader
+            .imports()
+            .unwrap()
+            .unwrap()
+            .directory_table
+            .to_string(),
+        pe.imports.as_ref().unwrap().directory_table.to_string()
+    );
+    assert_eq!(
+        reader.export().unwrap().unwrap().to_string(),
+        pe.export.as_ref().unwrap().to_string()
+    );
+    let resources = reader.resources().unwrap().unwrap();
+    assert_eq!(
+        resources.to_string(),
+        pe.resources.as_ref().unwrap().to_string()
+    );
+    let leaves = pe
+        .resources
+        .as_ref()
+        .unwrap()
+        .iter_leaves(pe.file, &pe.header.sections)
+        .collect::<Vec<_>>();
+    assert_eq!(leaves.len(), 1);
+    assert_eq!(leaves[0].data, b"data");
+    assert_eq!(
+        reader
+            .read_rva(leaves[0].data_rva, leaves[0].data.len())
+            .unwrap(),
+        leaves[0].data
+    );
+    // the read stops at the end of the file
+    assert_eq!(reader.read_rva(RDATA_RVA + 0x1fe, 4).unwrap(), vec![0, 0]);
+    assert!(reader.read_rva(0x5000, 4).unwrap().is_empty());
+
+    // a crafted lfanew is checked against the length of the source before reading the headers
+    put_u32(&mut pe_file, 0x3c, 0xfffffff0);
+    assert!(matches!(
+        SeekingReader::new(std::io::Cursor::new(&pe_file), ParseOptions::default()),
+        Err(ScanError::Parse(_))
+    ));
+}
+
+#[test]
+fn scan_path() {
+    let dir = std::env::temp_dir().join(format!("peparser-scan-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let image = dir.join("image.exe");
+    std::fs::write(
+        &image,
+        PeWriter::new(Machine::Amd64).code(vec![0xc3]).build(),
+    )
+    .unwrap();
+    let text = dir.join("notes.txt");
+    std::fs::write(&text, "not a PE file").unwrap();
+
+    let mut scanner = Scanner::new();
+    let summary = scanner.scan_path(&image).unwrap();
+    assert_eq!(summary.machine, Machine::Amd64);
+    assert!(summary.is_64bit);
+    assert!(!summary.is_dll);
+    assert!(matches!(scanner.scan_path(&text), Err(ScanError::Parse(_))));
+    match scanner.scan_path(dir.join("missing.exe")) {
+        Err(ScanError::Io(err)) => assert_eq!(err.kind(), std::io::ErrorKind::NotFound),
+        other => panic!("unexpected result {:?}", other),
+    }
+    // the scanner is still usable after the errors
+    assert!(scanner.scan_path(&image).is_ok());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn debug_directory() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(pe.debug.is_none());
+    assert!(!pe.supports_cet());
+    assert!(!pe.cet_compat_strict_mode());
+
+    // a CODEVIEW entry and an EX_DLLCHARACTERISTICS entry in .rdata, each followed by its data
+    let debug = 0x400 + 0x30;
+    put_u32(&mut pe_file, 0x58 + 144, RDATA_RVA + 0x30);
+    put_u32(&mut pe_file, 0x58 + 148, 2 * 28);
+    for (index, (typ, size, data)) in [(2, 32, 0x68), (20, 4, 0x88)].into_iter().enumerate() {
+        let entry = debug + index * 28;
+        put_u32(&mut pe_file, entry + 12, typ);
+        put_u32(&mut pe_file, entry + 16, size);
+        put_u32(&mut pe_file, entry + 20, RDATA_RVA + data);
+        put_u32(&mut pe_file, entry + 24, 0x400 + data);
+    }
+    let codeview = 0x400 + 0x68;
+    put_str(&mut pe_file, codeview, "RSDS");
+    for byte in 0..16 {
+        pe_file[codeview + 4 + byte] = byte as u8;
+    }
+    put_u32(&mut pe_file, codeview + 20, 1);
+    put_str(&mut pe_file, codeview + 24, "app.pdb");
+    put_u32(&mut pe_file, 0x400 + 0x88, 0x1);
+
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    let debug = pe.debug.as_ref().unwrap();
+    let types: Vec<Option<DebugType>> = debug
+        .entries
+        .iter()
+        .map(|entry| entry.debug_type())
+        .collect();
+    assert_eq!(
+        types,
+        vec![
+            Some(DebugType::CodeView),
+            Some(DebugType::ExDllCharacteristics)
+        ]
+    );
+    match &debug.codeview {
+        Some(CodeView::Pdb70 {
+            guid,
+            age,
+            pdb_file_name,
+        }) => {
+            assert_eq!(guid[..4], [0, 1, 2, 3]);
+            assert_eq!(*age, 1);
+            assert_eq!(pdb_file_name, "app.pdb");
+        }
+        other => panic!("unexpected CodeView {:?}", other),
+    }
+    assert_eq!(
+        pe.pdb_symbol_path().as_deref(),
+        Some("app.pdb/030201000504070608090A0B0C0D0E0F1/app.pdb")
+    );
+    assert_eq!(debug.ex_dll_characteristics, Some(0x1));
+    assert!(pe.supports_cet());
+    assert!(!pe.cet_compat_strict_mode());
+
+    put_u32(&mut pe_file, 0x400 + 0x88, 0x3);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(pe.supports_cet());
+    assert!(pe.cet_compat_strict_mode());
+}
+
+#[test]
+fn lfanew_beyond_the_file() {
+    let mut pe_file = sample();
+    put_u32(&mut pe_file, 0x3c, 0x10000);
+    let err = match PE::parse(&pe_file) {
+        Err(nom::Err::Failure(err)) => err,
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    };
+    assert!(matches!(
+        err.errors[0].1,
+        ErrorKind::OffsetOutOfBounds {
+            offset: 0x10000,
+            size: 0x600
+        }
+    ));
+    assert!(matches!(err.errors[1].1, ErrorKind::Context("lfanew")));
+    // at the end of the file, the DOS stub fits but the NT headers don't
+    put_u32(&mut pe_file, 0x3c, 0x600);
+    assert!(PE::parse(&pe_file).is_err());
+}
+
+#[test]
+fn raw_data_beyond_u32() {
+    let mut pe_file = sample();
+    // the offsets of the directories in .rdata overflow u32
+    put_u32(&mut pe_file, 0x138 + 40 + 20, u32::MAX - 0x100);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(pe.import_descriptors().is_empty());
+    assert!(pe.export.is_none());
+    assert_eq!(
+        pe.header.sections.rva_to_offset(RDATA_RVA + 0x100),
+        Some(u32::MAX)
+    );
+    assert_eq!(pe.header.sections.rva_to_offset(RDATA_RVA + 0x101), None);
+}
+
+#[test]
+fn tables_at_the_end_of_the_file() {
+    let mut pe_file = sample();
+    // an import lookup table without terminator whose hint/name entry is cut by the end of the file
+    put_u32(&mut pe_file, 0x400, RDATA_RVA + 0x1f8);
+    put_u32(&mut pe_file, 0x5f8, 0x80000010);
+    put_u32(&mut pe_file, 0x5fc, RDATA_RVA + 0x1ff);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    let descriptors = pe.import_descriptors();
+    assert_eq!(descriptors.len(), 1);
+    let functions = descriptors[0].functions();
+    assert_eq!(functions.len(), 1);
+    assert!(matches!(
+        functions[0],
+        ImportEntry::ByOrdinal { ordinal: 16, .. }
+    ));
+}
+
+#[test]
+fn parse_headers_then_directories() {
+    let mut pe_file = sample();
+    // a DLL name which is not valid UTF-8 fails the import directory in strict mode
+    pe_file[0x400 + 0x140] = 0xff;
+    let options = ParseOptions {
+        string_mode: StringMode::Strict,
+        ..ParseOptions::default()
+    };
+    assert!(PE::parse_with_options(&pe_file, &options).is_err());
+
+    let partial = PE::parse_headers_then_directories(&pe_file, &options).unwrap();
+    assert_eq!(partial.header.sections.len(), 2);
+    assert_eq!(
+        partial.header.nt_header.optional_header.image_base(),
+        0x10000000
+    );
+    assert!(partial.imports.is_err());
+    let export = partial.export.unwrap().unwrap();
+    assert_eq!(export.dll_name, "sample.dll");
+    assert_eq!(export.functions.len(), 2);
+    assert!(matches!(partial.resources, Ok(None)));
+    assert!(matches!(partial.delay_imports, Ok(None)));
+}
+
+#[test]
+fn resolved_exports() {
+    let pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    let sections = &pe.header.sections;
+    assert_eq!(sections.find_by_name(".rdata").unwrap().vir_addr, RDATA_RVA);
+    assert!(sections.find_by_name(".data").is_none());
+    assert_eq!(
+        sections.find_by_address(TEXT_RVA + 0x10).unwrap().name,
+        ".text"
+    );
+    assert!(sections.find_by_address(0x3000).is_none());
+    assert_eq!(sections.rva_to_offset(RDATA_RVA + 0x180), Some(0x580));
+    assert_eq!(sections.rva_to_offset(0x3000), None);
+
+    let export = pe.export.as_ref().unwrap().to_string();
+    assert!(export.contains("ordinal: 1, name: Run, rva: 0x1000"));
+    assert!(export.contains("ordinal: 2, name: -, rva: 0x1010"));
+}
+
+#[test]
+fn sections_beyond_size_of_headers() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    let nt_header = &pe.header.nt_header;
+    assert_eq!(nt_header.section_table_offset(), 0x138);
+    // (0x200 - 0x138) / 40
+    assert_eq!(nt_header.max_num_of_sections(), 5);
+
+    put_u16(&mut pe_file, 0x44 + 2, 6);
+    let messages = match PE::parse(&pe_file) {
+        Err(nom::Err::Error(err)) | Err(nom::Err::Failure(err)) => err
+            .errors
+            .iter()
+            .map(|(_, kind)| kind.to_string())
+            .collect::<Vec<String>>(),
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    };
+    assert!(messages
+        .iter()
+        .any(|message| message
+            .contains("number of sections 6 does not fit in the headers (at most 5)")));
+}
+
+#[test]
+fn smaller_than_dos_header() {
+    let pe_file = sample();
+    for len in [0, 2, 63] {
+        let messages = match PE::parse(&pe_file[..len]) {
+            Err(nom::Err::Error(err)) | Err(nom::Err::Failure(err)) => err
+                .errors
+                .iter()
+                .map(|(_, kind)| kind.to_string())
+                .collect::<Vec<String>>(),
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        };
+        assert_eq!(
+            messages,
+            vec![format!(
+                "file too small: {} bytes is less than the DOS header size of 64 bytes",
+                len
+            )]
+        );
+    }
+}
+
+#[test]
+fn directory_presence() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    let expected = DirectoryPresence {
+        export: true,
+        import: true,
+        ..DirectoryPresence::default()
+    };
+    assert_eq!(pe.directory_presence(), expected);
+
+    // a directory needs both an RVA and a size
+    put_u32(&mut pe_file, 0x58 + 144, RDATA_RVA + 0x30);
+    put_u32(&mut pe_file, 0x58 + 204, 0x100);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.directory_presence(), expected);
+    let data_directories = pe.header.nt_header.optional_header.data_directories();
+    assert_eq!(data_directories.presence(), expected);
+}
+
+#[test]
+fn declared_size() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    // the raw data of .rdata ends at 0x600
+    assert_eq!(pe.declared_size(), 0x600);
+    assert!(!pe.is_truncated());
+    assert_eq!(pe.overlay_size(), 0);
+
+    let (_, pe) = PE::parse(&pe_file[..0x500]).unwrap();
+    assert_eq!(pe.declared_size(), 0x600);
+    assert!(pe.is_truncated());
+    assert_eq!(pe.overlay_size(), 0);
+
+    pe_file.extend_from_slice(b"payload");
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(!pe.is_truncated());
+    assert_eq!(pe.overlay_size(), 7);
+    assert_eq!(pe.overlay(), b"payload");
+}
+
+/// The sample importing from WS2_32.dll the function `name` by name and then `ordinal`
+fn ws2_32_sample(name: &str, ordinal: u32) -> Vec<u8> {
+    let mut pe_file = sample();
+    pe_file[0x540..0x580].fill(0);
+    put_str(&mut pe_file, 0x540, "WS2_32.dll");
+    put_str(&mut pe_file, 0x562, name);
+    put_u32(&mut pe_file, 0x504, 0x80000000 | ordinal);
+    pe_file
+}
+
+fn import_diff_between(old: &[u8], new: &[u8], options: &ParseOptions) -> ImportDiff {
+    let (_, old) = PE::parse_with_options(old, options).unwrap();
+    let (_, new) = PE::parse_with_options(new, options).unwrap();
+    old.imports.unwrap().diff(new.imports.as_ref().unwrap())
+}
+
+#[cfg(feature = "ordinal-names")]
+#[test]
+fn import_diff() {
+    let options = ParseOptions::default();
+    // ordinal 16 is recv
+    let old = ws2_32_sample("socket", 16);
+    assert!(import_diff_between(&old, &ws2_32_sample("socket", 16), &options).is_empty());
+    let changed = import_diff_between(&old, &ws2_32_sample("connect", 1000), &options);
+    assert_eq!(
+        changed.added_functions["ws2_32.dll"],
+        vec!["#1000", "connect"]
+    );
+    assert_eq!(
+        changed.removed_functions["ws2_32.dll"],
+        vec!["recv", "socket"]
+    );
+
+    // socket imported by its ordinal 23 instead of by name
+    let mut by_ordinal = old.clone();
+    put_u32(&mut by_ordinal, 0x500, 0x80000000 | 23);
+    assert!(import_diff_between(&old, &by_ordinal, &options).is_empty());
+}
+
+#[test]
+fn unresolved_import_diff() {
+    // without the names, the imports by name are left out rather than compared by RVA
+    let options = ParseOptions {
+        resolve_import_names: false,
+        ..ParseOptions::default()
+    };
+    let old = ws2_32_sample("socket", 16);
+    let mut moved = old.clone();
+    put_u32(&mut moved, 0x500, RDATA_RVA + 0x170);
+    assert!(import_diff_between(&old, &moved, &options).is_empty());
+    let changed = import_diff_between(&old, &ws2_32_sample("socket", 17), &options);
+    assert_eq!(changed.removed_functions["ws2_32.dll"].len(), 1);
+    assert_eq!(changed.added_functions["ws2_32.dll"].len(), 1);
+}
+
+#[test]
+fn unmapped_directory() {
+    let mut pe_file = sample();
+    // the import directory beyond the sections, which end at 0x2200 in memory
+    put_u32(&mut pe_file, 0x58 + 104, 0x5000);
+    let err = match PE::parse(&pe_file) {
+        Err(nom::Err::Failure(err)) => err,
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    };
+    assert!(matches!(
+        err.errors[0].1,
+        ErrorKind::RvaOutOfBounds {
+            rva: 0x5000,
+            size: 0x2200
+        }
+    ));
+    assert!(matches!(
+        err.errors[1].1,
+        ErrorKind::Context("import directory")
+    ));
+    let partial = PE::parse_headers_then_directories(&pe_file, &ParseOptions::default()).unwrap();
+    assert!(partial.imports.is_err());
+    assert!(partial.export.unwrap().is_some());
+}
+
+#[test]
+fn sections_covering_directory() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    let names = |sections: Vec<&Section>| {
+        sections
+            .iter()
+            .map(|section| section.name.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names(pe.sections_covering_directory(DirectoryEntry::Import)),
+        vec![".rdata"]
+    );
+    assert!(pe
+        .sections_covering_directory(DirectoryEntry::Resource)
+        .is_empty());
+
+    // a VirtualSize of 0 maps SizeOfRawData bytes
+    put_u32(&mut pe_file, 0x138 + 40 + 8, 0);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(
+        names(pe.sections_covering_directory(DirectoryEntry::Import)),
+        vec![".rdata"]
+    );
+}
+
+#[test]
+fn orphan_directories() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(pe.orphan_directories().is_empty());
+
+    // bound imports of KERNEL32.dll in the headers, after the section table
+    let bound_imports = 0x188;
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 11, bound_imports as u32);
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 11 + 4, 0x20);
+    put_u32(&mut pe_file, bound_imports, 0x4a5bc60f);
+    put_u16(&mut pe_file, bound_imports + 4, 0x10);
+    put_str(&mut pe_file, bound_imports + 0x10, "KERNEL32.dll");
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(
+        pe.bound_imports().unwrap().descriptors()[0].module_name,
+        "KERNEL32.dll"
+    );
+    assert!(pe.orphan_directories().is_empty());
+
+    // the load config directory beyond the sections
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 10, 0x5000);
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 10 + 4, 0x40);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.orphan_directories(), vec![DirectoryEntry::LoadConfig]);
+}
+
+#[test]
+fn likely_oep_hints() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.likely_oep_hints(), vec![TEXT_RVA]);
+
+    // the entry point in an executable .rdata, and TLS callbacks in .text
+    put_u32(&mut pe_file, 0x58 + 16, RDATA_RVA + 0x10);
+    put_u32(&mut pe_file, 0x138 + 40 + 36, 0x60000020);
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 9, RDATA_RVA + 0x30);
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 9 + 4, 24);
+    put_u32(
+        &mut pe_file,
+        0x400 + 0x30 + 12,
+        0x10000000 + RDATA_RVA + 0x50,
+    );
+    put_u32(&mut pe_file, 0x400 + 0x50, 0x10000000 + TEXT_RVA + 0x100);
+    put_u32(&mut pe_file, 0x400 + 0x54, 0x10000000 + TEXT_RVA);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.tls.as_ref().unwrap().callbacks.len(), 2);
+    // the callbacks first, then the entry point; the start of .text is already listed as a callback
+    // and the start of .rdata is skipped as the section of the entry point
+    assert_eq!(
+        pe.likely_oep_hints(),
+        vec![TEXT_RVA + 0x100, TEXT_RVA, RDATA_RVA + 0x10]
+    );
+}
+
+#[test]
+fn referenced_rvas() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(
+        pe.referenced_rvas(),
+        vec![
+            (
+                RvaReference::Directory(DirectoryEntry::Export),
+                RDATA_RVA + 0x180,
+                0x60
+            ),
+            (
+                RvaReference::Directory(DirectoryEntry::Import),
+                RDATA_RVA,
+                0x28
+            ),
+            (RvaReference::EntryPoint, TEXT_RVA, 0),
+        ]
+    );
+
+    // the certificate directory is a file offset, and an entry point of 0 is absent
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 4, 0x580);
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 4 + 4, 0x80);
+    put_u32(&mut pe_file, 0x58 + 16, 0);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(
+        pe.referenced_rvas()
+            .iter()
+            .map(|(reference, _, _)| *reference)
+            .collect::<Vec<_>>(),
+        vec![
+            RvaReference::Directory(DirectoryEntry::Export),
+            RvaReference::Directory(DirectoryEntry::Import)
+        ]
+    );
+}
+
+#[test]
+fn va_to_rva() {
+    let pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.va_to_rva(0x10000000 + TEXT_RVA as u64), Some(TEXT_RVA));
+    assert_eq!(pe.va_to_rva(0x10000000), Some(0));
+    // below the image base, at size_of_image, and beyond u32 once the image base is subtracted
+    assert_eq!(pe.va_to_rva(0x0fffffff), None);
+    assert_eq!(pe.va_to_rva(0x10003000), None);
+    assert_eq!(pe.va_to_rva(0x10000000 + (1 << 32) + TEXT_RVA as u64), None);
+
+    // a PE32+ image based above 4 GiB
+    let pe_file = PeWriter::new(Machine::Amd64)
+        .image_base(0x180000000)
+        .code(vec![0xc3])
+        .build();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.bitness(), Bitness::Bits64);
+    let text = pe.sections().iter().next().unwrap().vir_addr;
+    assert_eq!(pe.va_to_rva(0x180000000 + text as u64), Some(text));
+    assert_eq!(pe.va_to_rva(text as u64), None);
+    assert_eq!(pe.va_to_rva(0x180000000 + (1 << 32) + text as u64), None);
+}
+
+#[test]
+fn identity_line() {
+    let pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(
+        pe.identity_line(),
+        "PE32 DLL (console) Intel 80386, 2 sections"
+    );
+
+    let pe_file = PeWriter::new(Machine::Amd64)
+        .subsystem(Subsystem::WindowsGui)
+        .code(vec![0xc3])
+        .build();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(
+        pe.identity_line(),
+        "PE32+ executable (GUI) x86-64, 1 section"
+    );
+}
+
+#[test]
+fn header_slack() {
+    let mut pe_file = sample();
+    // between the end of the section table and the raw data of .text
+    put_str(&mut pe_file, 0x190, "hidden");
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    let slack = pe.header_slack();
+    assert_eq!(slack.len(), 0x200 - 0x188);
+    assert_eq!(&slack[8..14], b"hidden");
+
+    // the slack still runs up to the raw data when SizeOfHeaders ends with the section table
+    put_u32(&mut pe_file, 0x58 + 60, 0x188);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.header_slack().len(), 0x200 - 0x188);
+
+    // without section data it runs up to SizeOfHeaders
+    for section in [0x138, 0x138 + 40] {
+        put_u32(&mut pe_file, section + 16, 0);
+    }
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(pe.header_slack().is_empty());
+    put_u32(&mut pe_file, 0x58 + 60, 0x198);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.header_slack(), b"\0\0\0\0\0\0\0\0hidden\0\0");
+}
+
+#[test]
+fn actual_headers_size() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    // the section table ends at 0x188, rounded up to the file alignment of 0x200
+    assert_eq!(pe.actual_headers_size(), 0x200);
+    assert!(pe.headers_size_matches());
+
+    // SizeOfHeaders ending with the section table, short of the aligned end of the headers
+    put_u32(&mut pe_file, 0x58 + 60, 0x188);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.actual_headers_size(), 0x200);
+    assert!(!pe.headers_size_matches());
+}
+
+#[test]
+fn timestamp_consistency() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(
+        pe.timestamp_consistency(),
+        TimestampConsistency::ExportZeroed
+    );
+
+    put_u32(&mut pe_file, 0x44 + 4, 0x5f000000);
+    put_u32(&mut pe_file, 0x580 + 4, 0x5f000000);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.timestamp_consistency(), TimestampConsistency::Consistent);
+
+    // the file header timestamp was modified after linking
+    put_u32(&mut pe_file, 0x44 + 4, 0x60000000);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.timestamp_consistency(), TimestampConsistency::Mismatch);
+
+    put_u32(&mut pe_file, 0x58 + 96, 0);
+    put_u32(&mut pe_file, 0x58 + 100, 0);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.timestamp_consistency(), TimestampConsistency::NoExports);
+}
+
+#[test]
+fn relocations_stripped() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    // no base relocation directory
+    assert!(pe.relocations_stripped());
+
+    // an empty block of relocations for .text
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 5, RDATA_RVA + 0x30);
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 5 + 4, 8);
+    put_u32(&mut pe_file, 0x400 + 0x30, TEXT_RVA);
+    put_u32(&mut pe_file, 0x400 + 0x34, 8);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(pe.relocations.is_some());
+    assert!(!pe.relocations_stripped());
+
+    // IMAGE_FILE_RELOCS_STRIPPED wins over the directory
+    put_u16(&mut pe_file, 0x44 + 18, 0x2103);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(pe.relocations_stripped());
+}
+
+#[test]
+fn is_driver() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(!pe.is_driver());
+
+    // a native image importing only from a user-mode DLL, like smss.exe
+    put_u16(&mut pe_file, 0x58 + 68, 1);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(!pe.is_driver());
+
+    put_str(&mut pe_file, 0x540, "NTOSKRNL.EXE");
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(pe.is_driver());
+    assert!(pe.identity_line().starts_with("PE32 driver (native)"));
+
+    // the kernel imports alone don't make a driver
+    put_u16(&mut pe_file, 0x58 + 68, 3);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert!(!pe.is_driver());
+}
+
+#[test]
+fn global_pointer() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.global_pointer(), None);
+
+    // the size of the directory is always zero
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 8, RDATA_RVA + 0x100);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.global_pointer(), Some(RDATA_RVA + 0x100));
+}
+
+#[test]
+fn iat_entries() {
+    let mut pe_file = sample();
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.iat_range(), None);
+    assert!(pe.iat_entries().is_empty());
+
+    // the IAT of KERNEL32.dll: ExitProcess by name, ordinal 16 and the terminator
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 12, RDATA_RVA + 0x120);
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 12 + 4, 12);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.iat_range(), Some((RDATA_RVA + 0x120, 12)));
+    assert_eq!(
+        pe.iat_entries(),
+        vec![RDATA_RVA as u64 + 0x160, 0x80000010, 0]
+    );
+
+    // an IAT beyond the sections has no entries to read
+    put_u32(&mut pe_file, 0x58 + 96 + 8 * 12, 0x5000);
+    let (_, pe) = PE::parse(&pe_file).unwrap();
+    assert_eq!(pe.iat_range(), Some((0x5000, 12)));
+    assert!(pe.iat_entries().is_empty());
+}